@@ -0,0 +1,237 @@
+use crate::ring::{hash_pubkey, PreparedRing};
+use crate::signature::{Error, Signature};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+// A single signature to be verified as part of a batch,
+// along with the ring and message it was produced over
+pub struct BatchItem<'a> {
+    pub signature: &'a Signature,
+    pub public_keys: &'a [Vec<CompressedRistretto>],
+    pub msg: &'a [u8],
+}
+
+// KeyCache stores the work which only depends on the public keys in a ring.
+// Rings which are verified together will often share decoys, so each distinct
+// key is decompressed once, and each distinct signing key is hashed to a point once.
+#[derive(Default)]
+pub struct KeyCache {
+    // Maps the compressed key to its decompressed form.
+    // A `None` value records that the key is not a valid point
+    decompressed: HashMap<[u8; 32], Option<RistrettoPoint>>,
+
+    // Maps the first public key of a member to Hash_to_point(key)
+    hashed_pubkeys: HashMap<[u8; 32], RistrettoPoint>,
+}
+
+impl KeyCache {
+    pub fn new() -> Self {
+        KeyCache::default()
+    }
+    // Decompresses and hashes every key in the rings which has not been seen before
+    pub fn insert_rings<'a, I>(&mut self, rings: I)
+    where
+        I: IntoIterator<Item = &'a [Vec<CompressedRistretto>]>,
+    {
+        let mut new_keys: HashSet<[u8; 32]> = HashSet::new();
+        let mut new_signing_keys: HashSet<[u8; 32]> = HashSet::new();
+
+        for ring in rings {
+            for pub_keys in ring {
+                if let Some(first_pubkey) = pub_keys.first() {
+                    if !self.hashed_pubkeys.contains_key(first_pubkey.as_bytes()) {
+                        new_signing_keys.insert(first_pubkey.to_bytes());
+                    }
+                }
+                for pub_key in pub_keys {
+                    if !self.decompressed.contains_key(pub_key.as_bytes()) {
+                        new_keys.insert(pub_key.to_bytes());
+                    }
+                }
+            }
+        }
+
+        let decompressed: Vec<([u8; 32], Option<RistrettoPoint>)> = new_keys
+            .into_par_iter()
+            .map(|bytes| (bytes, CompressedRistretto(bytes).decompress()))
+            .collect();
+        self.decompressed.extend(decompressed);
+
+        let hashed: Vec<([u8; 32], RistrettoPoint)> = new_signing_keys
+            .into_par_iter()
+            .map(|bytes| (bytes, hash_pubkey(&CompressedRistretto(bytes))))
+            .collect();
+        self.hashed_pubkeys.extend(hashed);
+    }
+    // Returns the number of distinct keys held in the cache
+    pub fn len(&self) -> usize {
+        self.decompressed.len()
+    }
+    // Returns true if the cache holds no keys
+    pub fn is_empty(&self) -> bool {
+        self.decompressed.is_empty()
+    }
+    // Prepares a ring using the keys held in the cache.
+    // The ring must have been added to the cache with `insert_rings`
+    pub fn prepare(&self, public_keys: &[Vec<CompressedRistretto>]) -> Result<PreparedRing, Error> {
+        let mut keys = Vec::with_capacity(public_keys.len());
+        let mut hashed_pubkeys = Vec::with_capacity(public_keys.len());
+
+        for pub_keys in public_keys {
+            let first_pubkey = pub_keys.first().ok_or(Error::IncorrectNumOfPubKeys)?;
+            let hashed_pubkey = self
+                .hashed_pubkeys
+                .get(first_pubkey.as_bytes())
+                .ok_or(Error::BadPoint)?;
+            hashed_pubkeys.push(*hashed_pubkey);
+
+            let member_keys = pub_keys
+                .iter()
+                .map(|pt| self.decompressed.get(pt.as_bytes()).cloned().flatten())
+                .collect::<Option<Vec<RistrettoPoint>>>()
+                .ok_or(Error::BadPoint)?;
+            keys.push(member_keys);
+        }
+
+        Ok(PreparedRing::from_parts(
+            public_keys.to_vec(),
+            keys,
+            hashed_pubkeys,
+        ))
+    }
+}
+
+// Verifies many signatures, possibly over different rings.
+// Decompressed keys and hashed signing keys are shared between rings,
+// and the rings themselves are walked in parallel.
+// Returns one result per item, in the same order as the items.
+pub fn verify_batch(items: &[BatchItem]) -> Vec<Result<(), Error>> {
+    let mut cache = KeyCache::new();
+    cache.insert_rings(items.iter().map(|item| item.public_keys));
+
+    verify_batch_with_cache(&cache, items)
+}
+
+// Verifies many signatures using a cache which has already been populated
+// with every ring in the batch. This allows a cache to be reused across batches.
+pub fn verify_batch_with_cache(cache: &KeyCache, items: &[BatchItem]) -> Vec<Result<(), Error>> {
+    items
+        .par_iter()
+        .map(|item| {
+            if item.public_keys.len() != item.signature.responses.len() {
+                return Err(Error::IncorrectNumOfPubKeys);
+            }
+            let ring = cache.prepare(item.public_keys)?;
+            item.signature.verify_prepared(&ring, item.msg)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::clsag::Clsag;
+    use crate::tests_helper::*;
+
+    #[test]
+    fn test_verify_batch() {
+        let num_keys = 2;
+        let num_decoys = 7;
+        let num_signatures = 5;
+
+        // Every ring shares the same decoys, with a different signer appended
+        let decoys = generate_decoys(num_decoys, num_keys);
+
+        let mut signatures = Vec::with_capacity(num_signatures);
+        let mut rings = Vec::with_capacity(num_signatures);
+        let mut msgs = Vec::with_capacity(num_signatures);
+        for i in 0..num_signatures {
+            let mut clsag = Clsag::new();
+            for decoy in decoys.iter() {
+                clsag.add_member(decoy.clone());
+            }
+            clsag.add_member(generate_signer(num_keys));
+
+            let msg = format!("message {}", i).into_bytes();
+            signatures.push(clsag.sign(&msg).unwrap());
+            rings.push(clsag.public_keys());
+            msgs.push(msg);
+        }
+
+        let items: Vec<BatchItem> = (0..num_signatures)
+            .map(|i| BatchItem {
+                signature: &signatures[i],
+                public_keys: &rings[i],
+                msg: &msgs[i],
+            })
+            .collect();
+
+        let results = verify_batch(&items);
+        assert_eq!(results.len(), num_signatures);
+        assert!(results.iter().all(|res| res.is_ok()));
+
+        // The cache should only contain the shared decoys once
+        let mut cache = KeyCache::new();
+        cache.insert_rings(rings.iter().map(|ring| ring.as_slice()));
+        assert_eq!(cache.len(), (num_decoys + num_signatures) * num_keys);
+    }
+
+    #[test]
+    fn test_verify_batch_reports_failures() {
+        let num_keys = 2;
+        let num_decoys = 7;
+        let msg = b"hello world";
+
+        let mut clsag = generate_clsag_with(num_decoys, num_keys);
+        clsag.add_member(generate_signer(num_keys));
+        let sig = clsag.sign(msg).unwrap();
+        let ring = clsag.public_keys();
+
+        let mut other_clsag = generate_clsag_with(num_decoys, num_keys);
+        other_clsag.add_member(generate_signer(num_keys));
+        let other_sig = other_clsag.sign(msg).unwrap();
+        let other_ring = other_clsag.public_keys();
+
+        let items = vec![
+            BatchItem {
+                signature: &sig,
+                public_keys: &ring,
+                msg,
+            },
+            // wrong message
+            BatchItem {
+                signature: &sig,
+                public_keys: &ring,
+                msg: b"goodbye world",
+            },
+            // wrong ring
+            BatchItem {
+                signature: &other_sig,
+                public_keys: &ring,
+                msg,
+            },
+            // wrong number of members
+            BatchItem {
+                signature: &other_sig,
+                public_keys: &other_ring[1..],
+                msg,
+            },
+            BatchItem {
+                signature: &other_sig,
+                public_keys: &other_ring,
+                msg,
+            },
+        ];
+
+        let results = verify_batch(&items);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_err());
+        match results[3] {
+            Err(Error::IncorrectNumOfPubKeys) => {}
+            _ => panic!("expected an incorrect number of public keys error"),
+        }
+        assert!(results[4].is_ok());
+    }
+}
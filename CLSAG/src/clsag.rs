@@ -1,10 +1,15 @@
-use crate::member::Member;
+use crate::constants::BASEPOINT;
+use crate::member::{compute_challenge, Member};
+use crate::ring::PreparedRing;
 use crate::signature::Signature;
-use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 
 use crate::transcript::TranscriptProtocol;
 use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use std::collections::HashSet;
 
 // This module will pull together all of the necessary things
 // Setting up everything we need
@@ -21,6 +26,18 @@ pub enum Error {
     MoreThanOneSigner,
     // This error occurs if a member in the ring has duplicate keys
     DuplicateKeysExist,
+    // This error occurs when a prepared ring does not contain the same
+    // members as the clsag it is used to sign with
+    RingMismatch,
+    // This error occurs when the secret keys given to sign with
+    // do not correspond to the public keys of the signer
+    SecretKeyMismatch,
+    // This error occurs when a pool does not contain enough
+    // candidates to select the requested number of decoys
+    NotEnoughCandidates,
+    // This error occurs when a decoy selection does not return the requested
+    // number of distinct indices into the pool of candidates
+    InvalidDecoySelection,
     // This error occurs when an underlying module produces an error
     UnderlyingErr(String),
 }
@@ -37,6 +54,11 @@ impl From<crate::member::Error> for crate::clsag::Error {
         }
     }
 }
+impl From<crate::signature::Error> for crate::clsag::Error {
+    fn from(e: crate::signature::Error) -> crate::clsag::Error {
+        Error::UnderlyingErr(format!("underlying signature error {:?}", e))
+    }
+}
 // This struct is used to construct the clsag signature
 pub struct Clsag {
     members: Vec<Member>,
@@ -69,85 +91,36 @@ impl Clsag {
             .map(|member| member.public_set.to_keys())
             .collect()
     }
+    // Returns the ring formed by all known members, prepared for signing
+    // The same prepared ring can be used by verifiers of the signature
+    pub fn prepared_ring(&self) -> Result<PreparedRing, Error> {
+        let public_keys = self
+            .members
+            .iter()
+            .map(|member| member.public_set.0.clone())
+            .collect();
+        Ok(PreparedRing::from_points(public_keys)?)
+    }
     // sign produces a clsag signature
     pub fn sign(&self, msg: &[u8]) -> Result<Signature, Error> {
         self.check_format()?;
 
-        let num_members = self.members.len();
-        let mut all_challenges: Vec<Scalar> = Vec::with_capacity(num_members);
+        let ring = self.prepared_ring()?;
+        self.sign_with_ring(&ring, msg)
+    }
+    // sign_with_ring produces a clsag signature using a ring
+    // which has already been prepared from the members of this clsag.
+    // This avoids recomputing the ring when signing many messages.
+    pub fn sign_with_ring(&self, ring: &PreparedRing, msg: &[u8]) -> Result<Signature, Error> {
+        self.check_format()?;
+        self.check_ring(ring)?;
 
         // Fetch signer of the ring
         let signer_index = self.find_signer()?;
-        let signer = &self.members[signer_index];
-
-        // Fetch public key matrix
-        let pubkey_matrix = self.public_keys_bytes();
-
-        // Compute key images for signer
-        let key_images = signer.compute_key_images()?;
-
-        // Calculate aggrgation co-efficients
-        let aggregation_cooeff = calc_aggregation_coefficients(&pubkey_matrix, &key_images, msg);
+        let secret_keys = self.members[signer_index].private_keys()?;
 
-        // Calculate first challenge
-        let mut challenge = signer.compute_challenge_commitment(&pubkey_matrix)?;
-        all_challenges.push(challenge);
-
-        // seed challenge into for loop starting from member after signer
-        for decoy in self
-            .members
-            .iter()
-            .cycle()
-            .skip(signer_index + 1)
-            .take(num_members - 1)
-        {
-            challenge = decoy.compute_decoy_challenge(
-                &challenge,
-                &key_images,
-                &aggregation_cooeff,
-                &pubkey_matrix,
-            )?;
-            all_challenges.push(challenge);
-        }
-
-        // The last challenge variable should be the one generated by the member before the signer,
-        // which will be for the signer. The signer will use this to generate his response values
-        // and close the ring
-        let signers_response = signer.compute_signer_response(challenge, &aggregation_cooeff)?;
-
-        // Collect all responses
-        let mut all_responses: Vec<Scalar> = Vec::with_capacity(num_members);
-        for member in &self.members {
-            match member.is_signer() {
-                true => {
-                    all_responses.push(signers_response);
-                }
-                false => {
-                    let mem_response =
-                        member
-                            .response
-                            .clone()
-                            .ok_or(Error::UnderlyingErr(String::from(
-                                "member does not have a response value",
-                            )))?;
-                    all_responses.push(mem_response);
-                }
-            }
-        }
-
-        // Collect first members challenge
-        // The last element in the vector of challenges will be the signers challenge
-        // Since we also know the index `n` of the signer. We need to walk back `n` times
-        // This is equivalent to reversing the vector and getting the `n`th element
-        // to fetch the first challenge; the challenge corresponding to the first member
-        all_challenges.reverse();
-        let first_challenge = all_challenges[signer_index];
-
-        Ok(Signature {
-            challenge: first_challenge,
-            responses: all_responses,
-            key_images: key_images,
-        })
+        let mut rng = rand::thread_rng();
+        sign_prepared(ring, signer_index, secret_keys, msg, &mut rng)
     }
     // Returns the position of the signer in the ring
     // If this call is completed after check_format, it should not fail
@@ -207,7 +180,182 @@ impl Clsag {
         }
         Ok(())
     }
+    // Checks that a prepared ring was made from the members of this clsag, in the same order
+    fn check_ring(&self, ring: &PreparedRing) -> Result<(), Error> {
+        if ring.len() != self.members.len() {
+            return Err(Error::RingMismatch);
+        }
+
+        let all_keys_match = self
+            .members
+            .iter()
+            .zip(ring.keys.iter())
+            .all(|(member, keys)| &member.public_set.0 == keys);
+        if !all_keys_match {
+            return Err(Error::RingMismatch);
+        }
+        Ok(())
+    }
+}
+// sign produces a clsag signature over the ring.
+// `secret_keys` are the private keys of the member at `signer_index`.
+// All randomness is generated from `rng`, so this can be called repeatedly,
+// and concurrently, with the same ring.
+pub fn sign<R: RngCore + CryptoRng>(
+    ring: &[Vec<RistrettoPoint>],
+    signer_index: usize,
+    secret_keys: &[Scalar],
+    msg: &[u8],
+    rng: &mut R,
+) -> Result<Signature, Error> {
+    let ring = PreparedRing::from_points(ring.to_vec())?;
+    sign_prepared(&ring, signer_index, secret_keys, msg, rng)
 }
+
+// sign_prepared produces a clsag signature over a ring which has already been prepared.
+// See `sign`
+pub fn sign_prepared<R: RngCore + CryptoRng>(
+    ring: &PreparedRing,
+    signer_index: usize,
+    secret_keys: &[Scalar],
+    msg: &[u8],
+    rng: &mut R,
+) -> Result<Signature, Error> {
+    check_ring_format(ring, signer_index, secret_keys)?;
+
+    let num_members = ring.len();
+
+    // Fetch public key matrix
+    let pubkey_matrix = ring.pubkey_matrix();
+
+    // Compute key images for signer
+    // keyImage = privateKey * HashToPoint(PublicSigningKey)
+    let signers_basepoint = &ring.hashed_pubkeys[signer_index];
+    let key_image_points: Vec<RistrettoPoint> = secret_keys
+        .iter()
+        .map(|priv_key| priv_key * signers_basepoint)
+        .collect();
+    let key_images: Vec<CompressedRistretto> = key_image_points
+        .iter()
+        .map(|key_image| key_image.compress())
+        .collect();
+
+    // Calculate aggrgation co-efficients
+    let aggregation_cooeff = calc_aggregation_coefficients(pubkey_matrix, &key_images, msg);
+
+    // The decoys responses are random, the signers response is computed last
+    let mut responses: Vec<Scalar> = (0..num_members).map(|_| Scalar::random(rng)).collect();
+    let mut challenges: Vec<Scalar> = vec![Scalar::zero(); num_members];
+
+    // Commit to a nonce to calculate the challenge for the member after the signer.
+    // The ring will therefore only be completed if the signer can generate the
+    // corresponding response, which can only be done if the signer possesses
+    // the discrete log to the public keys corresponding to their position in the ring.
+    let nonce = Scalar::random(rng);
+
+    // L = nonce * basepoint
+    let l = nonce * BASEPOINT;
+
+    // R = nonce * hashed_pubkey
+    let r = nonce * signers_basepoint;
+
+    // H(pubkey_matrix || L || R)
+    // XXX: Note m is omitted and will be added in a later iteration
+    let mut transcript = Transcript::new(b"clsag");
+    transcript.append_message(b"", pubkey_matrix);
+    transcript.append_point(b"", &l);
+    transcript.append_point(b"", &r);
+    let mut challenge = transcript.challenge_scalar(b"");
+
+    // seed challenge into for loop starting from member after signer
+    for decoy_index in (1..num_members).map(|i| (signer_index + i) % num_members) {
+        challenges[decoy_index] = challenge;
+        challenge = compute_challenge(
+            &ring.keys[decoy_index],
+            &challenge,
+            &key_image_points,
+            &responses[decoy_index],
+            &aggregation_cooeff,
+            &ring.hashed_pubkeys[decoy_index],
+            pubkey_matrix,
+        );
+    }
+
+    // The last challenge should be the one generated by the member before the signer,
+    // which will be for the signer. The signer will use this to generate their response
+    // and close the ring
+    // response = nonce - challenge * sum(mu_j * priv_keys)
+    challenges[signer_index] = challenge;
+    let sum_aux: Scalar = secret_keys
+        .iter()
+        .zip(aggregation_cooeff.iter())
+        .map(|(x, mu)| x * mu)
+        .sum();
+    responses[signer_index] = nonce - challenge * sum_aux;
+
+    Ok(Signature {
+        challenge: challenges[0],
+        responses,
+        key_images,
+    })
+}
+
+// Checks that a ring and the signers secret keys are correctly formatted for signing
+fn check_ring_format(
+    ring: &PreparedRing,
+    signer_index: usize,
+    secret_keys: &[Scalar],
+) -> Result<(), Error> {
+    // Check that we have more than one member
+    if ring.len() < 2 {
+        return Err(Error::NotEnoughMembers);
+    }
+
+    // Check that the signer is in the ring
+    if signer_index >= ring.len() {
+        return Err(Error::NoSigner);
+    }
+
+    // Check that each member has the same number of keys as the signer
+    let all_same_num_keys = ring.keys.iter().all(|keys| keys.len() == secret_keys.len());
+    if !all_same_num_keys {
+        return Err(Error::NumberOfKeysMismatch);
+    }
+
+    // Check that each member has no duplicates
+    let no_duplicates_exists = ring.public_keys().iter().all(|pub_keys| {
+        let uniques: HashSet<&[u8; 32]> = pub_keys.iter().map(|key| key.as_bytes()).collect();
+        uniques.len() == pub_keys.len()
+    });
+    if !no_duplicates_exists {
+        return Err(Error::DuplicateKeysExist);
+    }
+
+    // Check that the secret keys belong to the signer
+    let keys_match = secret_keys
+        .iter()
+        .zip(ring.keys[signer_index].iter())
+        .all(|(priv_key, pub_key)| &(priv_key * BASEPOINT) == pub_key);
+    if !keys_match {
+        return Err(Error::SecretKeyMismatch);
+    }
+
+    Ok(())
+}
+
+// prehash hashes a message to 64 bytes. Signing and verifying the digest instead
+// of a long message hashes the message once, rather than once for each of the
+// aggregation co-efficients, of which there is one per key of a member
+pub fn prehash(msg: &[u8]) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.input(b"clsag-prehash");
+    hasher.input(msg);
+
+    let mut digest = [0u8; 64];
+    digest.copy_from_slice(&hasher.result());
+    digest
+}
+
 // Calculates the aggregation co-efficients mu_x and [mu_j]
 pub fn calc_aggregation_coefficients(
     pubkey_matrix: &[u8],
@@ -326,6 +474,136 @@ mod test {
         assert_eq!(num_members, signature.responses.len());
     }
 
+    #[test]
+    fn test_sign_with_ring() {
+        let num_decoys = 10;
+        let num_keys = 3;
+        let mut clsag = generate_clsag_with(num_decoys, num_keys);
+        clsag.add_member(generate_signer(num_keys));
+
+        let ring = clsag.prepared_ring().unwrap();
+        for msg in [b"hello world", b"hello there"].iter() {
+            let signature = clsag.sign_with_ring(&ring, *msg).unwrap();
+            assert!(signature.verify_prepared(&ring, *msg).is_ok());
+        }
+
+        // A ring prepared from different members cannot be used
+        let mut other_clsag = generate_clsag_with(num_decoys, num_keys);
+        other_clsag.add_member(generate_signer(num_keys));
+        let other_ring = other_clsag.prepared_ring().unwrap();
+        match clsag.sign_with_ring(&other_ring, b"hello world") {
+            Ok(_) => panic!("expected an error as the ring does not match the members"),
+            Err(Error::RingMismatch) => {}
+            Err(_) => panic!("got an error, however we expected a ring mismatch error"),
+        }
+    }
+
+    #[test]
+    fn test_prehashed_message() {
+        let num_decoys = 10;
+        let num_keys = 3;
+        let mut clsag = generate_clsag_with(num_decoys, num_keys);
+        clsag.add_member(generate_signer(num_keys));
+
+        let msg = vec![7u8; 1 << 16];
+        let digest = prehash(&msg);
+        let signature = clsag.sign(&digest).unwrap();
+
+        let ring = clsag.prepared_ring().unwrap();
+        assert!(signature.verify_prepared(&ring, &digest).is_ok());
+        assert!(signature.verify_prepared(&ring, &msg).is_err());
+        assert_ne!(prehash(&msg[1..]), digest);
+    }
+
+    #[test]
+    fn test_stateless_sign() {
+        let num_members = 11;
+        let num_keys = 2;
+        let msg = b"hello world";
+        let mut rng = rand::thread_rng();
+
+        let ring: Vec<Vec<RistrettoPoint>> = (0..num_members)
+            .map(|_| generate_rand_points(num_keys))
+            .collect();
+        let secret_keys = generate_rand_scalars(num_keys);
+
+        for &signer_index in [0, 5, num_members - 1].iter() {
+            let mut ring = ring.clone();
+            ring[signer_index] = secret_keys.iter().map(|x| x * BASEPOINT).collect();
+
+            let sig = sign(&ring, signer_index, &secret_keys, msg, &mut rng).unwrap();
+            let mut public_keys: Vec<Vec<CompressedRistretto>> = ring
+                .iter()
+                .map(|keys| keys.iter().map(|key| key.compress()).collect())
+                .collect();
+            assert!(sig.optimised_verify(&mut public_keys, msg).is_ok());
+            assert!(sig.verify(&mut public_keys, msg).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_stateless_sign_concurrently() {
+        use rayon::prelude::*;
+
+        let num_members = 8;
+        let num_keys = 2;
+        let signer_index = 3;
+
+        let mut ring: Vec<Vec<RistrettoPoint>> = (0..num_members)
+            .map(|_| generate_rand_points(num_keys))
+            .collect();
+        let secret_keys = generate_rand_scalars(num_keys);
+        ring[signer_index] = secret_keys.iter().map(|x| x * BASEPOINT).collect();
+        let prepared = PreparedRing::from_points(ring.clone()).unwrap();
+
+        let signatures: Vec<Signature> = (0..16)
+            .into_par_iter()
+            .map(|_| {
+                let mut rng = rand::thread_rng();
+                sign(&ring, signer_index, &secret_keys, b"hello world", &mut rng).unwrap()
+            })
+            .collect();
+
+        for sig in signatures.iter() {
+            assert!(sig.verify_prepared(&prepared, b"hello world").is_ok());
+            // The same signer always produces the same key images
+            assert_eq!(sig.key_images, signatures[0].key_images);
+        }
+        // However the randomness differs between signatures
+        assert_ne!(signatures[0].challenge, signatures[1].challenge);
+    }
+
+    #[test]
+    fn test_stateless_sign_errors() {
+        let num_members = 4;
+        let num_keys = 2;
+        let msg = b"hello world";
+        let mut rng = rand::thread_rng();
+
+        let mut ring: Vec<Vec<RistrettoPoint>> = (0..num_members)
+            .map(|_| generate_rand_points(num_keys))
+            .collect();
+        let secret_keys = generate_rand_scalars(num_keys);
+        ring[0] = secret_keys.iter().map(|x| x * BASEPOINT).collect();
+
+        match sign(&ring[..1], 0, &secret_keys, msg, &mut rng) {
+            Err(Error::NotEnoughMembers) => {}
+            _ => panic!("expected a not enough members error"),
+        }
+        match sign(&ring, num_members, &secret_keys, msg, &mut rng) {
+            Err(Error::NoSigner) => {}
+            _ => panic!("expected a no signer error"),
+        }
+        match sign(&ring, 1, &secret_keys, msg, &mut rng) {
+            Err(Error::SecretKeyMismatch) => {}
+            _ => panic!("expected a secret key mismatch error"),
+        }
+        match sign(&ring, 0, &secret_keys[..1], msg, &mut rng) {
+            Err(Error::NumberOfKeysMismatch) => {}
+            _ => panic!("expected a number of keys mismatch error"),
+        }
+    }
+
     #[bench]
     fn bench_sign(b: &mut Bencher) {
         // One time setup code here
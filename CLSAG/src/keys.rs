@@ -1,7 +1,7 @@
 use crate::constants::BASEPOINT;
+use crate::ring::hash_pubkey;
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
-use sha2::Sha512;
 
 use std::collections::HashSet;
 // Public key set represents a set of public keys
@@ -37,8 +37,7 @@ impl PublicSet {
     // Returns the Hash_to_point of the first public key in the set
     // This point is used extensively during the protocol for each member
     pub fn hashed_pubkey(&self) -> RistrettoPoint {
-        let first_pubkey = &self.0[0].compress();
-        RistrettoPoint::hash_from_bytes::<Sha512>(first_pubkey.as_bytes())
+        hash_pubkey(&self.0[0].compress())
     }
     // Copies the public key set into a vector of bytes
     pub fn to_bytes(&self) -> Vec<u8> {
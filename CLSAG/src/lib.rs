@@ -1,9 +1,14 @@
 #![feature(test)]
+pub mod batch;
 pub mod clsag;
 #[allow(non_snake_case)]
 pub mod constants;
 pub mod keys;
 pub mod member;
+pub mod pedersen;
+pub mod ring;
+pub mod ring_builder;
+pub mod ringct;
 pub mod signature;
 pub mod tests_helper;
 mod transcript;
@@ -0,0 +1,106 @@
+use crate::constants::BASEPOINT;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+// Returns the generator which amounts are committed to.
+// This is the Hash_to_point of the basepoint, so nobody knows its discrete log
+// with respect to the basepoint, which is what makes the commitment binding.
+pub fn value_generator() -> RistrettoPoint {
+    RistrettoPoint::from_uniform_bytes(&hash_to_group::hash_to_ristretto255(
+        BASEPOINT.compress().as_bytes(),
+        hash_to_group::CLSAG_VALUE_GENERATOR_DST,
+    ))
+}
+
+// Computes the Pedersen commitment C = blinding * G + amount * H
+pub fn commit(amount: u64, blinding: &Scalar) -> RistrettoPoint {
+    blinding * BASEPOINT + Scalar::from(amount) * value_generator()
+}
+
+// An opening holds the secret values behind a Pedersen commitment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Opening {
+    pub amount: u64,
+    pub blinding: Scalar,
+}
+
+impl Opening {
+    pub fn new(amount: u64, blinding: Scalar) -> Self {
+        Opening { amount, blinding }
+    }
+    // Creates an opening for the amount with a random blinding factor
+    pub fn random(amount: u64) -> Self {
+        let mut rng = rand::thread_rng();
+        Opening {
+            amount,
+            blinding: Scalar::random(&mut rng),
+        }
+    }
+    // Returns the commitment to this opening
+    pub fn commit(&self) -> RistrettoPoint {
+        commit(self.amount, &self.blinding)
+    }
+}
+
+// Checks that the commitments to the inputs equal the commitments to the outputs
+// plus the fee, without knowing any of the amounts.
+// The fee is public, so it is committed to with a zero blinding factor
+pub fn is_balanced(inputs: &[RistrettoPoint], outputs: &[RistrettoPoint], fee: u64) -> bool {
+    let sum_inputs: RistrettoPoint = inputs.iter().sum();
+    let sum_outputs: RistrettoPoint = outputs.iter().sum();
+
+    sum_inputs == sum_outputs + commit(fee, &Scalar::zero())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_commitment_is_homomorphic() {
+        let a = Opening::random(10);
+        let b = Opening::random(32);
+
+        let sum = Opening::new(a.amount + b.amount, a.blinding + b.blinding);
+        assert_eq!(a.commit() + b.commit(), sum.commit());
+    }
+
+    #[test]
+    fn test_commitment_to_zero() {
+        // Two commitments to the same amount differ by a multiple of the basepoint
+        let input = Opening::random(100);
+        let pseudo_out = Opening::random(100);
+
+        let difference = input.commit() - pseudo_out.commit();
+        assert_eq!(
+            difference,
+            (input.blinding - pseudo_out.blinding) * BASEPOINT
+        );
+
+        // This is not the case when the amounts differ
+        let pseudo_out = Opening::new(99, pseudo_out.blinding);
+        let difference = input.commit() - pseudo_out.commit();
+        assert_ne!(
+            difference,
+            (input.blinding - pseudo_out.blinding) * BASEPOINT
+        );
+    }
+
+    #[test]
+    fn test_is_balanced() {
+        let input = Opening::random(100);
+        let output = Opening::random(90);
+        let fee = 10;
+
+        // The pseudo output blinding is chosen so that the blinding factors cancel
+        let pseudo_out = Opening::new(100, output.blinding);
+        assert!(is_balanced(&[pseudo_out.commit()], &[output.commit()], fee));
+
+        assert!(!is_balanced(
+            &[pseudo_out.commit()],
+            &[output.commit()],
+            fee + 1
+        ));
+        assert!(!is_balanced(&[input.commit()], &[output.commit()], fee));
+    }
+}
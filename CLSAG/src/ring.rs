@@ -0,0 +1,152 @@
+use crate::signature::Error;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+
+// A PreparedRing holds everything about a ring which does not depend on
+// the message or the signature. Computing these values is a large part of the
+// cost of signing and verifying, so a ring which is used for many signatures
+// should be prepared once and passed to `Clsag::sign_with_ring` and
+// `Signature::verify_prepared`.
+#[derive(Debug, Clone)]
+pub struct PreparedRing {
+    // The compressed public keys for each member, in ring order
+    pub(crate) compressed: Vec<Vec<CompressedRistretto>>,
+
+    // The decompressed public keys for each member, in ring order
+    pub(crate) keys: Vec<Vec<RistrettoPoint>>,
+
+    // Hash_to_point of the first public key of each member
+    pub(crate) hashed_pubkeys: Vec<RistrettoPoint>,
+
+    // The serialised public key matrix, which is committed to in every challenge
+    pub(crate) pubkey_matrix: Vec<u8>,
+}
+
+impl PreparedRing {
+    // Creates a prepared ring from compressed public keys, as received by a verifier.
+    // Returns an error if any key cannot be decompressed, or if a member has no keys
+    pub fn new(public_keys: &[Vec<CompressedRistretto>]) -> Result<Self, Error> {
+        let keys = public_keys
+            .iter()
+            .map(|pub_keys| {
+                pub_keys
+                    .iter()
+                    .map(|pt| pt.decompress())
+                    .collect::<Option<Vec<RistrettoPoint>>>()
+            })
+            .collect::<Option<Vec<Vec<RistrettoPoint>>>>()
+            .ok_or(Error::BadPoint)?;
+
+        let hashed_pubkeys = hash_pubkeys(public_keys)?;
+
+        Ok(Self::from_parts(public_keys.to_vec(), keys, hashed_pubkeys))
+    }
+
+    // Creates a prepared ring from public keys which are already decompressed,
+    // as held by a signer.
+    // Returns an error if a member has no keys
+    pub fn from_points(public_keys: Vec<Vec<RistrettoPoint>>) -> Result<Self, Error> {
+        let compressed: Vec<Vec<CompressedRistretto>> = public_keys
+            .iter()
+            .map(|pub_keys| pub_keys.iter().map(|pt| pt.compress()).collect())
+            .collect();
+
+        let hashed_pubkeys = hash_pubkeys(&compressed)?;
+
+        Ok(Self::from_parts(compressed, public_keys, hashed_pubkeys))
+    }
+
+    // Assembles a prepared ring from values which have already been computed,
+    // for example by a cache shared between rings
+    pub(crate) fn from_parts(
+        compressed: Vec<Vec<CompressedRistretto>>,
+        keys: Vec<Vec<RistrettoPoint>>,
+        hashed_pubkeys: Vec<RistrettoPoint>,
+    ) -> Self {
+        let pubkey_matrix = compressed
+            .iter()
+            .flatten()
+            .flat_map(|pubkey| pubkey.to_bytes().to_vec())
+            .collect();
+
+        PreparedRing {
+            compressed,
+            keys,
+            hashed_pubkeys,
+            pubkey_matrix,
+        }
+    }
+    // Returns the number of members in the ring
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    // Returns true if the ring has no members
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+    // Returns the compressed public keys of every member, in ring order
+    pub fn public_keys(&self) -> &[Vec<CompressedRistretto>] {
+        &self.compressed
+    }
+    // Returns the serialised public key matrix
+    pub fn pubkey_matrix(&self) -> &[u8] {
+        &self.pubkey_matrix
+    }
+}
+
+// Returns the Hash_to_point of the first public key of every member
+// Returns an error if a member has no keys
+fn hash_pubkeys(public_keys: &[Vec<CompressedRistretto>]) -> Result<Vec<RistrettoPoint>, Error> {
+    public_keys
+        .iter()
+        .map(|pub_keys| {
+            pub_keys
+                .first()
+                .map(hash_pubkey)
+                .ok_or(Error::IncorrectNumOfPubKeys)
+        })
+        .collect()
+}
+
+// Returns the Hash_to_point of a members first public key, which is the base of their
+// key image. This is the RFC 9380 ristretto255 hash under the key image base DST
+pub(crate) fn hash_pubkey(first_pubkey: &CompressedRistretto) -> RistrettoPoint {
+    RistrettoPoint::from_uniform_bytes(&hash_to_group::hash_to_ristretto255(
+        first_pubkey.as_bytes(),
+        hash_to_group::CLSAG_KEY_IMAGE_BASE_DST,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tests_helper::*;
+
+    #[test]
+    fn test_prepared_ring_matches_clsag() {
+        let num_keys = 3;
+        let num_decoys = 5;
+
+        let mut clsag = generate_clsag_with(num_decoys, num_keys);
+        clsag.add_member(generate_signer(num_keys));
+
+        let ring = PreparedRing::new(&clsag.public_keys()).unwrap();
+        assert_eq!(ring.len(), num_decoys + 1);
+        assert_eq!(ring.pubkey_matrix(), clsag.public_keys_bytes().as_slice());
+
+        let from_signer = clsag.prepared_ring().unwrap();
+        assert_eq!(from_signer.pubkey_matrix(), ring.pubkey_matrix());
+        assert_eq!(from_signer.hashed_pubkeys, ring.hashed_pubkeys);
+    }
+
+    #[test]
+    fn test_prepared_ring_bad_point() {
+        let mut public_keys = vec![generate_rand_compressed_points(2); 3];
+        // This is not a canonical encoding of a ristretto point
+        public_keys[1][0] = CompressedRistretto([0xff; 32]);
+
+        match PreparedRing::new(&public_keys) {
+            Err(Error::BadPoint) => {}
+            _ => panic!("expected a bad point error"),
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::clsag::{Clsag, Error};
+use crate::member::Member;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use rand::seq::index;
+use rand::Rng;
+use std::collections::HashSet;
+
+// DecoySelection decides which candidates from a pool of public key sets
+// are used as decoys in a ring
+pub trait DecoySelection {
+    // Returns `amount` distinct indices into a pool of `pool_size` candidates
+    fn select<R: Rng + ?Sized>(&self, rng: &mut R, pool_size: usize, amount: usize) -> Vec<usize>;
+}
+
+// Selects decoys uniformly at random from the pool
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Uniform;
+
+impl DecoySelection for Uniform {
+    fn select<R: Rng + ?Sized>(&self, rng: &mut R, pool_size: usize, amount: usize) -> Vec<usize> {
+        index::sample(rng, pool_size, amount).into_vec()
+    }
+}
+
+// The result of building a ring
+pub struct BuiltRing {
+    // The clsag, with members added in ring order, ready to sign
+    pub clsag: Clsag,
+    // The public keys of every member in ring order.
+    // Verifiers need this exact ordering to check the signature
+    pub ring: Vec<Vec<CompressedRistretto>>,
+    // The position of the signer in the ring.
+    // This should not be shared, it is only returned for the signer's benefit
+    pub signer_index: usize,
+}
+
+// RingBuilder constructs a ring from a pool of candidate public key sets.
+// Decoys are chosen from the pool using a `DecoySelection` and the signer is placed
+// at a uniformly random position, so the position does not reveal the signer.
+pub struct RingBuilder<D = Uniform> {
+    pool: Vec<Vec<RistrettoPoint>>,
+    num_decoys: usize,
+    selection: D,
+}
+
+impl RingBuilder<Uniform> {
+    // Creates a ring builder which selects `num_decoys` decoys uniformly from the pool
+    pub fn new(pool: Vec<Vec<RistrettoPoint>>, num_decoys: usize) -> Self {
+        RingBuilder {
+            pool,
+            num_decoys,
+            selection: Uniform,
+        }
+    }
+}
+
+impl<D: DecoySelection> RingBuilder<D> {
+    // Replaces the strategy used to select decoys from the pool
+    pub fn with_selection<E: DecoySelection>(self, selection: E) -> RingBuilder<E> {
+        RingBuilder {
+            pool: self.pool,
+            num_decoys: self.num_decoys,
+            selection,
+        }
+    }
+    // Builds a ring containing the signer and decoys selected from the pool.
+    // If the signers public keys are in the pool, they will not be selected as a decoy
+    pub fn build<R: Rng + ?Sized>(&self, rng: &mut R, signer: Member) -> Result<BuiltRing, Error> {
+        if !signer.is_signer() {
+            return Err(Error::NoSigner);
+        }
+
+        let num_keys = signer.num_keys();
+        if self
+            .pool
+            .iter()
+            .any(|candidate| candidate.len() != num_keys)
+        {
+            return Err(Error::NumberOfKeysMismatch);
+        }
+
+        // Remove the signer from the candidates
+        let candidates: Vec<&Vec<RistrettoPoint>> = self
+            .pool
+            .iter()
+            .filter(|candidate| **candidate != signer.public_set.0)
+            .collect();
+        if candidates.len() < self.num_decoys {
+            return Err(Error::NotEnoughCandidates);
+        }
+
+        let decoy_indices = self
+            .selection
+            .select(rng, candidates.len(), self.num_decoys);
+
+        // A custom selection is not trusted to return valid indices. The signer has
+        // been removed from the candidates, so an index in range is never the signer
+        let mut seen = HashSet::new();
+        if decoy_indices.len() != self.num_decoys
+            || !decoy_indices
+                .iter()
+                .all(|&i| i < candidates.len() && seen.insert(i))
+        {
+            return Err(Error::InvalidDecoySelection);
+        }
+
+        // The signer can take any position, including after the last decoy
+        let signer_index = rng.gen_range(0, self.num_decoys + 1);
+
+        let mut members: Vec<Member> = decoy_indices
+            .into_iter()
+            .map(|i| Member::new_decoy(candidates[i].clone()))
+            .collect();
+        members.insert(signer_index, signer);
+
+        let mut clsag = Clsag::new();
+        for member in members {
+            clsag.add_member(member);
+        }
+        let ring = clsag.public_keys();
+
+        Ok(BuiltRing {
+            clsag,
+            ring,
+            signer_index,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tests_helper::*;
+
+    fn generate_pool(pool_size: usize, num_keys: usize) -> Vec<Vec<RistrettoPoint>> {
+        (0..pool_size)
+            .map(|_| generate_rand_points(num_keys))
+            .collect()
+    }
+
+    #[test]
+    fn test_build_and_verify() {
+        let num_keys = 2;
+        let num_decoys = 10;
+        let msg = b"hello world";
+        let mut rng = rand::thread_rng();
+
+        let pool = generate_pool(50, num_keys);
+        let builder = RingBuilder::new(pool.clone(), num_decoys);
+
+        let built = builder.build(&mut rng, generate_signer(num_keys)).unwrap();
+        assert_eq!(built.ring.len(), num_decoys + 1);
+        assert_eq!(built.clsag.find_signer().unwrap(), built.signer_index);
+
+        // Every decoy should come from the pool
+        for (i, pub_keys) in built.ring.iter().enumerate() {
+            if i == built.signer_index {
+                continue;
+            }
+            assert!(pool.iter().any(|candidate| {
+                candidate.iter().map(|pt| pt.compress()).collect::<Vec<_>>() == *pub_keys
+            }));
+        }
+
+        let sig = built.clsag.sign(msg).unwrap();
+        let mut ring = built.ring.clone();
+        assert!(sig.optimised_verify(&mut ring, msg).is_ok());
+    }
+
+    #[test]
+    fn test_signer_position_is_not_fixed() {
+        let num_keys = 1;
+        let num_decoys = 3;
+        let mut rng = rand::thread_rng();
+
+        let builder = RingBuilder::new(generate_pool(10, num_keys), num_decoys);
+
+        // With 4 possible positions, 100 rings placing the signer at the same
+        // position every time happens with probability 4^-99
+        let mut positions = Vec::new();
+        for _ in 0..100 {
+            let built = builder.build(&mut rng, generate_signer(num_keys)).unwrap();
+            assert!(built.signer_index <= num_decoys);
+            positions.push(built.signer_index);
+        }
+        assert!(positions.iter().any(|&pos| pos != positions[0]));
+    }
+
+    #[test]
+    fn test_signer_not_selected_as_decoy() {
+        let num_keys = 2;
+        let mut rng = rand::thread_rng();
+
+        let signer = generate_signer(num_keys);
+        let mut pool = generate_pool(3, num_keys);
+        pool.push(signer.public_set.0.clone());
+
+        // The pool has four candidates, however one of them is the signer
+        let builder = RingBuilder::new(pool.clone(), 4);
+        match builder.build(&mut rng, signer.clone()) {
+            Err(Error::NotEnoughCandidates) => {}
+            _ => panic!("expected a not enough candidates error"),
+        }
+
+        let builder = RingBuilder::new(pool, 3);
+        let built = builder.build(&mut rng, signer).unwrap();
+        assert!(built.clsag.sign(b"hello world").is_ok());
+    }
+
+    // Always picks the first candidates in the pool
+    struct FirstCandidates;
+
+    impl DecoySelection for FirstCandidates {
+        fn select<R: Rng + ?Sized>(&self, _: &mut R, _: usize, amount: usize) -> Vec<usize> {
+            (0..amount).collect()
+        }
+    }
+
+    // Returns the given indices regardless of the pool
+    struct FixedIndices(Vec<usize>);
+
+    impl DecoySelection for FixedIndices {
+        fn select<R: Rng + ?Sized>(&self, _: &mut R, _: usize, _: usize) -> Vec<usize> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_invalid_selection() {
+        let num_keys = 1;
+        let mut rng = rand::thread_rng();
+        let pool = generate_pool(5, num_keys);
+
+        // Too few, too many, repeated and out of range indices are all rejected
+        for indices in vec![vec![0, 1], vec![0, 1, 2, 3], vec![0, 1, 1], vec![0, 1, 5]] {
+            let builder = RingBuilder::new(pool.clone(), 3).with_selection(FixedIndices(indices));
+            match builder.build(&mut rng, generate_signer(num_keys)) {
+                Err(Error::InvalidDecoySelection) => {}
+                _ => panic!("expected an invalid decoy selection error"),
+            }
+        }
+
+        let builder = RingBuilder::new(pool, 3).with_selection(FixedIndices(vec![4, 0, 2]));
+        assert!(builder.build(&mut rng, generate_signer(num_keys)).is_ok());
+    }
+
+    #[test]
+    fn test_custom_selection() {
+        let num_keys = 2;
+        let num_decoys = 4;
+        let mut rng = rand::thread_rng();
+
+        let pool = generate_pool(20, num_keys);
+        let builder = RingBuilder::new(pool.clone(), num_decoys).with_selection(FirstCandidates);
+
+        let built = builder.build(&mut rng, generate_signer(num_keys)).unwrap();
+        let decoys: Vec<&Vec<CompressedRistretto>> = built
+            .ring
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != built.signer_index)
+            .map(|(_, pub_keys)| pub_keys)
+            .collect();
+        for (decoy, candidate) in decoys.iter().zip(pool.iter()) {
+            let candidate: Vec<CompressedRistretto> =
+                candidate.iter().map(|pt| pt.compress()).collect();
+            assert_eq!(**decoy, candidate);
+        }
+    }
+}
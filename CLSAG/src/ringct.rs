@@ -0,0 +1,190 @@
+use crate::member::Member;
+use crate::pedersen::Opening;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+// In RingCT each ring member is an output with a one-time public key P
+// and a Pedersen commitment C to its amount. The signer publishes a pseudo output
+// commitment C' to the same amount they are spending.
+// Each member in the clsag then has two keys, P and the commitment difference C - C'.
+// For the real input C - C' is a commitment to zero, z * G, where z is the
+// difference in blinding factors. Producing a clsag therefore proves both that the
+// signer owns P and that the input and pseudo output commit to the same amount.
+
+#[derive(Debug)]
+pub enum Error {
+    // Occurs when the input and pseudo output do not commit to the same amount,
+    // so the commitment difference is not a commitment to zero
+    AmountMismatch,
+}
+
+// A ring member as seen by everybody: a one-time public key and an amount commitment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub public_key: RistrettoPoint,
+    pub commitment: RistrettoPoint,
+}
+
+impl Output {
+    pub fn new(public_key: RistrettoPoint, commitment: RistrettoPoint) -> Self {
+        Output {
+            public_key,
+            commitment,
+        }
+    }
+    // Returns the keys this output contributes to a clsag, [P, C - C']
+    pub fn keys(&self, pseudo_out: &RistrettoPoint) -> Vec<RistrettoPoint> {
+        vec![self.public_key, self.commitment - pseudo_out]
+    }
+}
+
+// Creates the signing member for the output being spent.
+// The private keys are the output's private key and the blinding factor
+// of the commitment to zero, input blinding - pseudo output blinding
+pub fn new_signer(
+    private_key: Scalar,
+    input: &Opening,
+    pseudo_out: &Opening,
+) -> Result<Member, Error> {
+    if input.amount != pseudo_out.amount {
+        return Err(Error::AmountMismatch);
+    }
+
+    let commitment_to_zero = input.blinding - pseudo_out.blinding;
+    Ok(Member::new_signer(vec![private_key, commitment_to_zero]))
+}
+
+// Creates a decoy member from another output on the chain
+pub fn new_decoy(output: &Output, pseudo_out: &RistrettoPoint) -> Member {
+    Member::new_decoy(output.keys(pseudo_out))
+}
+
+// Returns the public keys a verifier should check the clsag against.
+// These are computed from the ring's outputs and the pseudo output, so a signer who
+// does not hold a commitment to zero cannot produce a signature which verifies
+pub fn ring_keys(ring: &[Output], pseudo_out: &RistrettoPoint) -> Vec<Vec<CompressedRistretto>> {
+    ring.iter()
+        .map(|output| {
+            output
+                .keys(pseudo_out)
+                .iter()
+                .map(|key| key.compress())
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::clsag::Clsag;
+    use crate::constants::BASEPOINT;
+    use crate::pedersen::{self, Opening};
+    use crate::tests_helper::*;
+
+    // Returns the ring of outputs, with the signers output last
+    fn generate_ring(num_decoys: usize, signer: &Output) -> Vec<Output> {
+        let mut ring: Vec<Output> = (0..num_decoys)
+            .map(|i| {
+                let points = generate_rand_points(1);
+                Output::new(points[0], Opening::random(i as u64).commit())
+            })
+            .collect();
+        ring.push(signer.clone());
+        ring
+    }
+
+    fn sign_ringct(
+        ring: &[Output],
+        pseudo_out: &RistrettoPoint,
+        signer: Member,
+        msg: &[u8],
+    ) -> crate::signature::Signature {
+        let mut clsag = Clsag::new();
+        for output in &ring[..ring.len() - 1] {
+            clsag.add_member(new_decoy(output, pseudo_out));
+        }
+        clsag.add_member(signer);
+        clsag.sign(msg).unwrap()
+    }
+
+    #[test]
+    fn test_balanced_input_verifies() {
+        let num_decoys = 10;
+        let msg = b"transaction prefix hash";
+
+        let private_key = generate_rand_scalars(1)[0];
+        let input = Opening::random(1000);
+        let output = Output::new(private_key * BASEPOINT, input.commit());
+
+        let pseudo_out = Opening::random(1000);
+        let pseudo_out_commitment = pseudo_out.commit();
+
+        let ring = generate_ring(num_decoys, &output);
+        let signer = new_signer(private_key, &input, &pseudo_out).unwrap();
+        let sig = sign_ringct(&ring, &pseudo_out_commitment, signer, msg);
+
+        // Two key images, one for the output key and one for the commitment to zero
+        assert_eq!(sig.key_images.len(), 2);
+
+        let mut public_keys = ring_keys(&ring, &pseudo_out_commitment);
+        assert!(sig.optimised_verify(&mut public_keys, msg).is_ok());
+        assert!(sig.verify(&mut public_keys, msg).is_ok());
+
+        // The pseudo output together with a transaction's outputs can then be balanced
+        let change = Opening::new(900, pseudo_out.blinding);
+        assert!(pedersen::is_balanced(
+            &[pseudo_out_commitment],
+            &[change.commit()],
+            100
+        ));
+    }
+
+    #[test]
+    fn test_unbalanced_input_fails() {
+        let num_decoys = 10;
+        let msg = b"transaction prefix hash";
+
+        let private_key = generate_rand_scalars(1)[0];
+        let input = Opening::random(1000);
+        let output = Output::new(private_key * BASEPOINT, input.commit());
+
+        // The pseudo output claims more than the input holds
+        let pseudo_out = Opening::random(2000);
+        let pseudo_out_commitment = pseudo_out.commit();
+
+        match new_signer(private_key, &input, &pseudo_out) {
+            Err(Error::AmountMismatch) => {}
+            _ => panic!("expected an amount mismatch error"),
+        }
+
+        // A signer can still sign using the blinding difference directly,
+        // however the signature does not verify against the real commitments
+        let ring = generate_ring(num_decoys, &output);
+        let signer = Member::new_signer(vec![private_key, input.blinding - pseudo_out.blinding]);
+        let sig = sign_ringct(&ring, &pseudo_out_commitment, signer, msg);
+
+        let mut public_keys = ring_keys(&ring, &pseudo_out_commitment);
+        assert!(sig.optimised_verify(&mut public_keys, msg).is_err());
+    }
+
+    #[test]
+    fn test_wrong_pseudo_out_fails() {
+        let num_decoys = 4;
+        let msg = b"transaction prefix hash";
+
+        let private_key = generate_rand_scalars(1)[0];
+        let input = Opening::random(1000);
+        let output = Output::new(private_key * BASEPOINT, input.commit());
+
+        let pseudo_out = Opening::random(1000);
+        let ring = generate_ring(num_decoys, &output);
+        let signer = new_signer(private_key, &input, &pseudo_out).unwrap();
+        let sig = sign_ringct(&ring, &pseudo_out.commit(), signer, msg);
+
+        // Verifying against a different pseudo output commitment must fail
+        let other_pseudo_out = Opening::random(1000).commit();
+        let mut public_keys = ring_keys(&ring, &other_pseudo_out);
+        assert!(sig.optimised_verify(&mut public_keys, msg).is_err());
+    }
+}
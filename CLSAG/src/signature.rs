@@ -1,12 +1,12 @@
 use crate::clsag::calc_aggregation_coefficients;
-use crate::constants::BASEPOINT;
 use crate::member::compute_challenge_ring;
+use crate::ring::{hash_pubkey, PreparedRing};
 use crate::transcript::TranscriptProtocol;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::VartimeMultiscalarMul;
 use merlin::Transcript;
-use sha2::Sha512;
 
 #[derive(Debug)]
 pub struct Signature {
@@ -15,6 +15,7 @@ pub struct Signature {
     pub key_images: Vec<CompressedRistretto>,
 }
 
+#[derive(Debug)]
 pub enum Error {
     // This error occurs if the signature contains an amount of public keys
     // that does not match the number of public keys
@@ -60,7 +61,7 @@ impl Signature {
         let mut challenge = self.challenge.clone();
         for (pub_keys, response) in public_keys.iter().zip(self.responses.iter()) {
             let first_pubkey = pub_keys[0];
-            let hashed_pubkey = RistrettoPoint::hash_from_bytes::<Sha512>(first_pubkey.as_bytes());
+            let hashed_pubkey = hash_pubkey(&first_pubkey);
             challenge = compute_challenge_ring(
                 pub_keys,
                 &challenge,
@@ -94,59 +95,63 @@ impl Signature {
             return Err(Error::IncorrectNumOfPubKeys);
         }
 
+        let ring = PreparedRing::new(public_keys)?;
+
+        self.verify_prepared(&ring, msg)
+    }
+
+    // Verifies the signature against a ring which has already been prepared.
+    // Use this method when many signatures are verified over the same ring,
+    // so that decompressing and hashing the public keys is only done once.
+    pub fn verify_prepared(&self, ring: &PreparedRing, msg: &[u8]) -> Result<(), Error> {
+        // -- Check that we have the correct amount of public keys
+        if ring.len() != self.responses.len() {
+            return Err(Error::IncorrectNumOfPubKeys);
+        }
+
+        let key_images: Vec<RistrettoPoint> = self
+            .key_images
+            .iter()
+            .map(|pt| pt.decompress())
+            .collect::<Option<Vec<RistrettoPoint>>>()
+            .ok_or(Error::BadKeyImages)?;
+
         // Calculate all response * BASEPOINT
         let response_points: Vec<RistrettoPoint> = self
             .responses
             .iter()
-            .map(|response| response * BASEPOINT)
+            .map(|response| &RISTRETTO_BASEPOINT_TABLE * response)
             .collect();
 
         // calculate all response * H(signingKeys)
         let response_hashed_points: Vec<RistrettoPoint> = self
             .responses
             .iter()
-            .zip(public_keys.iter())
-            .map(|(response, pub_keys)| {
-                let first_pubkey = pub_keys[0];
-                let hashed_pubkey =
-                    RistrettoPoint::hash_from_bytes::<Sha512>(first_pubkey.as_bytes());
-
-                response * hashed_pubkey
-            })
+            .zip(ring.hashed_pubkeys.iter())
+            .map(|(response, hashed_pubkey)| response * hashed_pubkey)
             .collect();
 
-        // compute the public key bytes
-        let pubkey_matrix_bytes = self.pubkeys_to_bytes(public_keys);
-
         // Calculate aggregation co-efficients
-        let agg_coeffs = calc_aggregation_coefficients(&pubkey_matrix_bytes, &self.key_images, msg);
+        let agg_coeffs = calc_aggregation_coefficients(&ring.pubkey_matrix, &self.key_images, msg);
 
-        let mut challenge = self.challenge.clone();
+        let mut challenge = self.challenge;
 
         for ((resp_point, resp_hashed_point), pub_keys) in response_points
             .iter()
             .zip(response_hashed_points.iter())
-            .zip(public_keys.iter())
+            .zip(ring.keys.iter())
         {
             let challenge_agg_coeffs: Vec<Scalar> =
-                agg_coeffs.iter().map(|ac| ac * &challenge).collect();
-
-            let mut l_i = RistrettoPoint::optional_multiscalar_mul(
-                &challenge_agg_coeffs,
-                pub_keys.iter().map(|pt| pt.decompress()),
-            )
-            .ok_or(Error::BadPoint)?;
-            l_i = l_i + resp_point;
-
-            let mut r_i = RistrettoPoint::optional_multiscalar_mul(
-                &challenge_agg_coeffs,
-                self.key_images.iter().map(|pt| pt.decompress()),
-            )
-            .ok_or(Error::BadPoint)?;
-            r_i = r_i + resp_hashed_point;
+                agg_coeffs.iter().map(|ac| ac * challenge).collect();
+
+            let l_i = RistrettoPoint::vartime_multiscalar_mul(&challenge_agg_coeffs, pub_keys)
+                + resp_point;
+
+            let r_i = RistrettoPoint::vartime_multiscalar_mul(&challenge_agg_coeffs, &key_images)
+                + resp_hashed_point;
 
             let mut transcript = Transcript::new(b"clsag");
-            transcript.append_message(b"", &pubkey_matrix_bytes);
+            transcript.append_message(b"", &ring.pubkey_matrix);
             transcript.append_point(b"", &l_i);
             transcript.append_point(b"", &r_i);
 
@@ -160,7 +165,7 @@ impl Signature {
         Ok(())
     }
 
-    fn pubkeys_to_bytes(&self, pubkey_matrix: &Vec<Vec<CompressedRistretto>>) -> Vec<u8> {
+    fn pubkeys_to_bytes(&self, pubkey_matrix: &[Vec<CompressedRistretto>]) -> Vec<u8> {
         let mut bytes: Vec<u8> =
             Vec::with_capacity(self.key_images.len() * self.responses.len() * 64);
         for i in 0..pubkey_matrix.len() {
@@ -184,14 +189,21 @@ impl Signature {
 
 #[cfg(test)]
 mod test {
-    use std::time::Instant;
+    use bench_harness::alloc::CountingAlloc;
+    use bench_harness::timing::{black_box, Bench};
+    use bench_harness::{emit, Record, DEFAULT_MESSAGE_LENGTHS, DEFAULT_RING_SIZES};
     extern crate test;
     // use test::Bencher;
 
+    use crate::clsag::prehash;
     use crate::tests_helper::*;
     use rand::seq::SliceRandom;
     use rand::thread_rng;
 
+    // Lets the benchmarks report heap usage alongside their timings
+    #[global_allocator]
+    static ALLOC: CountingAlloc = CountingAlloc;
+
     #[test]
     fn test_verify() {
         let num_keys = 1;
@@ -250,15 +262,28 @@ mod test {
         assert!(sig.optimised_verify(&mut pub_keys, msg).is_err());
     }
 
+    // A ring needs a decoy besides the signer, so smaller ring sizes are skipped
+    fn ring_sizes() -> Vec<usize> {
+        let (sizes, skipped): (Vec<usize>, Vec<usize>) =
+            bench_harness::ring_sizes(&DEFAULT_RING_SIZES)
+                .into_iter()
+                .partition(|&n| n >= 2);
+        if !skipped.is_empty() {
+            eprintln!(
+                "clsag: skipping ring sizes {:?}, which leave no room for a decoy",
+                skipped
+            );
+        }
+        sizes
+    }
+
     #[test]
     fn bench_verify() {
-        println!("verify time");
         let num_keys = 2;
 
-        let ring_sizes = [7,15,31,63,127,255,511,1023];
-
-        for rs in ring_sizes {
-            let num_decoys = rs;
+        // The ring holds the decoys and the signer
+        for ring_size in ring_sizes() {
+            let num_decoys = ring_size - 1;
             let msg = b"hello world";
 
             let mut clsag = generate_clsag_with(num_decoys, num_keys);
@@ -266,86 +291,101 @@ mod test {
             let sig = clsag.sign(msg).unwrap();
             let mut pub_keys = clsag.public_keys();
 
-            let n = 10;
-            
-            let mut times = Vec::with_capacity(n);
-            
-            for _ in 0..n {
-                let start = Instant::now();
-                let _ = sig.optimised_verify(&mut pub_keys, msg);
-                let elapsed = start.elapsed();
-                times.push(elapsed.as_millis() as f64);
-            }
+            assert!(sig.optimised_verify(&mut pub_keys, msg).is_ok());
+            let measurement = Bench::from_env(10).run(|| {
+                black_box(&sig).optimised_verify(black_box(&mut pub_keys), black_box(msg))
+            });
             // b.iter(|| sig.optimised_verify(&mut pub_keys, msg));
 
-            let mean: f64 = times.iter().sum::<f64>() / n as f64;
-            let variance: f64 = times
-                .iter()
-                .map(|t| (t - mean).powi(2))
-                .sum::<f64>()
-                / (n-1) as f64;
-
-            let std_dev: f64 = variance.sqrt();
-
-            println!("ms: {}+{:.3}", mean, std_dev);
+            emit(&Record::measured("clsag", "verify", ring_size, &measurement));
         }
     }
 
     #[test]
     fn bench_sign() {
-        println!("sign time");
         let num_keys = 2;
 
-        let ring_sizes = [7,15,31,63,127,255,511,1023];
-
-        for rs in ring_sizes {
-            let num_decoys = rs;
+        // The ring holds the decoys and the signer
+        for ring_size in ring_sizes() {
+            let num_decoys = ring_size - 1;
             let msg = b"hello world";
             let mut clsag = generate_clsag_with(num_decoys, num_keys);
             clsag.add_member(generate_signer(num_keys));
 
-            let n = 10;
-            
-            let mut times = Vec::with_capacity(n);
-
-            for _ in 0..n {
-                let start = Instant::now();
-                clsag.sign(msg).unwrap();
-                let elapsed = start.elapsed();
-                times.push(elapsed.as_millis() as f64);
-            }
+            let measurement = Bench::from_env(10).run(|| {
+                black_box(&clsag).sign(black_box(msg)).unwrap()
+            });
             // b.iter(|| {
             //     clsag.sign(msg).unwrap()
             // });
 
-            let mean: f64 = times.iter().sum::<f64>() / n as f64;
-            let variance: f64 = times
-                .iter()
-                .map(|t| (t - mean).powi(2))
-                .sum::<f64>()
-                / (n-1) as f64;
-
-            let std_dev: f64 = variance.sqrt();
-
-            println!("ms: {}+{:.3}", mean, std_dev);
+            emit(&Record::measured("clsag", "sign", ring_size, &measurement));
         }
     }
 
     #[test]
     fn bench_size() {
-        println!("proof size");
         let num_keys = 2;
 
-        let ring_sizes = [7,15,31,63,127,255,511,1023];
-
-        for rs in ring_sizes {
-            let num_decoys = rs;
+        // The ring holds the decoys and the signer
+        for ring_size in ring_sizes() {
+            let num_decoys = ring_size - 1;
             let msg = b"hello world";
             let mut clsag = generate_clsag_with(num_decoys, num_keys);
             clsag.add_member(generate_signer(num_keys));
             
             let sig = clsag.sign(msg).unwrap();
-            println!("{}", sig.signature_size());
+            emit(&Record::size("clsag", "size", ring_size, sig.signature_size()));
+        }
+    }
+
+    #[test]
+    fn bench_message_length() {
+        let num_keys = 2;
+
+        // The message is hashed once per aggregation co-efficient, independent of the
+        // ring, so it is swept at the smallest ring size
+        let ring_size = ring_sizes()[0];
+        let mut clsag = generate_clsag_with(ring_size - 1, num_keys);
+        clsag.add_member(generate_signer(num_keys));
+        let ring = clsag.prepared_ring().unwrap();
+
+        for len in bench_harness::message_lengths(&DEFAULT_MESSAGE_LENGTHS) {
+            let msg = vec![1u8; len];
+
+            // Signing a pre-hashed message includes hashing it
+            let sign = Bench::from_env(10).run(|| {
+                black_box(&clsag).sign_with_ring(&ring, black_box(&msg)).unwrap()
+            });
+            let sign_prehashed = Bench::from_env(10).run(|| {
+                black_box(&clsag)
+                    .sign_with_ring(&ring, &prehash(black_box(&msg)))
+                    .unwrap()
+            });
+
+            let sig = clsag.sign_with_ring(&ring, &msg).unwrap();
+            let prehashed_sig = clsag.sign_with_ring(&ring, &prehash(&msg)).unwrap();
+            assert!(sig.verify_prepared(&ring, &msg).is_ok());
+            assert!(prehashed_sig.verify_prepared(&ring, &prehash(&msg)).is_ok());
+
+            let verify = Bench::from_env(10).run(|| {
+                black_box(&sig).verify_prepared(&ring, black_box(&msg))
+            });
+            let verify_prehashed = Bench::from_env(10).run(|| {
+                black_box(&prehashed_sig).verify_prepared(&ring, &prehash(black_box(&msg)))
+            });
+
+            for (operation, measurement) in [
+                ("sign", sign),
+                ("sign_prehashed", sign_prehashed),
+                ("verify", verify),
+                ("verify_prehashed", verify_prehashed),
+            ]
+            .iter()
+            {
+                let record = Record::measured("clsag", operation, ring_size, measurement);
+                emit(&record.with_message_bytes(len));
+            }
         }
     }
 
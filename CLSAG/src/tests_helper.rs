@@ -5,9 +5,6 @@ use crate::member::Member;
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 
-// There is an exact copy of this function in member.rs
-// which is being used to generate nonces. The reason is because the code in this
-// file should only be used for test
 pub fn generate_rand_scalars(num: usize) -> Vec<Scalar> {
     let mut rng = rand::thread_rng();
     let mut scalars = Vec::<Scalar>::with_capacity(num);
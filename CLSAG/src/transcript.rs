@@ -1,7 +1,7 @@
+use crate::ring::hash_pubkey;
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
 use merlin::Transcript;
-use sha2::Sha512;
 
 /// Extension trait to the Merlin transcript API that allows committing scalars and points and
 /// generating challenges as scalars.
@@ -54,7 +54,7 @@ impl TranscriptProtocol for Transcript {
         scalar: &Scalar,
         point: &RistrettoPoint,
     ) {
-        let hashed_point = RistrettoPoint::hash_from_bytes::<Sha512>(point.compress().as_bytes());
+        let hashed_point = hash_pubkey(&point.compress());
         let scalar_hashed_point = scalar * hashed_point;
         self.append_message(label, scalar_hashed_point.compress().as_bytes());
     }
@@ -11,8 +11,11 @@ import (
 	"fmt"
 	stdmath "math"
 	rand2 "math/rand"
+	"os"
 	"privacy-perserving-audit/dory"
 	"privacy-perserving-audit/threshold"
+	"strconv"
+	"strings"
 	"time"
 
 	math "github.com/IBM/mathlib"
@@ -27,7 +30,8 @@ func main() {
 	verification := make(map[int]int)
 	verify_std := make(map[int]float32)
 	sizes := make(map[int]int)
-	for n := 2; n <= 1024; n *= 2 {
+	ringSizes := benchRingSizes()
+	for _, n := range ringSizes {
 		averagePP, averageSigning, averageVerification, averageDualRingDory, averageAppend, size, stdSign, stdVerify := benchmark(n)
 		time.Sleep(time.Second)
 		sizes[n] = size
@@ -42,43 +46,66 @@ func main() {
 	}
 
 	fmt.Println("Sizes:")
-	for n := 2; n <= 1024; n *= 2 {
+	for _, n := range ringSizes {
 		fmt.Printf("(%d, %d)", n, sizes[n])
 	}
 	fmt.Println()
 
 	fmt.Println("Pre-processing:")
-	for n := 2; n <= 1024; n *= 2 {
+	for _, n := range ringSizes {
 		fmt.Printf("(%d, %d)", n, pp[n])
 	}
 	fmt.Println()
 
 	fmt.Println("Signing:")
-	for n := 2; n <= 1024; n *= 2 {
+	for _, n := range ringSizes {
 		fmt.Printf("(%d, %d, %.3f)", n, signing[n], signing_std[n])
 	}
 	fmt.Println()
 
 	fmt.Println("dualring+dory:")
-	for n := 2; n <= 1024; n *= 2 {
+	for _, n := range ringSizes {
 		fmt.Printf("(%d, %d)", n, dualRingDory[n])
 	}
 	fmt.Println()
 
 	fmt.Println("Append tag:")
-	for n := 2; n <= 1024; n *= 2 {
+	for _, n := range ringSizes {
 		fmt.Printf("(%d, %d)", n, appendProcess[n])
 	}
 	fmt.Println()
 
 	fmt.Println("Verify:")
-	for n := 2; n <= 1024; n *= 2 {
+	for _, n := range ringSizes {
 		fmt.Printf("(%d, %d, %.3f)", n, verification[n], verify_std[n])
 	}
 	fmt.Println()
 
 }
 
+// benchRingSizes returns the ring sizes in BENCH_RING_SIZES, a comma separated list set
+// by bench-runner, or the powers of two from 2 to 1024 if it is not set
+func benchRingSizes() []int {
+	env := os.Getenv("BENCH_RING_SIZES")
+	if env == "" {
+		var sizes []int
+		for n := 2; n <= 1024; n *= 2 {
+			sizes = append(sizes, n)
+		}
+		return sizes
+	}
+
+	var sizes []int
+	for _, field := range strings.Split(env, ",") {
+		n, err := strconv.Atoi(strings.TrimSpace(field))
+		if err != nil || n < 1 {
+			panic(fmt.Sprintf("invalid BENCH_RING_SIZES: %s", env))
+		}
+		sizes = append(sizes, n)
+	}
+	return sizes
+}
+
 func StdDev(xs []float32) float32 {
 	if len(xs) == 0 {
 		return 0
@@ -0,0 +1,83 @@
+//! Group types and hashing shared by the Dory, tag and ring signature modules,
+//! as in the Go `common` package.
+
+use ark_bls12_381::{g1, g2, Bls12_381, Fr, G1Projective, G2Projective};
+use ark_ec::hashing::curve_maps::wb::WBMap;
+use ark_ec::hashing::map_to_curve_hasher::MapToCurveBasedHasher;
+use ark_ec::hashing::HashToCurve;
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::CurveGroup;
+use ark_ff::field_hashers::DefaultFieldHasher;
+use ark_ff::{Field, PrimeField};
+use ark_serialize::CanonicalSerialize;
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+pub type G1 = G1Projective;
+pub type G2 = G2Projective;
+/// The pairing target group, written additively
+pub type Gt = PairingOutput<Bls12_381>;
+
+static DST_G1: &[u8] = b"DualDory-V01-CS01-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+static DST_G2: &[u8] = b"DualDory-V01-CS01-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+static H: OnceLock<G1> = OnceLock::new();
+
+/// The second generator of G1 used by the commitments, with no known discrete log
+pub fn h() -> G1 {
+    *H.get_or_init(|| hash_to_g1(&sha256(&[b"DualDory"])))
+}
+
+pub fn hash_to_g1(msg: &[u8]) -> G1 {
+    MapToCurveBasedHasher::<G1, DefaultFieldHasher<Sha256, 128>, WBMap<g1::Config>>::new(DST_G1)
+        .and_then(|hasher| hasher.hash(msg))
+        .expect("hashing to G1 cannot fail")
+        .into()
+}
+
+pub fn hash_to_g2(msg: &[u8]) -> G2 {
+    MapToCurveBasedHasher::<G2, DefaultFieldHasher<Sha256, 128>, WBMap<g2::Config>>::new(DST_G2)
+        .and_then(|hasher| hasher.hash(msg))
+        .expect("hashing to G2 cannot fail")
+        .into()
+}
+
+pub fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut h = Sha256::new();
+    for part in parts {
+        h.update(part);
+    }
+    h.finalize().into()
+}
+
+/// Reduces a SHA-256 digest to a scalar
+pub fn scalar_from_digest(digest: &[u8; 32]) -> Fr {
+    Fr::from_be_bytes_mod_order(digest)
+}
+
+pub fn hash_to_scalar(parts: &[&[u8]]) -> Fr {
+    scalar_from_digest(&sha256(parts))
+}
+
+pub fn inverse(x: Fr) -> Fr {
+    x.inverse().expect("challenges are nonzero")
+}
+
+/// Compressed serialization of a group element, scalar or vector of them
+pub fn to_bytes<T: CanonicalSerialize + ?Sized>(x: &T) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(x.compressed_size());
+    x.serialize_compressed(&mut bytes)
+        .expect("serializing to a Vec cannot fail");
+    bytes
+}
+
+pub fn pairing(g1: G1, g2: G2) -> Gt {
+    Bls12_381::pairing(g1, g2)
+}
+
+/// ⟨v₁, v₂⟩, the product of the pairings of corresponding elements
+pub fn inner_prod(v1: &[G1], v2: &[G2]) -> Gt {
+    assert_eq!(v1.len(), v2.len(), "length mismatch");
+    assert!(!v1.is_empty(), "empty vectors");
+    Bls12_381::multi_pairing(G1::normalize_batch(v1), G2::normalize_batch(v2))
+}
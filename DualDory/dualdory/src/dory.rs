@@ -0,0 +1,356 @@
+//! The Dory inner-product argument, as in the Go `dory` package.
+//!
+//! A prover shows it knows vectors v₁ ∈ G1ⁿ and v₂ ∈ G2ⁿ opening a commitment
+//! C = ⟨v₁, v₂⟩, D₁ = ⟨v₁, Γ₂⟩, D₂ = ⟨Γ₁, v₂⟩ to public generators Γ₁ and Γ₂.
+//! Each round halves n, so a proof is log₂ n rounds of six elements of Gt followed
+//! by the final elements of G1 and G2.
+
+use crate::common::*;
+use ark_bls12_381::Fr;
+use ark_std::UniformRand;
+
+/// The public parameters of one round, for vectors of length n.
+/// Generated for every n from the ring size down to 1 by [`generate_public_params`]
+#[derive(Clone, Debug)]
+pub struct PublicParams {
+    pub gamma1: Vec<G1>,
+    pub gamma2: Vec<G2>,
+    /// ⟨Γ₁, Γ₂⟩
+    pub chi: Gt,
+    reduce: Option<ReduceParams>,
+    digest: [u8; 32],
+}
+
+// The cross terms with the next round's generators Γ'₁ and Γ'₂, absent when n = 1
+#[derive(Clone, Debug)]
+struct ReduceParams {
+    delta_1l: Gt,
+    delta_1r: Gt,
+    delta_2l: Gt,
+    delta_2r: Gt,
+}
+
+impl PublicParams {
+    /// Hash of these parameters and those of every round before them
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+}
+
+/// Generates the public parameters for vectors of length `n`, which must be a power of two
+pub fn generate_public_params(n: usize) -> Vec<PublicParams> {
+    assert!(
+        n.is_power_of_two(),
+        "Dory needs a power of two length, got {}",
+        n
+    );
+
+    let mut pps: Vec<PublicParams> = Vec::new();
+    let (mut gamma1, mut gamma2) = (random_g1_vector(n), random_g2_vector(n));
+    loop {
+        let m = gamma1.len() / 2;
+        let (next1, next2) = (random_g1_vector(m), random_g2_vector(m));
+        let chi = inner_prod(&gamma1, &gamma2);
+
+        let reduce = if m == 0 {
+            None
+        } else {
+            Some(ReduceParams {
+                delta_1l: inner_prod(&gamma1[..m], &next2),
+                delta_1r: inner_prod(&gamma1[m..], &next2),
+                delta_2l: inner_prod(&next1, &gamma2[..m]),
+                delta_2r: inner_prod(&next1, &gamma2[m..]),
+            })
+        };
+
+        let prev = pps.last().map_or(&[][..], |pp| &pp.digest[..]);
+        let reduce_digest = reduce.as_ref().map(|r| {
+            sha256(&[
+                &to_bytes(&next1),
+                &to_bytes(&next2),
+                &to_bytes(&r.delta_1r),
+                &to_bytes(&r.delta_1l),
+                &to_bytes(&r.delta_2r),
+                &to_bytes(&r.delta_2l),
+            ])
+        });
+        let digest = sha256(&[
+            prev,
+            reduce_digest.as_ref().map_or(&[][..], |d| &d[..]),
+            &to_bytes(&chi),
+            &to_bytes(&gamma1),
+            &to_bytes(&gamma2),
+        ]);
+
+        pps.push(PublicParams {
+            gamma1,
+            gamma2,
+            chi,
+            reduce,
+            digest,
+        });
+        if m == 0 {
+            return pps;
+        }
+        gamma1 = next1;
+        gamma2 = next2;
+    }
+}
+
+// Generators are hashed from their position, so every party derives the same ones
+fn random_g1_vector(n: usize) -> Vec<G1> {
+    (0..n).map(|i| hash_to_g1(&generator_seed(n, i))).collect()
+}
+
+fn random_g2_vector(n: usize) -> Vec<G2> {
+    (0..n).map(|i| hash_to_g2(&generator_seed(n, i))).collect()
+}
+
+fn generator_seed(n: usize, i: usize) -> [u8; 32] {
+    sha256(&[
+        b"Dory",
+        &(n as u64).to_le_bytes(),
+        &(i as u64).to_le_bytes(),
+    ])
+}
+
+/// The committed vectors
+#[derive(Clone, Debug)]
+pub struct Witness {
+    pub v1: Vec<G1>,
+    pub v2: Vec<G2>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Commitment {
+    pub c: Gt,
+    pub d1: Gt,
+    pub d2: Gt,
+}
+
+/// Commits to the vectors of a witness
+pub fn commit(pp: &PublicParams, witness: &Witness) -> Commitment {
+    Commitment {
+        c: inner_prod(&witness.v1, &witness.v2),
+        d1: inner_prod(&witness.v1, &pp.gamma2),
+        d2: inner_prod(&pp.gamma1, &witness.v2),
+    }
+}
+
+// The prover's messages of one round, the cross terms with the next round's generators
+// before the verifier's challenge β and those of the folded vectors after it
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Round {
+    d1l: Gt,
+    d1r: Gt,
+    d2l: Gt,
+    d2r: Gt,
+    c_plus: Gt,
+    c_minus: Gt,
+}
+
+/// A non-interactive Dory proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    rounds: Vec<Round>,
+    e1: G1,
+    e2: G2,
+}
+
+impl Proof {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for round in &self.rounds {
+            for gt in &[
+                round.d1l,
+                round.d1r,
+                round.d2l,
+                round.d2r,
+                round.c_plus,
+                round.c_minus,
+            ] {
+                bytes.extend(to_bytes(gt));
+            }
+        }
+        bytes.extend(to_bytes(&self.e1));
+        bytes.extend(to_bytes(&self.e2));
+        bytes
+    }
+
+    /// Hash of the proof, which the tag proof binds to
+    pub fn digest(&self) -> [u8; 32] {
+        sha256(&[&self.to_bytes()])
+    }
+}
+
+// β := H(params, D₁L, D₁R, D₂L, D₂R, C, D₁, D₂), also returning the digest α chains from
+fn beta(pp: &PublicParams, cmt: &Commitment, cross: [&Gt; 4]) -> ([u8; 32], Fr) {
+    let digest = sha256(&[
+        &pp.digest,
+        &to_bytes(cross[0]),
+        &to_bytes(cross[1]),
+        &to_bytes(cross[2]),
+        &to_bytes(cross[3]),
+        &to_bytes(&cmt.c),
+        &to_bytes(&cmt.d1),
+        &to_bytes(&cmt.d2),
+    ]);
+    (digest, scalar_from_digest(&digest))
+}
+
+// α := H(C₊, C₋, β digest)
+fn alpha(beta_digest: &[u8; 32], c_plus: &Gt, c_minus: &Gt) -> Fr {
+    hash_to_scalar(&[&to_bytes(c_plus), &to_bytes(c_minus), beta_digest])
+}
+
+// The commitment to the folded vectors, which both sides compute
+fn fold(pp: &PublicParams, cmt: &Commitment, round: &Round, alpha: Fr, beta: Fr) -> Commitment {
+    let r = pp
+        .reduce
+        .as_ref()
+        .expect("only the last round has no reduce params");
+    let (alpha_inv, beta_inv) = (inverse(alpha), inverse(beta));
+    Commitment {
+        c: cmt.c
+            + pp.chi
+            + cmt.d2 * beta
+            + cmt.d1 * beta_inv
+            + round.c_plus * alpha
+            + round.c_minus * alpha_inv,
+        d1: round.d1l * alpha + round.d1r + r.delta_1l * (alpha * beta) + r.delta_1r * beta,
+        d2: round.d2l * alpha_inv
+            + round.d2r
+            + r.delta_2l * (alpha_inv * beta_inv)
+            + r.delta_2r * beta_inv,
+    }
+}
+
+/// Proves knowledge of the witness opening `commitment`
+pub fn reduce(pps: &[PublicParams], witness: Witness, commitment: Commitment) -> Proof {
+    assert_eq!(
+        witness.v1.len(),
+        pps[0].gamma1.len(),
+        "witness length mismatch"
+    );
+    let Witness { mut v1, mut v2 } = witness;
+    let mut cmt = commitment;
+    let mut rounds = Vec::with_capacity(pps.len() - 1);
+
+    for (pp, next) in pps.iter().zip(&pps[1..]) {
+        let m = v1.len() / 2;
+
+        // P → V
+        let d1l = inner_prod(&v1[..m], &next.gamma2);
+        let d1r = inner_prod(&v1[m..], &next.gamma2);
+        let d2l = inner_prod(&next.gamma1, &v2[..m]);
+        let d2r = inner_prod(&next.gamma1, &v2[m..]);
+
+        // V → P
+        let (beta_digest, beta) = beta(pp, &cmt, [&d1l, &d1r, &d2l, &d2r]);
+        let beta_inv = inverse(beta);
+
+        // P: v₁ += βΓ₁, v₂ += β⁻¹Γ₂
+        for (v, g) in v1.iter_mut().zip(&pp.gamma1) {
+            *v += *g * beta;
+        }
+        for (v, g) in v2.iter_mut().zip(&pp.gamma2) {
+            *v += *g * beta_inv;
+        }
+
+        // P → V
+        let c_plus = inner_prod(&v1[..m], &v2[m..]);
+        let c_minus = inner_prod(&v1[m..], &v2[..m]);
+
+        // V → P
+        let alpha = alpha(&beta_digest, &c_plus, &c_minus);
+        let alpha_inv = inverse(alpha);
+
+        let round = Round {
+            d1l,
+            d1r,
+            d2l,
+            d2r,
+            c_plus,
+            c_minus,
+        };
+        cmt = fold(pp, &cmt, &round, alpha, beta);
+        rounds.push(round);
+
+        // P: v₁' := αv₁L + v₁R, v₂' := α⁻¹v₂L + v₂R
+        v1 = (0..m).map(|i| v1[i] * alpha + v1[m + i]).collect();
+        v2 = (0..m).map(|i| v2[i] * alpha_inv + v2[m + i]).collect();
+    }
+
+    Proof {
+        rounds,
+        e1: v1[0],
+        e2: v2[0],
+    }
+}
+
+/// Verifies a proof for `commitment`
+pub fn verify_reduce(pps: &[PublicParams], commitment: &Commitment, proof: &Proof) -> bool {
+    if proof.rounds.len() + 1 != pps.len() {
+        return false;
+    }
+
+    let mut cmt = *commitment;
+    for (pp, round) in pps.iter().zip(&proof.rounds) {
+        let (beta_digest, beta) = beta(pp, &cmt, [&round.d1l, &round.d1r, &round.d2l, &round.d2r]);
+        let alpha = alpha(&beta_digest, &round.c_plus, &round.c_minus);
+        cmt = fold(pp, &cmt, round, alpha, beta);
+    }
+
+    // The scalar product check for n = 1 with a random challenge d:
+    // e(E₁ + dΓ₁, E₂ + d⁻¹Γ₂) = χ + C + dD₂ + d⁻¹D₁
+    let pp = pps.last().expect("at least one round of params");
+    let d = Fr::rand(&mut rand::thread_rng());
+    let d_inv = inverse(d);
+    let left = pairing(proof.e1 + pp.gamma1[0] * d, proof.e2 + pp.gamma2[0] * d_inv);
+    left == pp.chi + cmt.c + cmt.d2 * d + cmt.d1 * d_inv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::Group;
+
+    fn witness(n: usize) -> Witness {
+        let mut rng = rand::thread_rng();
+        Witness {
+            v1: (0..n)
+                .map(|_| G1::generator() * Fr::rand(&mut rng))
+                .collect(),
+            v2: (0..n)
+                .map(|_| G2::generator() * Fr::rand(&mut rng))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn reduce_and_verify() {
+        for n in [1, 2, 8] {
+            let pps = generate_public_params(n);
+            assert_eq!(pps.len(), n.trailing_zeros() as usize + 1);
+
+            let w = witness(n);
+            let cmt = commit(&pps[0], &w);
+            let proof = reduce(&pps, w, cmt);
+            assert!(verify_reduce(&pps, &cmt, &proof));
+
+            let wrong = Commitment {
+                c: cmt.c + cmt.d1,
+                ..cmt
+            };
+            assert!(!verify_reduce(&pps, &wrong, &proof));
+        }
+    }
+
+    #[test]
+    fn reject_other_witness() {
+        let pps = generate_public_params(4);
+        let cmt = commit(&pps[0], &witness(4));
+        let proof = reduce(&pps, witness(4), cmt);
+        assert!(!verify_reduce(&pps, &cmt, &proof));
+    }
+}
@@ -0,0 +1,15 @@
+//! DualDory threshold ring signatures, ported from the Go implementation in `DualDory/`.
+//!
+//! The modules follow the Go packages: [`dory`] is the Dory inner-product argument,
+//! [`tag`] the tags and their proofs, and [`threshold`] the ring signature with its
+//! `sign`, `verify` and `verify_threshold_signatures` operations. The Go version runs
+//! on BN254 with IBM mathlib, while this runs on BLS12-381 with arkworks.
+
+pub mod common;
+pub mod dory;
+pub mod tag;
+pub mod threshold;
+
+pub use threshold::{
+    key_gen, verify_threshold_signatures, Error, PrivateKey, PublicKey, PublicParams, RingSignature,
+};
@@ -0,0 +1,142 @@
+//! Tags and the proof that a tag matches a committed key, as in the Go `tag` package.
+//!
+//! The tag of a key x for a prefix is x·H_p(prefix), the same for every signature by
+//! that key with that prefix, so signatures by one signer can be counted once.
+
+use crate::common::*;
+use ark_bls12_381::Fr;
+use ark_ec::Group;
+use ark_std::UniformRand;
+use rand::{CryptoRng, RngCore};
+
+/// Commits to a private key as xG + rH, returning the blinding r and the commitment
+pub fn commit<R: RngCore + CryptoRng>(rng: &mut R, sk: &Fr) -> (Fr, G1) {
+    let r = Fr::rand(rng);
+    (r, G1::generator() * sk + h() * r)
+}
+
+/// The tag of a private key for a prefix
+pub fn tag(sk: &Fr, prefix: &[u8]) -> G1 {
+    prefix_point(prefix) * sk
+}
+
+fn prefix_point(prefix: &[u8]) -> G1 {
+    hash_to_g1(&sha256(&[prefix]))
+}
+
+/// A Schnorr proof that a tag and a commitment are for the same private key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    a_point: G1,
+    b_point: G1,
+    a: Fr,
+    b: Fr,
+}
+
+// c := H(A, B, T, com, prefix, context...). Without T, a prover could pick A = γH_p(prefix)
+// for any γ and then solve for a tag other than xH_p(prefix) which still verifies
+fn challenge(
+    a_point: &G1,
+    b_point: &G1,
+    tag: &G1,
+    com: &G1,
+    prefix: &[u8],
+    context: &[&[u8]],
+) -> Fr {
+    let points = [a_point, b_point, tag, com].map(to_bytes);
+    let mut parts: Vec<&[u8]> = points.iter().map(Vec::as_slice).collect();
+    parts.push(prefix);
+    parts.extend_from_slice(context);
+    hash_to_scalar(&parts)
+}
+
+impl Proof {
+    /// Proves the tag of `sk` for `prefix` matches the commitment with blinding `r`,
+    /// binding the proof to `context`
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        prefix: &[u8],
+        sk: &Fr,
+        r: &Fr,
+        context: &[&[u8]],
+    ) -> Proof {
+        let (ar, br) = (Fr::rand(rng), Fr::rand(rng));
+        let a_point = prefix_point(prefix) * ar;
+        let b_point = G1::generator() * ar + h() * br;
+
+        let (t, com) = (tag(sk, prefix), G1::generator() * sk + h() * r);
+        let c = challenge(&a_point, &b_point, &t, &com, prefix, context);
+        Proof {
+            a_point,
+            b_point,
+            a: ar + *sk * c,
+            b: br + *r * c,
+        }
+    }
+
+    pub fn verify(&self, tag: &G1, com: &G1, prefix: &[u8], context: &[&[u8]]) -> bool {
+        let c = challenge(&self.a_point, &self.b_point, tag, com, prefix, context);
+
+        // aH_p(prefix) = A + cT and aG + bH = B + c·com
+        prefix_point(prefix) * self.a == self.a_point + *tag * c
+            && G1::generator() * self.a + h() * self.b == self.b_point + *com * c
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            to_bytes(&self.a_point),
+            to_bytes(&self.b_point),
+            to_bytes(&self.a),
+            to_bytes(&self.b),
+        ]
+        .concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prove_and_verify() {
+        let mut rng = rand::thread_rng();
+        let sk = Fr::rand(&mut rng);
+        let (r, com) = commit(&mut rng, &sk);
+        let t = tag(&sk, b"prefix");
+
+        let proof = Proof::new(&mut rng, b"prefix", &sk, &r, &[b"msg"]);
+        assert!(proof.verify(&t, &com, b"prefix", &[b"msg"]));
+        assert!(!proof.verify(&t, &com, b"prefix", &[b"other msg"]));
+        assert!(!proof.verify(&t, &com, b"other prefix", &[b"msg"]));
+        assert!(!proof.verify(
+            &tag(&Fr::rand(&mut rng), b"prefix"),
+            &com,
+            b"prefix",
+            &[b"msg"]
+        ));
+    }
+
+    #[test]
+    fn rejects_tags_solved_for_after_the_challenge() {
+        let mut rng = rand::thread_rng();
+        let sk = Fr::rand(&mut rng);
+        let (r, com) = commit(&mut rng, &sk);
+        let t = tag(&sk, b"prefix");
+
+        // A = γH_p(prefix) with γ ≠ α_r, then T = ((α − γ)/c + x)H_p(prefix)
+        let (ar, br, gamma) = (Fr::rand(&mut rng), Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let a_point = prefix_point(b"prefix") * gamma;
+        let b_point = G1::generator() * ar + h() * br;
+        let c = challenge(&a_point, &b_point, &t, &com, b"prefix", &[b"msg"]);
+        let forged = prefix_point(b"prefix") * ((ar - gamma) / c + sk);
+        assert_ne!(forged, t);
+
+        let proof = Proof {
+            a_point,
+            b_point,
+            a: ar + sk * c,
+            b: br + r * c,
+        };
+        assert!(!proof.verify(&forged, &com, b"prefix", &[b"msg"]));
+    }
+}
@@ -0,0 +1,423 @@
+//! DualDory threshold ring signatures, as in the Go `threshold` package.
+//!
+//! A signature is a DualRing style proof that the signer's committed key is one of the
+//! ring's, with its two inner products proven by Dory, and a tag proof binding the
+//! commitment to the signer's tag and the message. A threshold of signatures is valid
+//! if every signature verifies and their tags are distinct.
+
+use crate::common::*;
+use crate::dory::{self, Commitment, Proof, Witness};
+use crate::tag;
+use ark_bls12_381::Fr;
+use ark_ec::Group;
+use ark_std::UniformRand;
+use rand::{CryptoRng, RngCore};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey(pub G1);
+
+impl PublicKey {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(&self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrivateKey(Fr);
+
+impl PrivateKey {
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(G1::generator() * self.0)
+    }
+}
+
+/// Generates a random key pair
+pub fn key_gen<R: RngCore + CryptoRng>(rng: &mut R) -> (PublicKey, PrivateKey) {
+    let sk = PrivateKey(Fr::rand(rng));
+    (sk.public_key(), sk)
+}
+
+/// The Dory parameters and the values derived from the ring that signing and verifying share
+#[derive(Clone, Debug)]
+pub struct PublicParams {
+    pub pre_processed: PreProcessedParams,
+    pub dory: Vec<dory::PublicParams>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PreProcessedParams {
+    digest: [u8; 32],
+    /// -⟨ring, Γ₂⟩
+    a0_inverse: Gt,
+    /// ⟨H₁, Γ₂⟩
+    d: Gt,
+    /// ΣΓ₂
+    gamma2: G2,
+    /// H repeated once for each member of the ring
+    h1: Vec<G1>,
+}
+
+impl PublicParams {
+    /// Generates the Dory parameters for the ring's size and pre-processes the ring
+    pub fn new(ring: &[PublicKey]) -> PublicParams {
+        let dory = dory::generate_public_params(ring.len());
+        PublicParams {
+            pre_processed: compute_pre_processed_params(&dory, ring),
+            dory,
+        }
+    }
+}
+
+pub fn compute_pre_processed_params(
+    dory: &[dory::PublicParams],
+    ring: &[PublicKey],
+) -> PreProcessedParams {
+    let pp = &dory[0];
+    let a0_inverse = -inner_prod(&ring_points(ring), &pp.gamma2);
+    let h1 = vec![h(); ring.len()];
+    let d = inner_prod(&h1, &pp.gamma2);
+    let gamma2 = pp.gamma2.iter().sum::<G2>();
+
+    let digest = sha256(&[
+        &to_bytes(&d),
+        &to_bytes(&a0_inverse),
+        &to_bytes(&gamma2),
+        &to_bytes(&h1),
+        dory.last().expect("at least one round of params").digest(),
+    ]);
+    PreProcessedParams {
+        digest,
+        a0_inverse,
+        d,
+        gamma2,
+        h1,
+    }
+}
+
+fn ring_points(ring: &[PublicKey]) -> Vec<G1> {
+    ring.iter().map(|pk| pk.0).collect()
+}
+
+/// The ring membership part of a signature, which can be computed before the message is known
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RingProof {
+    tag_commitment: G1,
+    dory_proof1: Proof,
+    dory_proof2: Proof,
+    b: Gt,
+    z: Fr,
+    y: G1,
+}
+
+/// A signature by one member of the ring
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RingSignature {
+    ring_proof: RingProof,
+    tag_value: G1,
+    tag_proof: tag::Proof,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Several of the signatures have the same tag
+    DuplicateSigners {
+        distinct: usize,
+        total: usize,
+    },
+    FirstDoryProof,
+    SecondDoryProof,
+    TagProof,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::DuplicateSigners { distinct, total } => write!(
+                f,
+                "signature set was signed by {} out of {} distinct signers",
+                distinct, total
+            ),
+            Error::FirstDoryProof => write!(f, "first Dory proof invalid"),
+            Error::SecondDoryProof => write!(f, "second Dory proof invalid"),
+            Error::TagProof => write!(f, "tag proof invalid"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// h := H(A, Y, params)
+fn challenge(a: &Gt, y: &G1, pp: &PublicParams) -> Fr {
+    hash_to_scalar(&[&to_bytes(a), &to_bytes(y), &pp.pre_processed.digest])
+}
+
+impl PrivateKey {
+    /// Signs a message for the prefix on behalf of the ring, which must contain this key
+    pub fn sign<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pp: &PublicParams,
+        msg: &[u8],
+        prefix: &[u8],
+        ring: &[PublicKey],
+    ) -> RingSignature {
+        let (r, ring_proof) = self.pre_process_ring_proof(rng, pp, ring);
+        self.append_tag_proof(rng, ring_proof, &r, msg, prefix)
+    }
+
+    /// Commits to this key and proves the commitment is to a member of the ring,
+    /// returning the commitment's blinding with the proof
+    pub fn pre_process_ring_proof<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pp: &PublicParams,
+        ring: &[PublicKey],
+    ) -> (Fr, RingProof) {
+        let (r, com) = tag::commit(rng, &self.0);
+        (r, self.ring_proof(rng, pp, ring, &r, com))
+    }
+
+    /// Completes a ring proof into a signature on the message
+    pub fn append_tag_proof<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        ring_proof: RingProof,
+        r: &Fr,
+        msg: &[u8],
+        prefix: &[u8],
+    ) -> RingSignature {
+        let (digest1, digest2) = (
+            ring_proof.dory_proof1.digest(),
+            ring_proof.dory_proof2.digest(),
+        );
+        let tag_proof = tag::Proof::new(rng, prefix, &self.0, r, &[msg, &digest1, &digest2]);
+        RingSignature {
+            ring_proof,
+            tag_value: tag::tag(&self.0, prefix),
+            tag_proof,
+        }
+    }
+
+    /// Proves `com` commits to the key of a ring member with blinding `r`
+    pub fn ring_proof<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pp: &PublicParams,
+        ring: &[PublicKey],
+        r: &Fr,
+        com: G1,
+    ) -> RingProof {
+        let n = ring.len();
+        let signer = ring
+            .iter()
+            .position(|pk| *pk == self.public_key())
+            .expect("PK not found within ring");
+        let pre = &pp.pre_processed;
+
+        // A := e(com, ΣΓ₂) - ⟨ring, Γ₂⟩
+        let a = pairing(com, pre.gamma2) + pre.a0_inverse;
+
+        // Y := yH + Σᵢ cᵢ(Pᵢ - com) over every member but the signer
+        let y = Fr::rand(rng);
+        let mut c: Vec<Fr> = (0..n).map(|_| Fr::rand(rng)).collect();
+        let big_y = ring
+            .iter()
+            .zip(&c)
+            .enumerate()
+            .filter(|(i, _)| *i != signer)
+            .fold(h() * y, |acc, (_, (pk, ci))| acc + (pk.0 - com) * ci);
+
+        // The signer's cⱼ makes the challenges sum to h
+        let challenge = challenge(&a, &big_y, pp);
+        c[signer] = Fr::from(0u64);
+        c[signer] = challenge - c.iter().sum::<Fr>();
+        let z = y + c[signer] * r;
+
+        let g2c: Vec<G2> = c.iter().map(|ci| G2::generator() * ci).collect();
+        let b = inner_prod(&pp.dory[0].gamma1, &g2c);
+
+        // C := e(zH - Y, G₂) and E := e(hH, G₂)
+        let cmt1 = Commitment {
+            c: pairing(h() * z - big_y, G2::generator()),
+            d1: a,
+            d2: b,
+        };
+        let w1 = Witness {
+            v1: ring.iter().map(|pk| com - pk.0).collect(),
+            v2: g2c.clone(),
+        };
+        let cmt2 = Commitment {
+            c: pairing(h() * challenge, G2::generator()),
+            d1: pre.d,
+            d2: b,
+        };
+        let w2 = Witness {
+            v1: pre.h1.clone(),
+            v2: g2c,
+        };
+
+        let (dory_proof1, dory_proof2) = rayon::join(
+            || dory::reduce(&pp.dory, w1, cmt1),
+            || dory::reduce(&pp.dory, w2, cmt2),
+        );
+
+        RingProof {
+            tag_commitment: com,
+            dory_proof1,
+            dory_proof2,
+            b,
+            z,
+            y: big_y,
+        }
+    }
+}
+
+impl RingSignature {
+    /// Verifies this signature on a message for the prefix
+    pub fn verify(&self, pp: &PublicParams, msg: &[u8], prefix: &[u8]) -> Result<(), Error> {
+        let proof = &self.ring_proof;
+        let pre = &pp.pre_processed;
+
+        let a = pairing(proof.tag_commitment, pre.gamma2) + pre.a0_inverse;
+        let c = pairing(h() * proof.z - proof.y, G2::generator());
+        let challenge = challenge(&a, &proof.y, pp);
+        let e = pairing(h() * challenge, G2::generator());
+
+        let (valid1, valid2) = rayon::join(
+            || {
+                let cmt = Commitment {
+                    c,
+                    d1: a,
+                    d2: proof.b,
+                };
+                dory::verify_reduce(&pp.dory, &cmt, &proof.dory_proof1)
+            },
+            || {
+                let cmt = Commitment {
+                    c: e,
+                    d1: pre.d,
+                    d2: proof.b,
+                };
+                dory::verify_reduce(&pp.dory, &cmt, &proof.dory_proof2)
+            },
+        );
+        if !valid1 {
+            return Err(Error::FirstDoryProof);
+        }
+        if !valid2 {
+            return Err(Error::SecondDoryProof);
+        }
+
+        let (digest1, digest2) = (proof.dory_proof1.digest(), proof.dory_proof2.digest());
+        if !self.tag_proof.verify(
+            &self.tag_value,
+            &proof.tag_commitment,
+            prefix,
+            &[msg, &digest1, &digest2],
+        ) {
+            return Err(Error::TagProof);
+        }
+        Ok(())
+    }
+
+    /// The tag, which is the same for every signature by one key for one prefix
+    pub fn tag(&self) -> Vec<u8> {
+        to_bytes(&self.tag_value)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let proof = &self.ring_proof;
+        [
+            to_bytes(&self.tag_value),
+            to_bytes(&proof.tag_commitment),
+            self.tag_proof.to_bytes(),
+            to_bytes(&proof.b),
+            to_bytes(&proof.y),
+            to_bytes(&proof.z),
+            proof.dory_proof1.to_bytes(),
+            proof.dory_proof2.to_bytes(),
+        ]
+        .concat()
+    }
+}
+
+/// Verifies signatures on a message for the prefix, which must all be by different signers
+pub fn verify_threshold_signatures(
+    pp: &PublicParams,
+    msg: &[u8],
+    prefix: &[u8],
+    signatures: &[RingSignature],
+) -> Result<(), Error> {
+    let tags: HashSet<Vec<u8>> = signatures.iter().map(RingSignature::tag).collect();
+    if tags.len() != signatures.len() {
+        return Err(Error::DuplicateSigners {
+            distinct: tags.len(),
+            total: signatures.len(),
+        });
+    }
+
+    signatures
+        .par_iter()
+        .try_for_each(|signature| signature.verify(pp, msg, prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threshold_ring_signature() {
+        let mut rng = rand::thread_rng();
+        let (pk1, sk1) = key_gen(&mut rng);
+        let (pk2, sk2) = key_gen(&mut rng);
+        let (pk3, _) = key_gen(&mut rng);
+        let (pk4, _) = key_gen(&mut rng);
+        let ring = [pk1, pk2, pk3, pk4];
+        let pp = PublicParams::new(&ring);
+
+        let msg = b"message";
+        let prefix = [1, 2, 3];
+        let sig1 = sk1.sign(&mut rng, &pp, msg, &prefix, &ring);
+        let sig2 = sk2.sign(&mut rng, &pp, msg, &prefix, &ring);
+
+        assert_eq!(
+            verify_threshold_signatures(&pp, msg, &prefix, &[sig1.clone(), sig2.clone()]),
+            Ok(())
+        );
+        assert_eq!(
+            verify_threshold_signatures(&pp, msg, &prefix, &[sig1.clone(), sig1.clone()])
+                .unwrap_err()
+                .to_string(),
+            "signature set was signed by 1 out of 2 distinct signers"
+        );
+        assert_eq!(
+            sig1.verify(&pp, b"other message", &prefix),
+            Err(Error::TagProof)
+        );
+        assert_eq!(sig1.verify(&pp, msg, &[4, 5, 6]), Err(Error::TagProof));
+    }
+
+    #[test]
+    fn pre_processed_ring_proof() {
+        let mut rng = rand::thread_rng();
+        let keys: Vec<_> = (0..2).map(|_| key_gen(&mut rng)).collect();
+        let ring: Vec<_> = keys.iter().map(|(pk, _)| *pk).collect();
+        let pp = PublicParams::new(&ring);
+        let sk = &keys[1].1;
+
+        let (r, ring_proof) = sk.pre_process_ring_proof(&mut rng, &pp, &ring);
+        let sig = sk.append_tag_proof(&mut rng, ring_proof, &r, b"message", b"prefix");
+        assert_eq!(sig.verify(&pp, b"message", b"prefix"), Ok(()));
+
+        // A ring the signer is not in does not verify
+        let (other, _) = key_gen(&mut rng);
+        let other_pp = PublicParams::new(&[ring[0], other]);
+        assert_eq!(
+            sig.verify(&other_pp, b"message", b"prefix"),
+            Err(Error::FirstDoryProof)
+        );
+    }
+}
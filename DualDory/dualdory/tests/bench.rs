@@ -0,0 +1,82 @@
+use bench_harness::alloc::CountingAlloc;
+use bench_harness::timing::{black_box, Bench};
+use bench_harness::{emit, Record};
+use dualdory::*;
+use rand::rngs::OsRng;
+
+// Lets the benchmarks report heap usage alongside their timings
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+// The ring sizes of the Go benchmarks
+const RING_SIZES: [usize; 10] = [2, 4, 8, 16, 32, 64, 128, 256, 512, 1024];
+
+// Dory halves the ring each round, so only powers of two are benchmarked
+fn ring_sizes() -> Vec<usize> {
+    let (sizes, skipped): (Vec<usize>, Vec<usize>) = bench_harness::ring_sizes(&RING_SIZES)
+        .into_iter()
+        .partition(|n| n.is_power_of_two());
+    if !skipped.is_empty() {
+        eprintln!(
+            "dualdory-rust: skipping ring sizes {:?}, which are not powers of two",
+            skipped
+        );
+    }
+    sizes
+}
+
+fn setup(n: usize) -> (Vec<PrivateKey>, Vec<PublicKey>, PublicParams) {
+    let (ring, privkeys): (Vec<PublicKey>, Vec<PrivateKey>) =
+        (0..n).map(|_| key_gen(&mut OsRng)).unzip();
+    let pp = PublicParams::new(&ring);
+    (privkeys, ring, pp)
+}
+
+#[test]
+fn sign_bench() {
+    for n in ring_sizes() {
+        let (privkeys, ring, pp) = setup(n);
+
+        let measurement = Bench::from_env(10).run(|| {
+            privkeys[0].sign(
+                &mut OsRng,
+                &pp,
+                black_box(b"message"),
+                b"prefix",
+                black_box(&ring),
+            )
+        });
+        emit(&Record::measured("dualdory-rust", "sign", n, &measurement));
+    }
+}
+
+#[test]
+fn verify_bench() {
+    for n in ring_sizes() {
+        let (privkeys, ring, pp) = setup(n);
+        let sig = privkeys[0].sign(&mut OsRng, &pp, b"message", b"prefix", &ring);
+
+        let measurement = Bench::from_env(10)
+            .run(|| assert_eq!(black_box(&sig).verify(&pp, b"message", b"prefix"), Ok(())));
+        emit(&Record::measured(
+            "dualdory-rust",
+            "verify",
+            n,
+            &measurement,
+        ));
+    }
+}
+
+#[test]
+fn size_bench() {
+    for n in ring_sizes() {
+        let (privkeys, ring, pp) = setup(n);
+        let sig = privkeys[0].sign(&mut OsRng, &pp, b"message", b"prefix", &ring);
+        emit(&Record::size(
+            "dualdory-rust",
+            "size",
+            n,
+            sig.to_bytes().len(),
+        ));
+    }
+}
@@ -10,7 +10,10 @@ import (
 	"hash"
 	"log"
 	"math"
+	"os"
 	"reflect"
+	"strconv"
+	"strings"
 	"time"
 	"unsafe"
 
@@ -168,9 +171,28 @@ func main() {
 }
 
 var (
-	ring_sizes = []int{8, 16, 32, 64, 128, 256, 512, 1024}
+	ring_sizes = benchRingSizes([]int{8, 16, 32, 64, 128, 256, 512, 1024})
 )
 
+// benchRingSizes returns the ring sizes in BENCH_RING_SIZES, a comma separated list set
+// by bench-runner, or the defaults if it is not set
+func benchRingSizes(defaults []int) []int {
+	env := os.Getenv("BENCH_RING_SIZES")
+	if env == "" {
+		return defaults
+	}
+
+	var sizes []int
+	for _, field := range strings.Split(env, ",") {
+		n, err := strconv.Atoi(strings.TrimSpace(field))
+		if err != nil || n < 1 {
+			log.Fatalf("invalid BENCH_RING_SIZES: %s", env)
+		}
+		sizes = append(sizes, n)
+	}
+	return sizes
+}
+
 func CreateNKeys(n int) ([]*ecdsa.PublicKey, []*ecdsa.PrivateKey) {
 	publicKeys := []*ecdsa.PublicKey{}
 	privateKeys := []*ecdsa.PrivateKey{}
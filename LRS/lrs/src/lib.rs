@@ -0,0 +1,330 @@
+//! Linkable ring signatures in the style of [lirisi](https://github.com/zbohm/lirisi).
+//!
+//! This is the back-linkable spontaneous anonymous group signature (bLSAG) that lirisi
+//! implements, on Ristretto instead of NIST P-256. As in lirisi, every signature is made
+//! for a case identifier, such as a poll or an election. Two signatures by the same key
+//! for the same case have the same key image, and so are linked, whichever ring or message
+//! they were made over, while signatures for different cases are unlinkable.
+//!
+//! The operations mirror those of the `trs` crate, so that the two can be benchmarked
+//! side by side: [`generate_keypair`], [`sign`], [`verify`] and [`link`].
+
+use blake2::digest::Update;
+use blake2::Blake2b;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+
+static DOMAIN_CASE: &[u8] = b"lrs-case";
+static DOMAIN_CHALLENGE: &[u8] = b"lrs-challenge";
+
+/// A public key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PublicKey(pub(crate) RistrettoPoint);
+
+impl PublicKey {
+    /// Serialize this public key to 32 bytes
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.compress().to_bytes()
+    }
+
+    /// Deserialize a public key from 32 bytes
+    pub fn from_bytes(bytes: &[u8]) -> Option<PublicKey> {
+        point_from_bytes(bytes).map(PublicKey)
+    }
+}
+
+/// A private key, which also holds its public key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PrivateKey(pub(crate) Scalar, pub(crate) PublicKey);
+
+impl PrivateKey {
+    pub fn public_key(&self) -> &PublicKey {
+        &self.1
+    }
+
+    /// Serialize this private key to its 32 byte scalar
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+
+    /// Deserialize a private key from a canonical 32 byte scalar
+    pub fn from_bytes(bytes: &[u8]) -> Option<PrivateKey> {
+        if bytes.len() != 32 {
+            return None;
+        }
+        let mut arr = [0u8; 32];
+        arr.copy_from_slice(bytes);
+        let x = Scalar::from_canonical_bytes(arr)?;
+        Some(PrivateKey(x, PublicKey(x * RISTRETTO_BASEPOINT_POINT)))
+    }
+}
+
+/// Generates a random key pair
+pub fn generate_keypair<R: RngCore + CryptoRng>(rng: &mut R) -> PrivateKey {
+    let x = Scalar::random(rng);
+    PrivateKey(x, PublicKey(x * RISTRETTO_BASEPOINT_POINT))
+}
+
+/// The ring of public keys a signature is made over, and the case it is made for
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ring {
+    pub pubkeys: Vec<PublicKey>,
+    pub case_id: Vec<u8>,
+}
+
+impl Ring {
+    // H_p(case), the base of the key images for this case
+    fn case_point(&self) -> RistrettoPoint {
+        let mut h = Blake2b::with_params(b"", b"", DOMAIN_CASE);
+        h.update(&self.case_id);
+        RistrettoPoint::from_hash(h)
+    }
+
+    // The challenge hash with the ring, case, key image and message already input,
+    // which only needs the commitments of each member added to it
+    fn challenge_prefix(&self, key_image: &CompressedRistretto, msg: &[u8]) -> Blake2b {
+        let mut h = Blake2b::with_params(b"", b"", DOMAIN_CHALLENGE);
+        h.update((self.pubkeys.len() as u64).to_le_bytes());
+        for pubkey in &self.pubkeys {
+            h.update(pubkey.0.compress().as_bytes());
+        }
+        h.update((self.case_id.len() as u64).to_le_bytes());
+        h.update(&self.case_id);
+        h.update(key_image.as_bytes());
+        h.update(msg);
+        h
+    }
+}
+
+// cᵢ₊₁ := H(L, case, I, m, Lᵢ, Rᵢ)
+fn challenge(prefix: &Blake2b, l: &RistrettoPoint, r: &RistrettoPoint) -> Scalar {
+    let mut h = prefix.clone();
+    h.update(l.compress().as_bytes());
+    h.update(r.compress().as_bytes());
+    Scalar::from_hash(h)
+}
+
+/// A linkable ring signature
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Signature {
+    c0: Scalar,
+    ss: Vec<Scalar>,
+    key_image: CompressedRistretto,
+}
+
+impl Signature {
+    /// The number of members of the ring this signature was made for
+    pub fn ring_size(&self) -> usize {
+        self.ss.len()
+    }
+
+    /// The key image, which is the same for every signature by one key for one case
+    pub fn key_image(&self) -> [u8; 32] {
+        self.key_image.to_bytes()
+    }
+
+    /// Serialize this signature as c₀, every response and then the key image,
+    /// 64 + 32n bytes for a ring of n members
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64 + 32 * self.ss.len());
+        bytes.extend_from_slice(self.c0.as_bytes());
+        for s in &self.ss {
+            bytes.extend_from_slice(s.as_bytes());
+        }
+        bytes.extend_from_slice(self.key_image.as_bytes());
+        bytes
+    }
+
+    /// Deserialize a signature produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Option<Signature> {
+        if bytes.len() < 64 || !bytes.len().is_multiple_of(32) {
+            return None;
+        }
+        let scalars = bytes[..bytes.len() - 32]
+            .chunks(32)
+            .map(scalar_from_bytes)
+            .collect::<Option<Vec<Scalar>>>()?;
+        let key_image = &bytes[bytes.len() - 32..];
+        point_from_bytes(key_image)?;
+
+        Some(Signature {
+            c0: scalars[0],
+            ss: scalars[1..].to_vec(),
+            key_image: CompressedRistretto::from_slice(key_image),
+        })
+    }
+}
+
+fn scalar_from_bytes(bytes: &[u8]) -> Option<Scalar> {
+    let mut arr = [0u8; 32];
+    arr.copy_from_slice(bytes);
+    Scalar::from_canonical_bytes(arr)
+}
+
+fn point_from_bytes(bytes: &[u8]) -> Option<RistrettoPoint> {
+    if bytes.len() != 32 {
+        return None;
+    }
+    CompressedRistretto::from_slice(bytes).decompress()
+}
+
+/// Signs a message on behalf of the ring, which must contain the private key's public key
+pub fn sign<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    msg: &[u8],
+    ring: &Ring,
+    privkey: &PrivateKey,
+) -> Signature {
+    let n = ring.pubkeys.len();
+    let signer = ring
+        .pubkeys
+        .iter()
+        .position(|pubkey| pubkey == privkey.public_key())
+        .expect("Could not find private key position in ring");
+
+    // I := x * H_p(case)
+    let hp = ring.case_point();
+    let key_image = (privkey.0 * hp).compress();
+    let prefix = ring.challenge_prefix(&key_image, msg);
+    let key_image_point = key_image.decompress().expect("key image is a valid point");
+
+    let mut cs = vec![Scalar::zero(); n];
+    let mut ss: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+
+    // Lπ := αG, Rπ := αH_p(case)
+    let alpha = Scalar::random(rng);
+    cs[(signer + 1) % n] = challenge(&prefix, &(alpha * RISTRETTO_BASEPOINT_POINT), &(alpha * hp));
+
+    // Lᵢ := sᵢG + cᵢPᵢ, Rᵢ := sᵢH_p(case) + cᵢI, around the ring back to the signer
+    for offset in 1..n {
+        let i = (signer + offset) % n;
+        let l = ss[i] * RISTRETTO_BASEPOINT_POINT + cs[i] * ring.pubkeys[i].0;
+        let r = ss[i] * hp + cs[i] * key_image_point;
+        cs[(i + 1) % n] = challenge(&prefix, &l, &r);
+    }
+
+    // sπ := α - cπx closes the ring
+    ss[signer] = alpha - cs[signer] * privkey.0;
+
+    Signature {
+        c0: cs[0],
+        ss,
+        key_image,
+    }
+}
+
+/// Verifies a signature on a message for the ring
+pub fn verify(msg: &[u8], ring: &Ring, sig: &Signature) -> bool {
+    if sig.ring_size() != ring.pubkeys.len() || ring.pubkeys.is_empty() {
+        return false;
+    }
+    let key_image = match sig.key_image.decompress() {
+        Some(point) => point,
+        None => return false,
+    };
+
+    let hp = ring.case_point();
+    let prefix = ring.challenge_prefix(&sig.key_image, msg);
+
+    let mut c = sig.c0;
+    for (s, pubkey) in sig.ss.iter().zip(&ring.pubkeys) {
+        let l = s * RISTRETTO_BASEPOINT_POINT + c * pubkey.0;
+        let r = s * hp + c * key_image;
+        c = challenge(&prefix, &l, &r);
+    }
+    c == sig.c0
+}
+
+/// Whether two signatures were made by the same key for the same case.
+/// The signatures should both have been verified
+pub fn link(sig1: &Signature, sig2: &Signature) -> bool {
+    sig1.key_image == sig2.key_image
+}
+
+// Functions for benchmarking
+
+/// Generates the keys of a ring, the ring for a fixed case, and a message of `msg_len` bytes
+pub fn generate_ring_and_message(
+    ring_size: usize,
+    msg_len: usize,
+) -> (Vec<PrivateKey>, Ring, Vec<u8>) {
+    let mut rng = rand::thread_rng();
+    let privkeys: Vec<PrivateKey> = (0..ring_size).map(|_| generate_keypair(&mut rng)).collect();
+    let ring = Ring {
+        pubkeys: privkeys.iter().map(|k| k.public_key().clone()).collect(),
+        case_id: b"Round Nr.1".to_vec(),
+    };
+    (privkeys, ring, vec![1u8; msg_len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_and_verify() {
+        let mut rng = rand::thread_rng();
+        let (privkeys, ring, msg) = generate_ring_and_message(5, 32);
+
+        for privkey in &privkeys {
+            let sig = sign(&mut rng, &msg, &ring, privkey);
+            assert!(verify(&msg, &ring, &sig));
+            assert!(!verify(b"another message", &ring, &sig));
+
+            let mut other_case = ring.clone();
+            other_case.case_id = b"Round Nr.2".to_vec();
+            assert!(!verify(&msg, &other_case, &sig));
+
+            let mut smaller = ring.clone();
+            smaller.pubkeys.pop();
+            assert!(!verify(&msg, &smaller, &sig));
+        }
+    }
+
+    #[test]
+    fn signatures_link_by_case() {
+        let mut rng = rand::thread_rng();
+        let (privkeys, ring, msg) = generate_ring_and_message(4, 32);
+        let sig1 = sign(&mut rng, &msg, &ring, &privkeys[1]);
+        let sig2 = sign(&mut rng, b"other", &ring, &privkeys[1]);
+        let sig3 = sign(&mut rng, &msg, &ring, &privkeys[2]);
+        assert!(link(&sig1, &sig2));
+        assert!(!link(&sig1, &sig3));
+
+        // The same case links across rings, and different cases do not link
+        let mut other_ring = ring.clone();
+        other_ring
+            .pubkeys
+            .push(generate_keypair(&mut rng).public_key().clone());
+        assert!(link(
+            &sig1,
+            &sign(&mut rng, &msg, &other_ring, &privkeys[1])
+        ));
+
+        let mut other_case = ring.clone();
+        other_case.case_id = b"Round Nr.2".to_vec();
+        assert!(!link(
+            &sig1,
+            &sign(&mut rng, &msg, &other_case, &privkeys[1])
+        ));
+    }
+
+    #[test]
+    fn serialization_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let (privkeys, ring, msg) = generate_ring_and_message(3, 32);
+        let sig = sign(&mut rng, &msg, &ring, &privkeys[0]);
+
+        let bytes = sig.to_bytes();
+        assert_eq!(bytes.len(), 64 + 32 * 3);
+        assert_eq!(Signature::from_bytes(&bytes), Some(sig));
+        assert_eq!(Signature::from_bytes(&bytes[..bytes.len() - 1]), None);
+
+        let privkey = PrivateKey::from_bytes(&privkeys[0].to_bytes()).unwrap();
+        assert_eq!(privkey, privkeys[0]);
+        let pubkey = PublicKey::from_bytes(&privkeys[0].public_key().to_bytes()).unwrap();
+        assert_eq!(&pubkey, privkeys[0].public_key());
+    }
+}
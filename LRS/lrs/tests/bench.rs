@@ -0,0 +1,44 @@
+use bench_harness::alloc::CountingAlloc;
+use bench_harness::timing::{black_box, Bench};
+use bench_harness::{emit, Record, DEFAULT_RING_SIZES};
+use lrs::*;
+use rand::rngs::OsRng;
+
+// Lets the benchmarks report heap usage alongside their timings
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+#[test]
+fn sign_bench() {
+    for n in bench_harness::ring_sizes(&DEFAULT_RING_SIZES) {
+        let (privkeys, ring, msg) = generate_ring_and_message(n, 32);
+
+        let measurement = Bench::from_env(10)
+            .run(|| sign(&mut OsRng, black_box(&msg), black_box(&ring), &privkeys[0]));
+        emit(&Record::measured("lrs-rust", "sign", n, &measurement));
+    }
+}
+
+#[test]
+fn verify_bench() {
+    for n in bench_harness::ring_sizes(&DEFAULT_RING_SIZES) {
+        let (privkeys, ring, msg) = generate_ring_and_message(n, 32);
+        let sig = sign(&mut OsRng, &msg, &ring, &privkeys[0]);
+
+        let measurement = Bench::from_env(10)
+            .run(|| assert!(verify(black_box(&msg), black_box(&ring), black_box(&sig))));
+        emit(&Record::measured("lrs-rust", "verify", n, &measurement));
+    }
+}
+
+#[test]
+fn size_bench() {
+    for n in bench_harness::ring_sizes(&DEFAULT_RING_SIZES) {
+        let (privkeys, ring, msg) = generate_ring_and_message(n, 32);
+        let sig = sign(&mut OsRng, &msg, &ring, &privkeys[0]);
+
+        let sig_size = sig.to_bytes().len();
+        assert_eq!(sig_size, 64 + 32 * n);
+        emit(&Record::size("lrs-rust", "size", n, sig_size));
+    }
+}
@@ -17,7 +17,13 @@
 #define DIM     512     /* ntru ring: x^512+1 */
 #define SEEDLEN 64
 #define SIGMA   123     /* also the smooth parameter */
+/* number of ring members, fixed at compile time unless built with
+ * -DNOU=raptor_nou to choose it at runtime, as raptor-sys does */
+#ifndef NOU
 #define NOU     512
+#else
+extern int raptor_nou;
+#endif
 #define PARAM_Q 12289
 #define PARAM_NONCE     40
 
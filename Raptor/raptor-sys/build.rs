@@ -0,0 +1,49 @@
+// Compiles the Raptor C sources in the parent directory. The ring size NOU is a
+// variable rather than the constant in param.h, so one build serves every ring size.
+// OpenSSL's libcrypto is needed for the AES DRBG, as with the makefile, and is found
+// in OPENSSL_DIR if it is set.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let root = Path::new("..");
+    let mut sources: Vec<PathBuf> = ["raptor.c", "linkable_raptor.c", "poly.c"]
+        .iter()
+        .map(|file| root.join(file))
+        .collect();
+    for dir in &["falcon", "rng"] {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(root.join(dir))
+            .expect("Raptor source directory")
+            .map(|entry| entry.expect("directory entry").path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "c"))
+            .collect();
+        files.sort();
+        sources.extend(files);
+    }
+
+    let mut build = cc::Build::new();
+    build
+        .files(&sources)
+        .file("src/nou.c")
+        .include(root)
+        .define("NOU", "raptor_nou")
+        .flag("-std=c99")
+        .opt_level(3)
+        .warnings(false);
+
+    println!("cargo:rerun-if-env-changed=OPENSSL_DIR");
+    if let Ok(dir) = env::var("OPENSSL_DIR") {
+        build.include(Path::new(&dir).join("include"));
+        println!("cargo:rustc-link-search=native={}/lib", dir);
+    }
+    build.compile("raptor");
+    println!("cargo:rustc-link-lib=crypto");
+
+    for path in sources.iter().chain(&[PathBuf::from("src/nou.c")]) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+    for header in &["raptor.h", "param.h", "poly.h"] {
+        println!("cargo:rerun-if-changed={}", root.join(header).display());
+    }
+}
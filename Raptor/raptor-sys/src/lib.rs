@@ -0,0 +1,87 @@
+//! Raw bindings to the Raptor C implementation in the parent directory.
+//!
+//! See `raptor.h` for the functions. Every function reads the ring size from
+//! [`raptor_nou`] and uses global random number generator state, so calls must not
+//! be made from several threads at once. The `raptor` crate wraps these safely.
+
+#![allow(non_camel_case_types, non_upper_case_globals)]
+
+use libc::{c_int, c_uchar, c_ulonglong};
+
+/// The dimension of the NTRU ring x^512 + 1
+pub const DIM: usize = 512;
+pub const SEEDLEN: usize = 64;
+pub const PARAM_Q: i64 = 12289;
+
+// Falcon-512, from falcon/api.h
+pub const CRYPTO_SECRETKEYBYTES: usize = 4097;
+pub const CRYPTO_PUBLICKEYBYTES: usize = 897;
+pub const CRYPTO_BYTES: usize = 690;
+
+/// The public key and signature polynomials of one ring member, each of `DIM` coefficients
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct raptor_data {
+    pub c: *mut i64,
+    pub d: *mut i64,
+    pub h: *mut i64,
+    pub r0: *mut i64,
+    pub r1: *mut i64,
+}
+
+extern "C" {
+    /// The number of ring members, NOU in the C sources
+    pub static mut raptor_nou: c_int;
+
+    pub fn raptor_keygen(data: raptor_data, sk: *mut c_uchar) -> c_int;
+
+    pub fn raptor_fake_keygen(data: raptor_data) -> c_int;
+
+    pub fn raptor_sign(
+        msg: *const c_uchar,
+        msg_len: c_ulonglong,
+        data: *mut raptor_data,
+        sk: *mut c_uchar,
+        H: *mut i64,
+    ) -> c_int;
+
+    pub fn raptor_verify(
+        msg: *const c_uchar,
+        msg_len: c_ulonglong,
+        data: *mut raptor_data,
+        H: *mut i64,
+    ) -> c_int;
+
+    pub fn linkable_raptor_sign(
+        msg: *const c_uchar,
+        msg_len: c_ulonglong,
+        data: *mut raptor_data,
+        sk: *mut c_uchar,
+        H: *mut i64,
+        ots_pk: *mut c_uchar,
+        ots_sk: *mut c_uchar,
+        ots_sm: *mut c_uchar,
+    ) -> c_int;
+
+    pub fn linkable_raptor_verify(
+        msg: *const c_uchar,
+        msg_len: c_ulonglong,
+        data: *mut raptor_data,
+        H: *mut i64,
+        ots_pk: *mut c_uchar,
+        ots_sm: *mut c_uchar,
+        ots_mlen: c_ulonglong,
+    ) -> c_int;
+
+    pub fn pol_unidrnd_with_seed(v: *mut i64, N: i16, q: i16, seed: *mut c_uchar, seed_len: i16);
+
+    pub fn randombytes_init(
+        entropy_input: *mut c_uchar,
+        personalization_string: *mut c_uchar,
+        security_strength: c_int,
+    );
+
+    pub fn randombytes(x: *mut c_uchar, xlen: c_ulonglong) -> c_int;
+
+    pub fn crypto_sign_keypair(pk: *mut c_uchar, sk: *mut c_uchar) -> c_int;
+}
@@ -0,0 +1,2 @@
+/* The ring size, set by the Rust bindings before each call */
+int raptor_nou = 512;
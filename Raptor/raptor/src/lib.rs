@@ -0,0 +1,419 @@
+//! Safe wrapper over the Raptor lattice ring signature in the parent directory.
+//!
+//! Raptor signs with a Falcon-512 key on behalf of a ring of NTRU public keys, and the
+//! linkable variant additionally signs the ring signature with a one-time Falcon key
+//! whose public key links signatures. The C code keeps its random number generator in
+//! global state, so every call here holds a lock, and the generator is seeded from the
+//! operating system on first use.
+//!
+//! As in the C code, the signer must be the last member of the ring.
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use raptor_sys as sys;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+pub use sys::DIM;
+
+// Whether the C random number generator has been seeded
+static LOCK: Mutex<bool> = Mutex::new(false);
+
+// Locks the C library for a call with a ring of `n` members
+fn lock(n: usize) -> MutexGuard<'static, bool> {
+    let mut seeded = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    if !*seeded {
+        let mut entropy = [0u8; 48];
+        OsRng.fill_bytes(&mut entropy);
+        unsafe { sys::randombytes_init(entropy.as_mut_ptr(), std::ptr::null_mut(), 256) };
+        *seeded = true;
+    }
+    unsafe { sys::raptor_nou = n as i32 };
+    seeded
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    EmptyRing,
+    /// The signer's public key is not the last member of the ring
+    SignerNotLast,
+    KeyGen,
+    Sign,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::EmptyRing => write!(f, "the ring has no members"),
+            Error::SignerNotLast => write!(f, "the signer must be the last member of the ring"),
+            Error::KeyGen => write!(f, "key generation failed"),
+            Error::Sign => write!(f, "signing failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The public matrix H shared by every signer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Params {
+    h: Vec<i64>,
+}
+
+impl Params {
+    pub fn generate() -> Params {
+        let _lock = lock(1);
+        let mut seed = [0u8; sys::SEEDLEN];
+        let mut h = vec![0i64; DIM];
+        unsafe {
+            sys::randombytes(seed.as_mut_ptr(), seed.len() as u64);
+            sys::pol_unidrnd_with_seed(
+                h.as_mut_ptr(),
+                DIM as i16,
+                sys::PARAM_Q as i16,
+                seed.as_mut_ptr(),
+                seed.len() as i16,
+            );
+        }
+        Params { h }
+    }
+}
+
+/// An NTRU public key, the polynomial h of a Falcon key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey(Vec<i64>);
+
+/// A Falcon secret key, which also holds its public key
+#[derive(Clone, Debug)]
+pub struct SecretKey {
+    sk: Vec<u8>,
+    pk: PublicKey,
+}
+
+impl SecretKey {
+    pub fn public_key(&self) -> &PublicKey {
+        &self.pk
+    }
+}
+
+/// Generates a Falcon key pair
+pub fn keygen() -> Result<(PublicKey, SecretKey), Error> {
+    let mut members = Members::new(1);
+    let mut sk = vec![0u8; sys::CRYPTO_SECRETKEYBYTES];
+    let _lock = lock(1);
+    if unsafe { sys::raptor_keygen(members.data()[0], sk.as_mut_ptr()) } != 0 {
+        return Err(Error::KeyGen);
+    }
+    let pk = PublicKey(members.h);
+    Ok((pk.clone(), SecretKey { sk, pk }))
+}
+
+/// Generates a random public key with no secret key, to fill rings cheaply,
+/// as the C benchmarks do
+pub fn fake_keygen() -> PublicKey {
+    let mut members = Members::new(1);
+    let _lock = lock(1);
+    unsafe { sys::raptor_fake_keygen(members.data()[0]) };
+    PublicKey(members.h)
+}
+
+// The polynomials of every member of a ring, which the C code takes as an array of
+// raptor_data pointing into these buffers
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Members {
+    c: Vec<i64>,
+    d: Vec<i64>,
+    h: Vec<i64>,
+    r0: Vec<i64>,
+    r1: Vec<i64>,
+}
+
+impl Members {
+    fn new(n: usize) -> Members {
+        Members {
+            c: vec![0; n * DIM],
+            d: vec![0; n * DIM],
+            h: vec![0; n * DIM],
+            r0: vec![0; n * DIM],
+            r1: vec![0; n * DIM],
+        }
+    }
+
+    fn with_ring(ring: &[PublicKey]) -> Members {
+        let mut members = Members::new(ring.len());
+        members.h = ring.iter().flat_map(|pk| pk.0.iter().copied()).collect();
+        members
+    }
+
+    // Valid while the buffers are neither moved nor resized. Each base pointer is taken
+    // once, as a later borrow of its buffer would invalidate those derived from it
+    fn data(&mut self) -> Vec<sys::raptor_data> {
+        let (c, d, h) = (self.c.as_mut_ptr(), self.d.as_mut_ptr(), self.h.as_mut_ptr());
+        let (r0, r1) = (self.r0.as_mut_ptr(), self.r1.as_mut_ptr());
+        (0..self.h.len() / DIM)
+            .map(|i| unsafe {
+                sys::raptor_data {
+                    c: c.add(i * DIM),
+                    d: d.add(i * DIM),
+                    h: h.add(i * DIM),
+                    r0: r0.add(i * DIM),
+                    r1: r1.add(i * DIM),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A Raptor ring signature
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    // The ring's keys are kept in h, and replaced by those of the ring verified against
+    members: Members,
+}
+
+impl Signature {
+    pub fn ring_size(&self) -> usize {
+        self.members.h.len() / DIM
+    }
+
+    /// The c, d, r₀ and r₁ polynomials of every member as 64-bit integers. Verifying
+    /// checks the c saved at signing against dH + r₀ + r₁h, so it is part of the signature
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let m = &self.members;
+        (0..self.ring_size())
+            .flat_map(|i| {
+                let range = i * DIM..(i + 1) * DIM;
+                m.c[range.clone()]
+                    .iter()
+                    .chain(&m.d[range.clone()])
+                    .chain(&m.r0[range.clone()])
+                    .chain(&m.r1[range])
+            })
+            .flat_map(|x| x.to_le_bytes())
+            .collect()
+    }
+
+    // The signature's polynomials with the given ring's keys
+    fn members_for(&self, ring: &[PublicKey]) -> Option<Members> {
+        if ring.len() != self.ring_size() || ring.is_empty() {
+            return None;
+        }
+        Some(Members {
+            h: Members::with_ring(ring).h,
+            ..self.members.clone()
+        })
+    }
+}
+
+fn check_signer(ring: &[PublicKey], sk: &SecretKey) -> Result<(), Error> {
+    match ring.last() {
+        None => Err(Error::EmptyRing),
+        Some(pk) if *pk == sk.pk => Ok(()),
+        Some(_) => Err(Error::SignerNotLast),
+    }
+}
+
+/// Signs a message on behalf of the ring, whose last member must be the signer
+pub fn sign(
+    params: &Params,
+    msg: &[u8],
+    ring: &[PublicKey],
+    sk: &SecretKey,
+) -> Result<Signature, Error> {
+    check_signer(ring, sk)?;
+    let mut members = Members::with_ring(ring);
+    let (mut sk, mut h) = (sk.sk.clone(), params.h.clone());
+
+    let _lock = lock(ring.len());
+    let ret = unsafe {
+        sys::raptor_sign(
+            msg.as_ptr(),
+            msg.len() as u64,
+            members.data().as_mut_ptr(),
+            sk.as_mut_ptr(),
+            h.as_mut_ptr(),
+        )
+    };
+    if ret != 0 {
+        return Err(Error::Sign);
+    }
+    Ok(Signature { members })
+}
+
+/// Verifies a signature on a message for the ring
+pub fn verify(params: &Params, msg: &[u8], ring: &[PublicKey], sig: &Signature) -> bool {
+    let mut members = match sig.members_for(ring) {
+        Some(members) => members,
+        None => return false,
+    };
+    let mut h = params.h.clone();
+
+    let _lock = lock(ring.len());
+    unsafe {
+        sys::raptor_verify(
+            msg.as_ptr(),
+            msg.len() as u64,
+            members.data().as_mut_ptr(),
+            h.as_mut_ptr(),
+        ) == 0
+    }
+}
+
+/// A one-time Falcon key pair for a linkable signature
+#[derive(Clone, Debug)]
+pub struct OtsKeys {
+    pk: Vec<u8>,
+    sk: Vec<u8>,
+}
+
+pub fn ots_keygen() -> Result<OtsKeys, Error> {
+    let mut pk = vec![0u8; sys::CRYPTO_PUBLICKEYBYTES];
+    let mut sk = vec![0u8; sys::CRYPTO_SECRETKEYBYTES];
+    let _lock = lock(1);
+    if unsafe { sys::crypto_sign_keypair(pk.as_mut_ptr(), sk.as_mut_ptr()) } != 0 {
+        return Err(Error::KeyGen);
+    }
+    Ok(OtsKeys { pk, sk })
+}
+
+/// A ring signature signed again with a one-time key, whose public key links it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkableSignature {
+    signature: Signature,
+    ots_pk: Vec<u8>,
+    // The one-time signature with the message it signs attached, which holds the ring
+    // signature, ring and one-time public key
+    ots_sm: Vec<u8>,
+}
+
+impl LinkableSignature {
+    /// The signed message produced by the C code, whose length it reports as the proof size
+    pub fn to_bytes(&self) -> &[u8] {
+        &self.ots_sm
+    }
+
+    /// Whether two signatures were made with the same one-time key
+    pub fn links(&self, other: &LinkableSignature) -> bool {
+        self.ots_pk == other.ots_pk
+    }
+}
+
+// The length of the buffer the one-time key signs
+fn ots_message_len(n: usize) -> usize {
+    8 * DIM * n * 4 + sys::CRYPTO_PUBLICKEYBYTES
+}
+
+/// Signs a message on behalf of the ring, whose last member must be the signer, and
+/// signs the result with the one-time key
+pub fn linkable_sign(
+    params: &Params,
+    msg: &[u8],
+    ring: &[PublicKey],
+    sk: &SecretKey,
+    ots: &OtsKeys,
+) -> Result<LinkableSignature, Error> {
+    check_signer(ring, sk)?;
+    let mut members = Members::with_ring(ring);
+    let (mut sk, mut h) = (sk.sk.clone(), params.h.clone());
+    let mut ots_keys = ots.clone();
+    let mut ots_sm = vec![0u8; sys::CRYPTO_BYTES + ots_message_len(ring.len())];
+
+    let _lock = lock(ring.len());
+    let len = unsafe {
+        sys::linkable_raptor_sign(
+            msg.as_ptr(),
+            msg.len() as u64,
+            members.data().as_mut_ptr(),
+            sk.as_mut_ptr(),
+            h.as_mut_ptr(),
+            ots_keys.pk.as_mut_ptr(),
+            ots_keys.sk.as_mut_ptr(),
+            ots_sm.as_mut_ptr(),
+        )
+    };
+    if len <= 0 {
+        return Err(Error::Sign);
+    }
+    ots_sm.truncate(len as usize);
+    Ok(LinkableSignature {
+        signature: Signature { members },
+        ots_pk: ots.pk.clone(),
+        ots_sm,
+    })
+}
+
+/// Verifies a linkable signature on a message for the ring
+pub fn linkable_verify(
+    params: &Params,
+    msg: &[u8],
+    ring: &[PublicKey],
+    sig: &LinkableSignature,
+) -> bool {
+    let mut members = match sig.signature.members_for(ring) {
+        Some(members) => members,
+        None => return false,
+    };
+    let mut h = params.h.clone();
+    let (mut ots_pk, mut ots_sm) = (sig.ots_pk.clone(), sig.ots_sm.clone());
+
+    let _lock = lock(ring.len());
+    unsafe {
+        sys::linkable_raptor_verify(
+            msg.as_ptr(),
+            msg.len() as u64,
+            members.data().as_mut_ptr(),
+            h.as_mut_ptr(),
+            ots_pk.as_mut_ptr(),
+            ots_sm.as_mut_ptr(),
+            ots_sm.len() as u64,
+        ) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(n: usize) -> (Vec<PublicKey>, SecretKey) {
+        let (pk, sk) = keygen().unwrap();
+        let mut ring: Vec<PublicKey> = (1..n).map(|_| fake_keygen()).collect();
+        ring.push(pk);
+        (ring, sk)
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let params = Params::generate();
+        let (ring, sk) = ring(4);
+
+        let sig = sign(&params, b"message", &ring, &sk).unwrap();
+        assert!(verify(&params, b"message", &ring, &sig));
+        assert!(!verify(&params, b"other message", &ring, &sig));
+        assert!(!verify(&params, b"message", &ring[1..], &sig));
+        assert_eq!(sig.to_bytes().len(), 4 * 8 * DIM * 4);
+
+        let mut reordered = ring.clone();
+        reordered.swap(0, 3);
+        assert_eq!(
+            sign(&params, b"message", &reordered, &sk).unwrap_err(),
+            Error::SignerNotLast
+        );
+    }
+
+    #[test]
+    fn linkable_sign_and_verify() {
+        let params = Params::generate();
+        let (ring, sk) = ring(3);
+        let (ots1, ots2) = (ots_keygen().unwrap(), ots_keygen().unwrap());
+
+        let sig1 = linkable_sign(&params, b"message", &ring, &sk, &ots1).unwrap();
+        assert!(linkable_verify(&params, b"message", &ring, &sig1));
+        assert!(!linkable_verify(&params, b"other message", &ring, &sig1));
+
+        let sig2 = linkable_sign(&params, b"other message", &ring, &sk, &ots1).unwrap();
+        let sig3 = linkable_sign(&params, b"message", &ring, &sk, &ots2).unwrap();
+        assert!(sig1.links(&sig2));
+        assert!(!sig1.links(&sig3));
+        assert!(sig1.to_bytes().len() > ots_message_len(3));
+    }
+}
@@ -0,0 +1,63 @@
+use bench_harness::alloc::CountingAlloc;
+use bench_harness::timing::{black_box, Bench};
+use bench_harness::{emit, Record};
+use raptor::*;
+
+// Lets the benchmarks report heap usage alongside their timings
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+// The ring sizes of bench.sh
+const RING_SIZES: [usize; 8] = [8, 16, 32, 64, 128, 256, 512, 1024];
+
+// As in the C benchmarks, the signer's key is real and the rest of the ring is random
+fn setup(n: usize) -> (Params, Vec<PublicKey>, SecretKey, OtsKeys) {
+    let (pk, sk) = keygen().unwrap();
+    let mut ring: Vec<PublicKey> = (1..n).map(|_| fake_keygen()).collect();
+    ring.push(pk);
+    (Params::generate(), ring, sk, ots_keygen().unwrap())
+}
+
+#[test]
+fn linkable_sign_bench() {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
+        let (params, ring, sk, ots) = setup(n);
+
+        let measurement = Bench::from_env(10).run(|| {
+            linkable_sign(&params, black_box(b"message"), black_box(&ring), &sk, &ots).unwrap()
+        });
+        emit(&Record::measured("raptor-rust", "sign", n, &measurement));
+    }
+}
+
+#[test]
+fn linkable_verify_bench() {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
+        let (params, ring, sk, ots) = setup(n);
+        let sig = linkable_sign(&params, b"message", &ring, &sk, &ots).unwrap();
+
+        let measurement = Bench::from_env(10).run(|| {
+            assert!(linkable_verify(
+                &params,
+                black_box(b"message"),
+                black_box(&ring),
+                &sig
+            ))
+        });
+        emit(&Record::measured("raptor-rust", "verify", n, &measurement));
+    }
+}
+
+#[test]
+fn size_bench() {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
+        let (params, ring, sk, ots) = setup(n);
+        let sig = linkable_sign(&params, b"message", &ring, &sk, &ots).unwrap();
+        emit(&Record::size(
+            "raptor-rust",
+            "size",
+            n,
+            sig.to_bytes().len(),
+        ));
+    }
+}
@@ -4,7 +4,7 @@ use trs::*;
 fn proof_time_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("proof_time");
 
-    for &n in &RING_SIZES {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
         let tup = generate_keys_and_message(n);
         let set_publickey = tup.0;
         let set_secretkey = tup.1;
@@ -12,7 +12,7 @@ fn proof_time_bench(c: &mut Criterion) {
         let msg = tup.3;
 
         group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
-            b.iter(|| proof_time(n, set_publickey.clone(), set_secretkey.clone(), tag.clone(), msg.clone()))
+            b.iter(|| proof_time(n, &set_publickey, &set_secretkey, &tag, &msg))
         });
     }
 
@@ -22,17 +22,17 @@ fn proof_time_bench(c: &mut Criterion) {
 fn verification_time_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("verification_time");
 
-    for &n in &RING_SIZES {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
         let tup = generate_keys_and_message(n);
         let set_publickey = tup.0;
         let set_secretkey = tup.1;
         let tag = tup.2;
         let msg = tup.3;
 
-        let sigs = proof_time(n, set_publickey.clone(), set_secretkey.clone(), tag.clone(), msg.clone());
+        let sigs = proof_time(n, &set_publickey, &set_secretkey, &tag, &msg);
 
         group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
-            b.iter(|| verification_time(msg.clone(), tag.clone(), sigs.clone(), n))
+            b.iter(|| verification_time(&msg, &tag, &sigs, n))
         });
     }
 
@@ -42,18 +42,18 @@ fn verification_time_bench(c: &mut Criterion) {
 fn trace_time_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("trace_time");
 
-    for &n in &RING_SIZES {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
         let tup = generate_keys_and_message(n);
         let set_publickey = tup.0;
         let set_secretkey = tup.1;
         let tag = tup.2;
         let msg = tup.3;
 
-        let sigs = proof_time(n, set_publickey.clone(), set_secretkey.clone(), tag.clone(), msg.clone());
-        let sigs2 = proof_time(n, set_publickey.clone(), set_secretkey.clone(), tag.clone(), msg.clone());
+        let sigs = proof_time(n, &set_publickey, &set_secretkey, &tag, &msg);
+        let sigs2 = proof_time(n, &set_publickey, &set_secretkey, &tag, &msg);
 
         group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
-            b.iter(|| trace_time(msg.clone(), msg.clone(), tag.clone(), sigs[0].clone(), sigs2[0].clone()))
+            b.iter(|| trace_time(&msg, &msg, &tag, &sigs[0], &sigs2[0]))
         });
     }
 
@@ -63,7 +63,7 @@ fn trace_time_bench(c: &mut Criterion) {
 fn generation_time_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("generation_time");
 
-    for &n in &RING_SIZES {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
         group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
             b.iter(|| generation_time(n))
         });
@@ -0,0 +1,310 @@
+//! Command line tool for traceable ring signatures.
+//!
+//! Keys, rings and signatures are written to files as hex or base64 text, so the
+//! tool can be scripted for demos and integration tests. Messages are read as raw
+//! bytes from a file, or from stdin if the path is `-`.
+//!
+//! Exit codes: 0 on success, 1 if a signature does not verify, and 2 for usage
+//! errors or unreadable or malformed input.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use clap::{Parser, Subcommand, ValueEnum};
+use rand::rngs::OsRng;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+use trs::{sign, trace, verify, Mode, PrivateKey, PublicKey, Signature, Tag};
+
+const EXIT_INVALID: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "trs-cli",
+    about = "Traceable ring signatures: keygen, sign, verify and trace"
+)]
+struct Args {
+    /// Text encoding of key, ring and signature files
+    #[arg(long, value_enum, default_value_t = Encoding::Hex, global = true)]
+    encoding: Encoding,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Encoding {
+    Hex,
+    Base64,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Generate a key pair
+    Keygen {
+        /// File the private key is written to
+        #[arg(long)]
+        out: PathBuf,
+        /// File the public key is written to, defaults to the private key file with `.pub` appended
+        #[arg(long)]
+        pub_out: Option<PathBuf>,
+    },
+    /// Build rings from public keys
+    Ring {
+        #[command(subcommand)]
+        command: RingCommand,
+    },
+    /// Sign a message on behalf of a ring
+    Sign {
+        /// Private key of a member of the ring
+        #[arg(long)]
+        key: PathBuf,
+        /// Ring written by `ring build`
+        #[arg(long)]
+        ring: PathBuf,
+        /// Message to sign, or `-` for stdin
+        #[arg(long)]
+        message: PathBuf,
+        /// File the signature is written to
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Verify a signature, exiting with 1 if it is invalid
+    Verify {
+        #[arg(long)]
+        ring: PathBuf,
+        /// Signed message, or `-` for stdin
+        #[arg(long)]
+        message: PathBuf,
+        #[arg(long)]
+        signature: PathBuf,
+    },
+    /// Trace two signatures, printing `independent`, `linked` or `signer <index>`.
+    /// Exits with 1 if either signature is invalid
+    Trace {
+        #[arg(long)]
+        ring: PathBuf,
+        #[arg(long)]
+        message1: PathBuf,
+        #[arg(long)]
+        signature1: PathBuf,
+        #[arg(long)]
+        message2: PathBuf,
+        #[arg(long)]
+        signature2: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum RingCommand {
+    /// Build a ring for an issue from the public keys of its members, in order
+    Build {
+        /// The issue the ring signs for, e.g. an election or poll identifier
+        #[arg(long)]
+        issue: String,
+        /// File the ring is written to
+        #[arg(long)]
+        out: PathBuf,
+        /// Only link two signatures by one member, rather than tracing them to the member
+        #[arg(long)]
+        linkable_only: bool,
+        /// Public key files of the members
+        #[arg(required = true)]
+        keys: Vec<PathBuf>,
+    },
+}
+
+// Errors which exit with EXIT_ERROR
+#[derive(Debug)]
+enum Error {
+    Io(PathBuf, io::Error),
+    Decode(PathBuf),
+    Malformed(PathBuf, &'static str),
+    NotInRing,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::Decode(path) => {
+                write!(f, "{}: not valid for the chosen --encoding", path.display())
+            }
+            Error::Malformed(path, what) => write!(f, "{}: not a valid {}", path.display(), what),
+            Error::NotInRing => write!(f, "the key's public key is not a member of the ring"),
+        }
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    match run(args) {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+// Runs a command, returning its exit code
+fn run(args: Args) -> Result<i32, Error> {
+    let encoding = args.encoding;
+
+    match args.command {
+        Command::Keygen { out, pub_out } => {
+            let mut private_key = [0u8; 32];
+            let mut public_key = [0u8; 32];
+            trs::trs_generate_keypair(&mut private_key, &mut public_key);
+
+            let pub_out = pub_out.unwrap_or_else(|| {
+                let mut path = out.clone().into_os_string();
+                path.push(".pub");
+                path.into()
+            });
+            // PrivateKey expects the scalar concatenated with the public key
+            write(
+                &out,
+                &[&private_key[..], &public_key[..]].concat(),
+                encoding,
+            )?;
+            write(&pub_out, &public_key, encoding)?;
+            eprintln!("wrote {} and {}", out.display(), pub_out.display());
+        }
+        Command::Ring {
+            command:
+                RingCommand::Build {
+                    issue,
+                    out,
+                    linkable_only,
+                    keys,
+                },
+        } => {
+            let pubkeys = keys
+                .iter()
+                .map(|path| {
+                    PublicKey::from_bytes(&read(path, encoding)?)
+                        .ok_or_else(|| Error::Malformed(path.clone(), "public key"))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let tag = Tag {
+                pubkeys,
+                issue: issue.into_bytes(),
+                mode: if linkable_only {
+                    Mode::LinkableOnly
+                } else {
+                    Mode::Traceable
+                },
+            };
+            write(&out, &tag.to_bytes(), encoding)?;
+            eprintln!(
+                "wrote a ring of {} members to {}",
+                tag.pubkeys.len(),
+                out.display()
+            );
+        }
+        Command::Sign {
+            key,
+            ring,
+            message,
+            out,
+        } => {
+            let private_key = PrivateKey::from_bytes(&read(&key, encoding)?)
+                .ok_or_else(|| Error::Malformed(key.clone(), "private key"))?;
+            let tag = read_ring(&ring, encoding)?;
+
+            // The signer's public key is the second half of the private key
+            let public_key = PublicKey::from_bytes(&private_key.as_bytes()[32..])
+                .ok_or_else(|| Error::Malformed(key.clone(), "private key"))?;
+            if !tag.pubkeys.contains(&public_key) {
+                return Err(Error::NotInRing);
+            }
+
+            let signature = sign(&mut OsRng, &read_message(&message)?, &tag, &private_key);
+            write(&out, &signature.to_bytes(), encoding)?;
+            eprintln!("wrote {}", out.display());
+        }
+        Command::Verify {
+            ring,
+            message,
+            signature,
+        } => {
+            let tag = read_ring(&ring, encoding)?;
+            let signature = read_signature(&signature, &tag, encoding)?;
+            if !verify(&read_message(&message)?, &tag, &signature) {
+                println!("invalid");
+                return Ok(EXIT_INVALID);
+            }
+            println!("valid");
+        }
+        Command::Trace {
+            ring,
+            message1,
+            signature1,
+            message2,
+            signature2,
+        } => {
+            let tag = read_ring(&ring, encoding)?;
+            let (msg1, msg2) = (read_message(&message1)?, read_message(&message2)?);
+            let sig1 = read_signature(&signature1, &tag, encoding)?;
+            let sig2 = read_signature(&signature2, &tag, encoding)?;
+
+            for (msg, sig, path) in [(&msg1, &sig1, &signature1), (&msg2, &sig2, &signature2)] {
+                if !verify(msg, &tag, sig) {
+                    eprintln!("{}: invalid signature", path.display());
+                    return Ok(EXIT_INVALID);
+                }
+            }
+            match trace(&tag, &msg1, &msg2, &sig1, &sig2) {
+                -1 => println!("linked"),
+                -2 => println!("independent"),
+                signer => println!("signer {}", signer),
+            }
+        }
+    }
+    Ok(0)
+}
+
+fn read(path: &Path, encoding: Encoding) -> Result<Vec<u8>, Error> {
+    let text = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    let text = text.trim();
+    let bytes = match encoding {
+        Encoding::Hex => hex::decode(text).ok(),
+        Encoding::Base64 => BASE64.decode(text).ok(),
+    };
+    bytes.ok_or_else(|| Error::Decode(path.to_path_buf()))
+}
+
+fn write(path: &Path, bytes: &[u8], encoding: Encoding) -> Result<(), Error> {
+    let text = match encoding {
+        Encoding::Hex => hex::encode(bytes),
+        Encoding::Base64 => BASE64.encode(bytes),
+    };
+    fs::write(path, text + "\n").map_err(|e| Error::Io(path.to_path_buf(), e))
+}
+
+fn read_ring(path: &Path, encoding: Encoding) -> Result<Tag, Error> {
+    Tag::from_bytes(&read(path, encoding)?)
+        .ok_or_else(|| Error::Malformed(path.to_path_buf(), "ring"))
+}
+
+// Reads a signature, which must have been made for a ring of `tag`'s size
+fn read_signature(path: &Path, tag: &Tag, encoding: Encoding) -> Result<Signature, Error> {
+    Signature::from_bytes(&read(path, encoding)?)
+        .filter(|signature| signature.ring_size() == tag.pubkeys.len())
+        .ok_or_else(|| Error::Malformed(path.to_path_buf(), "signature for the ring"))
+}
+
+// Messages are raw bytes rather than encoded text
+fn read_message(path: &Path) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    let result = if path == Path::new("-") {
+        io::stdin().read_to_end(&mut bytes).map(|_| ())
+    } else {
+        fs::File::open(path).and_then(|mut file| file.read_to_end(&mut bytes).map(|_| ()))
+    };
+    result.map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    Ok(bytes)
+}
@@ -0,0 +1,178 @@
+// Ed25519 signatures as in RFC 8032, for messages signed outside the ring, e.g. ballot
+// envelopes from a front-end that signs with a standard Ed25519 library.
+//
+// Keys are the 32 byte RFC 8032 secret key (the seed) and public key, which are unrelated
+// to the ring's Ristretto keys.
+
+use ed25519_dalek::{ExpandedSecretKey, PublicKey, SecretKey, Signature, Verifier};
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The public key is not an encoded point
+    InvalidPublicKey,
+    /// The signature's s is not reduced
+    MalformedSignature,
+    /// The signature does not verify
+    InvalidSignature,
+}
+
+impl Error {
+    /// The code the FFI functions return for this error. Success is 0
+    pub fn code(&self) -> i32 {
+        match self {
+            Error::InvalidPublicKey => 2,
+            Error::MalformedSignature => 4,
+            Error::InvalidSignature => 5,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidPublicKey => write!(f, "the public key is not a valid point"),
+            Error::MalformedSignature => write!(f, "the signature is malformed"),
+            Error::InvalidSignature => write!(f, "the signature does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+fn secret_key(secret: &[u8; 32]) -> SecretKey {
+    SecretKey::from_bytes(secret).expect("every 32 bytes are a secret key")
+}
+
+/// The public key of a secret key
+pub fn public_key(secret: &[u8; 32]) -> [u8; 32] {
+    PublicKey::from(&secret_key(secret)).to_bytes()
+}
+
+/// Signs a message
+pub fn sign(secret: &[u8; 32], msg: &[u8]) -> [u8; 64] {
+    let secret = secret_key(secret);
+    let public = PublicKey::from(&secret);
+    ExpandedSecretKey::from(&secret).sign(msg, &public).to_bytes()
+}
+
+/// Verifies a signature on a message
+pub fn verify(public: &[u8; 32], msg: &[u8], signature: &[u8; 64]) -> Result<(), Error> {
+    let public = PublicKey::from_bytes(public).map_err(|_| Error::InvalidPublicKey)?;
+    let signature = Signature::try_from(&signature[..]).map_err(|_| Error::MalformedSignature)?;
+    public
+        .verify(msg, &signature)
+        .map_err(|_| Error::InvalidSignature)
+}
+
+/// Writes the public key of a 32 byte secret key
+pub fn trs_ed25519_public_key(secret_key: &[u8; 32], public_key: &mut [u8; 32]) {
+    *public_key = self::public_key(secret_key);
+}
+
+/// Signs `msg_len` bytes at `msg` with a 32 byte secret key, writing the 64 byte signature
+///
+/// # Safety
+///
+/// `msg` must be non-null, even if `msg_len` is 0, and valid for reads of `msg_len`
+/// bytes, which must not be written to during the call. `msg_len` must be at most
+/// `isize::MAX`.
+pub unsafe fn trs_ed25519_sign(
+    secret_key: &[u8; 32],
+    msg: *const u8,
+    msg_len: usize,
+    signature: &mut [u8; 64],
+) {
+    let msg = std::slice::from_raw_parts(msg, msg_len);
+    *signature = sign(secret_key, msg);
+}
+
+/// Verifies a 64 byte signature on `msg_len` bytes at `msg` with a 32 byte public key.
+/// Returns 0 if it is valid, and the code of the error otherwise
+///
+/// # Safety
+///
+/// `msg` must be non-null, even if `msg_len` is 0, and valid for reads of `msg_len`
+/// bytes, which must not be written to during the call. `msg_len` must be at most
+/// `isize::MAX`.
+pub unsafe fn trs_ed25519_verify(
+    public_key: &[u8; 32],
+    msg: *const u8,
+    msg_len: usize,
+    signature: &[u8; 64],
+) -> i32 {
+    let msg = std::slice::from_raw_parts(msg, msg_len);
+    match verify(public_key, msg, signature) {
+        Ok(()) => 0,
+        Err(e) => e.code(),
+    }
+}
+
+// The test vectors of RFC 8032 section 7.1, as secret key, public key, message and
+// signature in hex
+#[cfg(test)]
+const RFC8032_VECTORS: [[&str; 4]; 4] = [
+    [
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        "",
+        "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+    ],
+    [
+        "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        "72",
+        "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+    ],
+    [
+        "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+        "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+        "af82",
+        "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+    ],
+    [
+        "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42",
+        "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf",
+        "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+        "dc2a4459e7369633a52b1bf277839a00201009a3efbf3ecb69bea2186c26b58909351fc9ac90b3ecfdfbc7c66431e0303dca179c138ac17ad9bef1177331a704",
+    ],
+];
+
+#[test]
+fn test_ed25519_rfc8032_vectors() {
+    for [secret, public, msg, sig] in RFC8032_VECTORS.iter() {
+        let secret = <[u8; 32]>::try_from(&hex::decode(secret).unwrap()[..]).unwrap();
+        let public = <[u8; 32]>::try_from(&hex::decode(public).unwrap()[..]).unwrap();
+        let msg = hex::decode(msg).unwrap();
+        let sig = <[u8; 64]>::try_from(&hex::decode(sig).unwrap()[..]).unwrap();
+
+        assert_eq!(public_key(&secret), public);
+        assert_eq!(sign(&secret, &msg), sig);
+        assert_eq!(verify(&public, &msg, &sig), Ok(()));
+        assert_eq!(
+            verify(&public, &[msg.clone(), vec![0]].concat(), &sig),
+            Err(Error::InvalidSignature)
+        );
+    }
+}
+
+#[test]
+fn test_ed25519_ffi() {
+    let secret = [7u8; 32];
+    let mut public = [0u8; 32];
+    trs_ed25519_public_key(&secret, &mut public);
+
+    let msg = b"ballot envelope";
+    let mut sig = [0u8; 64];
+    unsafe {
+        trs_ed25519_sign(&secret, msg.as_ptr(), msg.len(), &mut sig);
+        assert_eq!(trs_ed25519_verify(&public, msg.as_ptr(), msg.len(), &sig), 0);
+        assert_eq!(trs_ed25519_verify(&public, msg.as_ptr(), msg.len() - 1, &sig), 5);
+
+        // s with its top bits set is rejected before verifying
+        let mut malformed = sig;
+        malformed[63] |= 0xe0;
+        assert_eq!(trs_ed25519_verify(&public, msg.as_ptr(), msg.len(), &malformed), 4);
+    }
+}
@@ -3,16 +3,22 @@ use curve25519_dalek::{
     traits::Identity,};
 
 use core::convert::TryFrom;
-use std::fs::File;
-use std::io::{self, Write, BufRead};
 
-use blake2::{digest::Update, Blake2b};
+use blake2::{digest::{FixedOutput, Update}, Blake2b};
 
 use rand_core::{CryptoRng, RngCore};
 
+pub mod ed25519;
+pub mod schnorr;
+pub mod suite;
+pub mod threshold;
+pub mod trustee;
+
+use suite::{Blake2bSuite, HashSuite};
+
 static DOMAIN_STR0: &'static [u8] = b"rust-ringsig-0";
-static DOMAIN_STR1: &'static [u8] = b"rust-ringsig-1";
 static DOMAIN_STR2: &'static [u8] = b"rust-ringsig-2";
+static DOMAIN_PREHASH: &[u8] = b"rust-ringsig-msg";
 
 pub const RING_SIZES: [usize; 7] = [16, 32, 64, 128, 256, 512, 1024];
 
@@ -123,31 +129,134 @@ pub fn trs_keypair_from_seed(private_key: &mut [u8; 32], public_key: &mut [u8; 3
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Signature {
+    // A₁ for a traceable tag, or the key image xh for a linkable-only one
     aa1: RistrettoPoint,
     cs: Vec<Scalar>,
     zs: Vec<Scalar>,
 }
 
 impl Signature {
+    /// The number of members of the ring this signature was made for
+    pub fn ring_size(&self) -> usize {
+        self.cs.len()
+    }
+
     pub fn aa1_bytes(&self) -> [u8; 32] {
         self.aa1.compress().to_bytes()
     }
+
+    /// Serialize this signature as the compressed A₁ followed by every cᵢ and then every zᵢ.
+    /// This is 32 + 64n bytes for a ring of size n
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 + 32 * (self.cs.len() + self.zs.len()));
+        bytes.extend_from_slice(self.aa1.compress().as_bytes());
+        for c in &self.cs {
+            bytes.extend_from_slice(c.as_bytes());
+        }
+        for z in &self.zs {
+            bytes.extend_from_slice(z.as_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize a signature produced by `to_bytes`. The ring size is implied by the length
+    pub fn from_bytes(bytes: &[u8]) -> Option<Signature> {
+        if bytes.len() < 32 || !(bytes.len() - 32).is_multiple_of(64) {
+            return None;
+        }
+        let ring_size = (bytes.len() - 32) / 64;
+
+        let aa1 = CompressedRistretto::from_slice(&bytes[..32]).decompress()?;
+        let scalars = bytes[32..]
+            .chunks(32)
+            .map(|chunk| Scalar::from_canonical_bytes(*array_ref!(chunk, 0, 32)))
+            .collect::<Option<Vec<Scalar>>>()?;
+        let (cs, zs) = scalars.split_at(ring_size);
+
+        Some(Signature {
+            aa1,
+            cs: cs.to_vec(),
+            zs: zs.to_vec(),
+        })
+    }
 }
 
+/// What two signatures by one signer with the same tag reveal
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    /// Signatures on different messages reveal the signer's index to `trace`
+    Traceable,
+    /// Every signature carries the key image σ = xh in place of the σ-line through A₀
+    /// and A₁, so `trace` can only say whether two signatures are linked
+    LinkableOnly,
+}
+
+// The top bit of a serialized tag's key count, set for linkable-only tags
+const LINKABLE_ONLY_FLAG: u64 = 1 << 63;
+
 #[derive(Clone, Debug, Eq, PartialEq)] // szhou: looks like this file actually has the implementation details
 pub struct Tag {
     pub pubkeys: Vec<PublicKey>,
     pub issue: Vec<u8>,
+    pub mode: Mode,
 }
 
 impl Tag {
-    // Given an initialized hash function, input the pubkeys and issue number
+    /// Serialize this tag as the number of public keys as 8 little endian bytes, with
+    /// the top bit set for a linkable-only tag, followed by every compressed public key
+    /// and then the issue
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + 32 * self.pubkeys.len() + self.issue.len());
+        let flag = match self.mode {
+            Mode::Traceable => 0,
+            Mode::LinkableOnly => LINKABLE_ONLY_FLAG,
+        };
+        bytes.extend_from_slice(&(self.pubkeys.len() as u64 | flag).to_le_bytes());
+        for pubkey in &self.pubkeys {
+            bytes.extend_from_slice(&pubkey.as_bytes());
+        }
+        bytes.extend_from_slice(&self.issue);
+        bytes
+    }
+
+    /// Deserialize a tag produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Option<Tag> {
+        if bytes.len() < 8 {
+            return None;
+        }
+        let count = u64::from_le_bytes(*array_ref!(bytes, 0, 8));
+        let mode = if count & LINKABLE_ONLY_FLAG == 0 {
+            Mode::Traceable
+        } else {
+            Mode::LinkableOnly
+        };
+        let ring_size = usize::try_from(count & !LINKABLE_ONLY_FLAG).ok()?;
+        let keys_len = ring_size.checked_mul(32)?;
+        let rest = &bytes[8..];
+        if rest.len() < keys_len {
+            return None;
+        }
+
+        let pubkeys = rest[..keys_len]
+            .chunks(32)
+            .map(PublicKey::from_bytes)
+            .collect::<Option<Vec<PublicKey>>>()?;
+        let issue = rest[keys_len..].to_vec();
+
+        Some(Tag { pubkeys, issue, mode })
+    }
+
+    // Given an initialized hash function, input the pubkeys and issue number, and the
+    // mode if it is linkable-only, so traceable tags hash as they always have
     fn hash_self<T: Update>(&self, mut h: T) -> T {
         for pubkey in &self.pubkeys {
             let pubkey_c = pubkey.0.compress();
             h.update(pubkey_c.as_bytes());
         }
         h.update(&*self.issue);
+        if self.mode == Mode::LinkableOnly {
+            h.update(b"linkable-only");
+        }
 
         h
     }
@@ -155,23 +264,51 @@ impl Tag {
     // 3 independent hash functions
     // szhou: are these hash functions that can be used anywhere after we initialize a Tag?
 
-    fn hash0(&self) -> Blake2b {
-        let h = Blake2b::with_params(b"", b"", DOMAIN_STR0);
+    // h and A₀ are hashed to the group with the suite's RFC 9380 expander, each under
+    // its own DST
+
+    fn hash0<S: HashSuite>(&self) -> GroupHasher {
+        let h = GroupHasher(hash_to_group::Hasher::with_expander(S::EXPANDER, S::TRACING_BASE_DST));
         self.hash_self(h)
     }
 
-    fn hash1(&self) -> Blake2b {
-        let h = Blake2b::with_params(b"", b"", DOMAIN_STR1);
+    fn hash1<S: HashSuite>(&self) -> GroupHasher {
+        let h = GroupHasher(hash_to_group::Hasher::with_expander(S::EXPANDER, S::A0_DST));
         self.hash_self(h)
     }
 
-    fn hash2(&self) -> Blake2b {
-        let h = Blake2b::with_params(b"", b"", DOMAIN_STR2);
-        self.hash_self(h)
+    fn hash2<S: HashSuite>(&self) -> S::Hash {
+        self.hash_self(S::hash(DOMAIN_STR2))
+    }
+}
+
+// Feeds the tag to hash_to_group like any other hash function
+struct GroupHasher(hash_to_group::Hasher);
+
+impl Update for GroupHasher {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.0.update(data.as_ref());
     }
 }
 
-pub(crate) fn compute_sigma(
+impl GroupHasher {
+    fn into_point(self) -> RistrettoPoint {
+        RistrettoPoint::from_uniform_bytes(&self.0.finalize())
+    }
+}
+
+// Hashes a message to 64 bytes. Signing and verifying the digest instead of a long
+// message hashes the message once rather than once per hash of the message
+pub fn prehash(msg: &[u8]) -> [u8; 64] {
+    let mut h = Blake2b::with_params(b"", b"", DOMAIN_PREHASH);
+    h.update(msg);
+
+    let mut digest = [0u8; 64];
+    digest.copy_from_slice(&h.finalize_fixed());
+    digest
+}
+
+pub(crate) fn compute_sigma_with<S: HashSuite>(
     msg: &[u8],
     tag: &Tag,
     sig: &Signature,
@@ -185,31 +322,45 @@ pub(crate) fn compute_sigma(
 
     // A₀ := H'(L, m)
     let aa0 = {
-        let mut d = tag.hash1();
+        let mut d = tag.hash1::<S>();
         d.update(msg);
-        RistrettoPoint::from_hash(d)
+        d.into_point()
     };
 
-    // σᵢ := A₀ * A₁ⁱ. See note in the sign function about the i+1 here
-    let sigma: Vec<RistrettoPoint> = {
-        let mut vals = Vec::new();
-        for i in 0..ring_size {
-            let s = Scalar::from((i + 1) as u64);
-            let aa1i = &s * &aa1;
-            vals.push(&aa0 + &aa1i);
-        }
-
-        vals
-    };
+    let sigma: Vec<RistrettoPoint> = (0..ring_size)
+        .map(|i| sigma_i(tag.mode, &aa0, &aa1, i))
+        .collect();
 
     (aa0, sigma)
 }
 
+// σᵢ := A₀ * A₁ⁱ, or the key image A₁ for every i in linkable-only mode. See note in
+// the sign function about the i+1 here
+fn sigma_i(mode: Mode, aa0: &RistrettoPoint, aa1: &RistrettoPoint, i: usize) -> RistrettoPoint {
+    match mode {
+        Mode::Traceable => {
+            let s = Scalar::from((i + 1) as u64);
+            aa0 + &s * aa1
+        }
+        Mode::LinkableOnly => *aa1,
+    }
+}
+
 pub fn sign<R: RngCore + CryptoRng>(
     rng: &mut R,
     msg: &[u8],
     tag: &Tag,
     privkey: &PrivateKey,
+) -> Signature {
+    sign_with::<Blake2bSuite, R>(rng, msg, tag, privkey)
+}
+
+/// Signs a message with the tag, hashing with the suite `S`
+pub fn sign_with<S: HashSuite, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    msg: &[u8],
+    tag: &Tag,
+    privkey: &PrivateKey,
 ) -> Signature {
     // Make sure the ring size isn't bigger than a u64
     let ring_size = tag.pubkeys.len();
@@ -228,19 +379,21 @@ pub fn sign<R: RngCore + CryptoRng>(
     let privkey_idx = privkey_idx.expect("Could not find private key position in ring");
 
     // h := H(L)
-    let h = RistrettoPoint::from_hash(tag.hash0());
+    let h = tag.hash0::<S>().into_point();
     let mut sigma: Vec<RistrettoPoint> = vec![RistrettoPoint::identity(); ring_size];
     sigma[privkey_idx] = &privkey.0 * &h;
 
     // A₀ := H'(L, m)
     let aa0 = {
-        let mut d = tag.hash1();
+        let mut d = tag.hash1::<S>();
         d.update(msg);
-        RistrettoPoint::from_hash(d)
+        d.into_point()
     };
 
-    // A₁ := (j+1)^{-1} * (σⱼ - A₀)
-    let aa1 = {
+    // A₁ := (j+1)^{-1} * (σⱼ - A₀), or the key image σⱼ in linkable-only mode
+    let aa1 = if tag.mode == Mode::LinkableOnly {
+        sigma[privkey_idx]
+    } else {
         let t = &sigma[privkey_idx] - &aa0;
         // sigma is indexed by zero but the paper assumes it is indexed at 1. We can keep it
         // indexed at zero, but we have to calculate 1/(i+1) instead of 1/i, otherwise we might
@@ -252,9 +405,7 @@ pub fn sign<R: RngCore + CryptoRng>(
 
     // σᵢ := A₀ * A₁^{i+1}. Same reasoning for the +1 applies here.
     for i in (0..ring_size).filter(|&j| j != privkey_idx) {
-        let s = Scalar::from((i + 1) as u64);
-        let aa1i = &s * &aa1;
-        sigma[i] = &aa0 + &aa1i;
+        sigma[i] = sigma_i(tag.mode, &aa0, &aa1, i);
     }
 
     // Signature values
@@ -290,7 +441,7 @@ pub fn sign<R: RngCore + CryptoRng>(
 
     // c := H''(L, A₀, A₁, {aᵢ}, {bᵢ})
     let cc = {
-        let mut d = tag.hash2();
+        let mut d = tag.hash2::<S>();
         let aa0c = aa0.compress();
         let aa1c = aa1.compress();
         d.update(aa0c.as_bytes());
@@ -342,7 +493,7 @@ pub fn trs_sign(set_publickey: *const u8, set_publickey_len: usize, secret_key:
         })
         .collect();
     let mut rng = rand::thread_rng();
-    let tag = Tag{pubkeys, issue:issue.to_vec()};
+    let tag = Tag{pubkeys, issue:issue.to_vec(), mode: Mode::Traceable};
     let _sk = PrivateKey::from_bytes(secret_key).unwrap();
     let signature = sign(&mut rng, &*msg, &tag, &_sk);
     let aa1_bytes = signature.aa1_bytes();
@@ -359,14 +510,19 @@ pub fn trs_sign(set_publickey: *const u8, set_publickey_len: usize, secret_key:
 
 
 pub fn verify(msg: &[u8], tag: &Tag, sig: &Signature) -> bool {
+    verify_with::<Blake2bSuite>(msg, tag, sig)
+}
+
+/// Verifies a signature made with the suite `S`
+pub fn verify_with<S: HashSuite>(msg: &[u8], tag: &Tag, sig: &Signature) -> bool {
     let c = &sig.cs;
     let z = &sig.zs;
     let aa1 = sig.aa1; // A₁
 
     // h := H(L)
-    let h = RistrettoPoint::from_hash(tag.hash0());
+    let h = tag.hash0::<S>().into_point();
 
-    let (aa0, sigma) = compute_sigma(msg, tag, sig);
+    let (aa0, sigma) = compute_sigma_with::<S>(msg, tag, sig);
 
     // aᵢ := zᵢG * cᵢyᵢ
     let a: Vec<RistrettoPoint> = {
@@ -393,7 +549,7 @@ pub fn verify(msg: &[u8], tag: &Tag, sig: &Signature) -> bool {
 
     // c := H''(L, A₀, A₁, {aᵢ}, {bᵢ})
     let cc = {
-        let mut d = tag.hash2();
+        let mut d = tag.hash2::<S>();
         let aa0c = aa0.compress();
         let aa1c = aa1.compress();
         d.update(aa0c.as_bytes());
@@ -428,7 +584,7 @@ pub fn trs_verify(set_publickey: *const u8, set_publickey_len: usize, issue: &mu
             PublicKey::from_bytes(&arr).unwrap()
         })
         .collect();
-    let tag = Tag{pubkeys, issue:issue.to_vec()};
+    let tag = Tag{pubkeys, issue:issue.to_vec(), mode: Mode::Traceable};
     let set_c = unsafe { std::slice::from_raw_parts(c_n, set_publickey_len) };
     let set_z = unsafe { std::slice::from_raw_parts(z_n, set_publickey_len) };
 
@@ -449,8 +605,19 @@ pub fn trs_verify(set_publickey: *const u8, set_publickey_len: usize, issue: &mu
 }
 
 pub fn trace(tag: &Tag, msg1: &[u8], msg2: &[u8], sig1: &Signature, sig2: &Signature) -> i32 {
-    let (_, sigma1) = compute_sigma(msg1, tag, sig1);
-    let (_, sigma2) = compute_sigma(msg2, tag, sig2);
+    trace_with::<Blake2bSuite>(tag, msg1, msg2, sig1, sig2)
+}
+
+/// Traces two signatures made with the suite `S`
+pub fn trace_with<S: HashSuite>(
+    tag: &Tag,
+    msg1: &[u8],
+    msg2: &[u8],
+    sig1: &Signature,
+    sig2: &Signature,
+) -> i32 {
+    let (_, sigma1) = compute_sigma_with::<S>(msg1, tag, sig1);
+    let (_, sigma2) = compute_sigma_with::<S>(msg2, tag, sig2);
     let intersecting_points = (0..(tag.pubkeys.len()))
         .filter(|&i| sigma1[i] == sigma2[i])
         .collect::<Vec<usize>>();
@@ -479,7 +646,7 @@ pub fn trs_trace(set_publickey: *const u8, set_publickey_len: usize, issue: &mut
             PublicKey::from_bytes(&arr).unwrap()
         })
         .collect();
-    let tag = Tag{pubkeys, issue:issue.to_vec()};
+    let tag = Tag{pubkeys, issue:issue.to_vec(), mode: Mode::Traceable};
     let set_c1 = unsafe { std::slice::from_raw_parts(c_n1, set_publickey_len) };
     let set_z1 = unsafe { std::slice::from_raw_parts(z_n1, set_publickey_len) };
 
@@ -530,68 +697,33 @@ pub fn trs_trace(set_publickey: *const u8, set_publickey_len: usize, issue: &mut
     // check
 }
 
-pub fn ed25519_sign_rust(private_key: &mut [u8; 32], msg: *const u8, msg_len: usize, signature: &mut [u8; 64]) {
-    let scalar = match Scalar::from_canonical_bytes(*private_key) {
-        Some(scalar) => scalar,
-        None => return, // Return or handle the error here
-    };
-    let pubkey = PublicKey(&scalar * &RISTRETTO_BASEPOINT_POINT);
-
-    let r = {
-        let mut h = Blake2b::default();
-        h.update(private_key);
-        h.update(unsafe { std::slice::from_raw_parts(msg, msg_len) });
-        Scalar::from_hash(h)
-    };
-
-    let R = (&r * &RISTRETTO_BASEPOINT_POINT).compress();
-
-        // Calculate the hash h
-    let h= {
-        let mut hasher = Blake2b::default();
-        hasher.update(&R.to_bytes());
-        hasher.update(&pubkey.as_bytes());
-        hasher.update(unsafe { std::slice::from_raw_parts(msg, msg_len) });
-        Scalar::from_hash(hasher)
-    };
-
-    let s = (r + h * scalar).reduce();
-    signature[..32].copy_from_slice(R.as_bytes());
-    signature[32..].copy_from_slice(s.as_bytes());
-
+/// # Safety
+///
+/// `msg` must be non-null, even if `msg_len` is 0, and valid for reads of `msg_len`
+/// bytes, which must not be written to during the call. `msg_len` must be at most
+/// `isize::MAX`.
+#[deprecated(note = "this is a Schnorr signature over Ristretto, not Ed25519: use schnorr::trs_schnorr_sign, or ed25519::trs_ed25519_sign for Ed25519")]
+pub unsafe fn ed25519_sign_rust(private_key: &mut [u8; 32], msg: *const u8, msg_len: usize, signature: &mut [u8; 64]) {
+    // An invalid private key leaves the signature as it was
+    schnorr::trs_schnorr_sign(private_key, msg, msg_len, signature);
 }
 
 use arrayref::array_ref;
 
-pub fn ed25519_verify_rust(
+/// # Safety
+///
+/// `msg` must be non-null, even if `msg_len` is 0, and valid for reads of `msg_len`
+/// bytes, which must not be written to during the call. `msg_len` must be at most
+/// `isize::MAX`.
+#[deprecated(note = "this is a Schnorr signature over Ristretto, not Ed25519: use schnorr::trs_schnorr_verify, or ed25519::trs_ed25519_verify for Ed25519")]
+pub unsafe fn ed25519_verify_rust(
     public_key: &[u8; 32],
     msg: *const u8,
     msg_len: usize,
     signature: &[u8; 64],
 ) -> bool {
-
-    let pubkey = match PublicKey::from_bytes(public_key) {
-        Some(pubkey) => pubkey,
-        None => return false, // Return false if PublicKey construction fails
-    };
-    let R = CompressedRistretto::from_slice(&signature[..32]).decompress().unwrap();
-    let s_bytes = array_ref!(signature, 32, 32);
-    let s = Scalar::from_canonical_bytes(*s_bytes).unwrap();
-
-    let mut hasher = Blake2b::default();
-    hasher.update(R.compress().as_bytes());
-    hasher.update(public_key);
-    hasher.update(unsafe { std::slice::from_raw_parts(msg, msg_len) });
-    let h = Scalar::from_hash(hasher);
-    // Calculate P1 = s * G
-    let p1 = &s * &RISTRETTO_BASEPOINT_POINT;
-
-    // Calculate P2 = R + h * pubKey
-    let p2 = R + (&h * pubkey.0);
-
-    // Check if P1 equals P2
-    p1 == p2
-} 
+    schnorr::trs_schnorr_verify(public_key, msg, msg_len, signature) == 0
+}
 
 // TODO(szhou): make this into a test module
 #[test]
@@ -641,7 +773,15 @@ fn test_keygen() {
 
 // functions for benchmarking
 // TODO: put these into a module
-pub fn generate_keys_and_message(ring_size: usize) -> (Vec<[u8;32]>, Vec<[u8;32]>, Tag, Vec<u8>){
+// The public keys, secret keys, tag and message used by the benchmarks
+pub type KeysAndMessage = (Vec<[u8;32]>, Vec<[u8;32]>, Tag, Vec<u8>);
+
+pub fn generate_keys_and_message(ring_size: usize) -> KeysAndMessage {
+    generate_keys_and_message_of_len(ring_size, 32)
+}
+
+// Same as generate_keys_and_message, with a message of msg_len bytes
+pub fn generate_keys_and_message_of_len(ring_size: usize, msg_len: usize) -> KeysAndMessage {
     // generate n keys 
     let mut set_publickey = vec![[0u8; 32]; ring_size];
     let mut set_secretkey = vec![[0u8; 32]; ring_size];
@@ -660,14 +800,14 @@ pub fn generate_keys_and_message(ring_size: usize) -> (Vec<[u8;32]>, Vec<[u8;32]
         let public_key= PublicKey::from_bytes(&set_publickey[i]).unwrap();
         pubkeys.push(public_key);
     }
-    let tag = Tag{issue, pubkeys};
+    let tag = Tag{issue, pubkeys, mode: Mode::Traceable};
 
-    // everyone signs the same message // TODO: message size also impacts this probably
-    let msg = vec![1u8; 32];
+    // everyone signs the same message
+    let msg = vec![1u8; msg_len];
     (set_publickey, set_secretkey, tag, msg)
 }
 
-pub fn proof_time(ring_size: usize, set_publickey: Vec<[u8;32]>, set_secretkey: Vec<[u8;32]>, tag: Tag, msg: Vec<u8>) -> Vec<Signature> {
+pub fn proof_time(ring_size: usize, set_publickey: &[[u8;32]], set_secretkey: &[[u8;32]], tag: &Tag, msg: &[u8]) -> Vec<Signature> {
     // Times the signing of messages, in other words, proof generation time 
 
     // PrivateKey expects the scalar concatenated with the public key
@@ -677,21 +817,21 @@ pub fn proof_time(ring_size: usize, set_publickey: Vec<[u8;32]>, set_secretkey:
     // for i in 0..ring_size {
     let secretkey = [&set_secretkey[0][..], &set_publickey[0][..]].concat();
     // sign(&mut rng, &msg, &tag, &PrivateKey::from_bytes(&secretkey).unwrap())
-    sigs.push(sign(&mut rng, &msg, &tag, &PrivateKey::from_bytes(&secretkey).unwrap()));
+    sigs.push(sign(&mut rng, msg, tag, &PrivateKey::from_bytes(&secretkey).unwrap()));
     // }
     sigs
 }
 
-pub fn verification_time(msg: Vec<u8>, tag: Tag, sigs: Vec<Signature>, ring_size: usize) {
+pub fn verification_time(msg: &[u8], tag: &Tag, sigs: &[Signature], ring_size: usize) -> bool {
     // Times the verification of messages, in other words, proof verification time 
     // a bunch of users sign the same message
     // for i in 0..ring_size {
-    verify(&msg, &tag, &sigs[0]);
+    verify(msg, tag, &sigs[0])
     // }
 }
 
-pub fn trace_time(msg1: Vec<u8>, msg2: Vec<u8>, tag: Tag, sig1: Signature, sig2: Signature) {
-    trace(&tag, &msg1, &msg2, &sig1, &sig2);
+pub fn trace_time(msg1: &[u8], msg2: &[u8], tag: &Tag, sig1: &Signature, sig2: &Signature) -> i32 {
+    trace(tag, msg1, msg2, sig1, sig2)
 }
 
 pub fn generation_time(ring_size: usize) {
@@ -703,81 +843,111 @@ pub fn generation_time(ring_size: usize) {
 
 // benchmarking proof size
 #[test]
-fn proof_size() -> io::Result<()> { 
-    use std::mem;
-
-    let mut points = Vec::new();
+fn proof_size() { 
+    use bench_harness::{emit, Record};
 
-    for &n in &RING_SIZES {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
         let tup = generate_keys_and_message(n);
         let set_publickey = tup.0;
         let set_secretkey = tup.1;
         let tag = tup.2;
         let msg = tup.3;
 
-        let sigs = proof_time(n, set_publickey.clone(), set_secretkey.clone(), tag.clone(), msg.clone());
+        let sigs = proof_time(n, &set_publickey, &set_secretkey, &tag, &msg);
         let sig = &sigs[0];
 
-        // NOTE: mem::size_of_val(&sig.aa1) = mem::size_of::<RistrettoPoint>() = 160 bytes
-        // NOTE: mem::size_of_val(&sig.aa1) = mem::size_of::<RistrettoPoint>() = 160 bytes
-        // 
-        let total_size = mem::size_of_val(&sig.aa1) + sig.cs.len()*mem::size_of_val(&sig.cs[0]) + sig.zs.len()*mem::size_of_val(&sig.zs[0]);
-        println!("Ring size: {}, Proof size: {} bytes", n, total_size);
-        points.push((n as i32, total_size as f64));
+        // The signature on its own, and together with the ring and issue a verifier also needs
+        let sig_size = sig.to_bytes().len();
+        let tag_size = tag.to_bytes().len();
+        assert_eq!(sig_size, 32 + 64 * n);
+        emit(&Record::size("trs", "size", n, sig_size));
+        emit(&Record::size("trs", "size_with_tag", n, sig_size + tag_size));
     }
+}
 
-    // write to csv
-    let mut file = File::create("proof_sizes.csv")?;
-    writeln!(file, "ring_size,proof_size")?;
+#[test]
+fn test_signature_roundtrip() {
+    let n = 4;
+    let (set_publickey, set_secretkey, tag, msg) = generate_keys_and_message(n);
+    let sigs = proof_time(n, &set_publickey, &set_secretkey, &tag, &msg);
+    let sig = &sigs[0];
+
+    let bytes = sig.to_bytes();
+    assert_eq!(bytes.len(), 32 + 64 * n);
+    let decoded = Signature::from_bytes(&bytes).unwrap();
+    assert_eq!(&decoded, sig);
+    assert!(verify(&msg, &tag, &decoded));
+
+    // Lengths which do not match a whole ring are rejected
+    assert!(Signature::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+    assert!(Signature::from_bytes(&bytes[..31]).is_none());
+
+    let tag_bytes = tag.to_bytes();
+    assert_eq!(tag_bytes.len(), 8 + 32 * n + tag.issue.len());
+    assert_eq!(Tag::from_bytes(&tag_bytes).unwrap(), tag);
+    assert!(Tag::from_bytes(&tag_bytes[..8 + 32 * n - 1]).is_none());
+}
 
-    for (x,y) in &points {
-        writeln!(file, "{},{}", x, y)?;
-    }
+#[test]
+fn test_linkable_only_mode() {
+    let n = 4;
+    let (set_publickey, set_secretkey, mut tag, msg) = generate_keys_and_message(n);
+    tag.mode = Mode::LinkableOnly;
+    let sig1 = proof_time(n, &set_publickey, &set_secretkey, &tag, &msg).remove(0);
+    let sig2 = proof_time(n, &set_publickey, &set_secretkey, &tag, b"other msg").remove(0);
+    assert!(verify(&msg, &tag, &sig1));
+    assert!(verify(b"other msg", &tag, &sig2));
+    assert!(!verify(b"other msg", &tag, &sig1));
+
+    // The same signer is linked on different messages but never identified
+    assert_eq!(trace(&tag, &msg, b"other msg", &sig1, &sig2), -1);
+    let other_secretkey = [&set_secretkey[1][..], &set_publickey[1][..]].concat();
+    let sig3 = sign(&mut rand::thread_rng(), &msg, &tag, &PrivateKey::from_bytes(&other_secretkey).unwrap());
+    assert_eq!(trace(&tag, &msg, &msg, &sig1, &sig3), -2);
+
+    // The mode is part of the tag, so a signature does not verify in the other mode
+    let tag_bytes = tag.to_bytes();
+    assert_eq!(Tag::from_bytes(&tag_bytes).unwrap(), tag);
+    tag.mode = Mode::Traceable;
+    assert!(!verify(&msg, &tag, &sig1));
+    assert_eq!(Tag::from_bytes(&tag.to_bytes()).unwrap().mode, Mode::Traceable);
+}
+
+#[test]
+fn test_hash_to_group_dsts() {
+    let (_, _, tag, msg) = generate_keys_and_message(4);
+    let encoded: Vec<u8> = tag
+        .pubkeys
+        .iter()
+        .flat_map(|pk| pk.as_bytes())
+        .chain(tag.issue.iter().cloned())
+        .collect();
+
+    // h and A₀ are the RFC 9380 hashes of the encoded tag, and the message, under their DSTs
+    let h = RistrettoPoint::from_uniform_bytes(&hash_to_group::hash_to_ristretto255(
+        &encoded,
+        hash_to_group::TRS_TRACING_BASE_DST,
+    ));
+    assert_eq!(tag.hash0::<Blake2bSuite>().into_point(), h);
+
+    let mut d = tag.hash1::<Blake2bSuite>();
+    d.update(&msg);
+    let aa0 = RistrettoPoint::from_uniform_bytes(&hash_to_group::hash_to_ristretto255(
+        &[encoded, msg].concat(),
+        hash_to_group::TRS_A0_DST,
+    ));
+    assert_eq!(d.into_point(), aa0);
+    assert_ne!(aa0, h);
+}
 
-    println!("CSV written to proof_sizes.csv");
-    Ok(())
-}
-
-// NOTE: this function assumes that proof_sizes.csv already exists
-// NOTE: just decided to plot through google sheets
-// pub fn plot_proof_size() -> Result<(), Box<dyn std::error::Error>> {
-//     use plotters::prelude::*;
-
-//     let mut points = Vec::new();
-//     let file = File::open("proof_sizes.csv")?;
-//     for line in io::BufReader::new(file).lines() {
-//         let line = line?;
-//         let parts: Vec<&str> = line.split(',').collect();
-//         if parts[0] == "ring_size" { continue } // skip header
-//         let ring_size: f64 = parts[0].parse().unwrap();
-//         let proof_size: f64 = parts[1].parse().unwrap();
-//         println!("Ring size: {}, Proof size: {}", ring_size, proof_size);
-//         points.push((ring_size as i32, proof_size));
-//     }
-
-//     // NOTE: this is all from GPT
-//     let root = BitMapBackend::new("proof_size.png", (640, 480)).into_drawing_area();
-//     root.fill(&WHITE)?;
-
-//     let mut chart = ChartBuilder::on(&root)
-//         .caption("Proof size vs ring size", ("sans-serif", 30))
-//         .margin(20)
-//         .x_label_area_size(40)
-//         .y_label_area_size(40)
-//         .build_cartesian_2d(0..1024, 0f64..70000f64)?;
-
-//     chart.configure_mesh()
-//     .x_desc("Ring size")
-//     .y_desc("Proof size (bytes)").draw()?;
-
-//     chart.draw_series(LineSeries::new(
-//         points.clone(),
-//         &RED,
-//     ))?
-//     .label("Proof size (bytes)")
-//     .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
-
-//     chart.configure_series_labels().border_style(&BLACK).draw()?;
-
-//     Ok(())
-// }
\ No newline at end of file
+#[test]
+fn test_prehashed_message() {
+    let n = 4;
+    let (set_publickey, set_secretkey, tag, msg) = generate_keys_and_message_of_len(n, 1 << 16);
+    let digest = prehash(&msg);
+    let sigs = proof_time(n, &set_publickey, &set_secretkey, &tag, &digest);
+
+    assert!(verify(&digest, &tag, &sigs[0]));
+    assert!(!verify(&msg, &tag, &sigs[0]));
+    assert_ne!(prehash(&msg[1..]), digest);
+}
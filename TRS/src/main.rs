@@ -26,7 +26,7 @@ fn main(){
         let public_key= PublicKey::from_bytes(&set_publickey[i]).unwrap();
         pubkeys.push(public_key);
     }
-    let tag = Tag{issue, pubkeys};
+    let tag = Tag{issue, pubkeys, mode: Mode::Traceable};
     println!("Tag generation done.");
 
     // 3. Sign, verify, and trace some messages
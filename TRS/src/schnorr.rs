@@ -0,0 +1,252 @@
+// Schnorr signatures over Ristretto with the ring's keys.
+//
+// A signature is R || s for R := rG and s := r + cx, where r := H(x || m) and
+// c := H(R || y || m). These are not Ed25519 signatures, see `crate::ed25519` for those.
+// The hash is that of a `HashSuite`, Blake2b unless another suite is given with
+// `sign_with` and `verify_with`.
+
+use crate::suite::{Blake2bSuite, HashSuite};
+use crate::{PrivateKey, PublicKey};
+
+use arrayref::array_ref;
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT, ristretto::CompressedRistretto, scalar::Scalar,
+};
+use digest::Update;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The private key is not a canonical scalar
+    InvalidPrivateKey,
+    /// The public key is not a Ristretto point
+    InvalidPublicKey,
+    /// R is not a Ristretto point
+    InvalidCommitment,
+    /// s is not a canonical scalar
+    NonCanonicalResponse,
+    /// The signature does not verify
+    InvalidSignature,
+}
+
+impl Error {
+    /// The code the FFI functions return for this error. Success is 0
+    pub fn code(&self) -> i32 {
+        match self {
+            Error::InvalidPrivateKey => 1,
+            Error::InvalidPublicKey => 2,
+            Error::InvalidCommitment => 3,
+            Error::NonCanonicalResponse => 4,
+            Error::InvalidSignature => 5,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidPrivateKey => write!(f, "the private key is not a canonical scalar"),
+            Error::InvalidPublicKey => write!(f, "the public key is not a valid point"),
+            Error::InvalidCommitment => write!(f, "the signature's R is not a valid point"),
+            Error::NonCanonicalResponse => write!(f, "the signature's s is not a canonical scalar"),
+            Error::InvalidSignature => write!(f, "the signature does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature {
+    r: CompressedRistretto,
+    s: Scalar,
+}
+
+impl Signature {
+    /// Serialize this signature as the compressed R followed by s
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(self.r.as_bytes());
+        bytes[32..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    /// Deserialize a signature produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Signature, Error> {
+        let r = CompressedRistretto(*array_ref!(bytes, 0, 32));
+        if r.decompress().is_none() {
+            return Err(Error::InvalidCommitment);
+        }
+        let s = Scalar::from_canonical_bytes(*array_ref!(bytes, 32, 32))
+            .ok_or(Error::NonCanonicalResponse)?;
+        Ok(Signature { r, s })
+    }
+}
+
+/// Signs a message with Blake2b
+pub fn sign(privkey: &PrivateKey, msg: &[u8]) -> Signature {
+    sign_with::<Blake2bSuite>(privkey, msg)
+}
+
+/// Signs a message, hashing with the suite `S`
+pub fn sign_with<S: HashSuite>(privkey: &PrivateKey, msg: &[u8]) -> Signature {
+    // r := H(x || m)
+    let r = {
+        let mut h = S::hash(b"");
+        h.update(privkey.0.as_bytes());
+        h.update(msg);
+        Scalar::from_hash(h)
+    };
+    let rr = (r * RISTRETTO_BASEPOINT_POINT).compress();
+
+    let c = challenge::<S>(&rr, &privkey.1.compress(), msg);
+    Signature {
+        r: rr,
+        s: r + c * privkey.0,
+    }
+}
+
+/// Verifies a signature made with Blake2b
+pub fn verify(pubkey: &PublicKey, msg: &[u8], sig: &Signature) -> Result<(), Error> {
+    verify_with::<Blake2bSuite>(pubkey, msg, sig)
+}
+
+/// Verifies a signature made with the suite `S`
+pub fn verify_with<S: HashSuite>(
+    pubkey: &PublicKey,
+    msg: &[u8],
+    sig: &Signature,
+) -> Result<(), Error> {
+    let rr = sig.r.decompress().ok_or(Error::InvalidCommitment)?;
+    let c = challenge::<S>(&sig.r, &pubkey.0.compress(), msg);
+
+    // sG == R + cY
+    if sig.s * RISTRETTO_BASEPOINT_POINT == rr + c * pubkey.0 {
+        Ok(())
+    } else {
+        Err(Error::InvalidSignature)
+    }
+}
+
+// c := H(R || Y || m)
+fn challenge<S: HashSuite>(
+    rr: &CompressedRistretto,
+    pubkey: &CompressedRistretto,
+    msg: &[u8],
+) -> Scalar {
+    let mut h = S::hash(b"");
+    h.update(rr.as_bytes());
+    h.update(pubkey.as_bytes());
+    h.update(msg);
+    Scalar::from_hash(h)
+}
+
+/// Signs `msg_len` bytes at `msg` with a 32 byte private key, writing the 64 byte signature.
+/// Returns 0, or the code of the error if the private key is invalid
+///
+/// # Safety
+///
+/// `msg` must be non-null, even if `msg_len` is 0, and valid for reads of `msg_len`
+/// bytes, which must not be written to during the call. `msg_len` must be at most
+/// `isize::MAX`.
+pub unsafe fn trs_schnorr_sign(
+    private_key: &[u8; 32],
+    msg: *const u8,
+    msg_len: usize,
+    signature: &mut [u8; 64],
+) -> i32 {
+    let x = match Scalar::from_canonical_bytes(*private_key) {
+        Some(x) => x,
+        None => return Error::InvalidPrivateKey.code(),
+    };
+    let privkey = PrivateKey(x, x * RISTRETTO_BASEPOINT_POINT);
+    let msg = std::slice::from_raw_parts(msg, msg_len);
+    *signature = sign(&privkey, msg).to_bytes();
+    0
+}
+
+/// Verifies a 64 byte signature on `msg_len` bytes at `msg` with a 32 byte public key.
+/// Returns 0 if it is valid, and the code of the error otherwise
+///
+/// # Safety
+///
+/// `msg` must be non-null, even if `msg_len` is 0, and valid for reads of `msg_len`
+/// bytes, which must not be written to during the call. `msg_len` must be at most
+/// `isize::MAX`.
+pub unsafe fn trs_schnorr_verify(
+    public_key: &[u8; 32],
+    msg: *const u8,
+    msg_len: usize,
+    signature: &[u8; 64],
+) -> i32 {
+    let pubkey = match PublicKey::from_bytes(public_key) {
+        Some(pubkey) => pubkey,
+        None => return Error::InvalidPublicKey.code(),
+    };
+    let msg = std::slice::from_raw_parts(msg, msg_len);
+    match Signature::from_bytes(signature).and_then(|sig| verify(&pubkey, msg, &sig)) {
+        Ok(()) => 0,
+        Err(e) => e.code(),
+    }
+}
+
+#[cfg(test)]
+fn keypair() -> PrivateKey {
+    let x = Scalar::random(&mut rand::thread_rng());
+    PrivateKey(x, x * RISTRETTO_BASEPOINT_POINT)
+}
+
+#[test]
+fn test_schnorr_sign_and_verify() {
+    let privkey = keypair();
+    let pubkey = PublicKey(privkey.1);
+    let sig = sign(&privkey, b"msg");
+    assert_eq!(verify(&pubkey, b"msg", &sig), Ok(()));
+    assert_eq!(verify(&pubkey, b"other msg", &sig), Err(Error::InvalidSignature));
+    assert_eq!(
+        verify(&PublicKey(keypair().1), b"msg", &sig),
+        Err(Error::InvalidSignature)
+    );
+    assert_eq!(Signature::from_bytes(&sig.to_bytes()), Ok(sig));
+
+    // Signatures only verify under the suite they were made with
+    let sig = sign_with::<crate::suite::Sha512Suite>(&privkey, b"msg");
+    assert_eq!(verify_with::<crate::suite::Sha512Suite>(&pubkey, b"msg", &sig), Ok(()));
+    assert_eq!(verify(&pubkey, b"msg", &sig), Err(Error::InvalidSignature));
+}
+
+#[test]
+fn test_schnorr_malformed() {
+    let privkey = keypair();
+    let mut bytes = sign(&privkey, b"msg").to_bytes();
+
+    // s + ℓ is the same scalar but is not canonical
+    let mut s = *array_ref!(bytes, 32, 32);
+    let mut carry = 0u16;
+    for (b, l) in s.iter_mut().zip(curve25519_dalek::constants::BASEPOINT_ORDER.as_bytes()) {
+        let sum = *b as u16 + *l as u16 + carry;
+        *b = sum as u8;
+        carry = sum >> 8;
+    }
+    let mut non_canonical = bytes;
+    non_canonical[32..].copy_from_slice(&s);
+    assert_eq!(Signature::from_bytes(&non_canonical), Err(Error::NonCanonicalResponse));
+
+    bytes[..32].copy_from_slice(&[0xff; 32]);
+    assert_eq!(Signature::from_bytes(&bytes), Err(Error::InvalidCommitment));
+
+    // The FFI functions return the error codes rather than panicking
+    let pubkey = privkey.1.compress().to_bytes();
+    let msg = b"msg";
+    unsafe {
+        assert_eq!(trs_schnorr_verify(&pubkey, msg.as_ptr(), 3, &bytes), 3);
+        assert_eq!(trs_schnorr_verify(&pubkey, msg.as_ptr(), 3, &non_canonical), 4);
+        assert_eq!(trs_schnorr_verify(&[0xff; 32], msg.as_ptr(), 3, &bytes), 2);
+
+        let mut sig = [0u8; 64];
+        assert_eq!(trs_schnorr_sign(&[0xff; 32], msg.as_ptr(), 3, &mut sig), 1);
+        assert_eq!(trs_schnorr_sign(&privkey.0.to_bytes(), msg.as_ptr(), 3, &mut sig), 0);
+        assert_eq!(trs_schnorr_verify(&pubkey, msg.as_ptr(), 3, &sig), 0);
+        assert_eq!(trs_schnorr_verify(&pubkey, msg.as_ptr(), 2, &sig), 5);
+    }
+}
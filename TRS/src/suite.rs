@@ -0,0 +1,165 @@
+// The hash functions a traceable ring signature is made with.
+//
+// A suite fixes the RFC 9380 expander that the tracing base h and A₀ are hashed to the
+// group with, and the hash the challenge is hashed to a scalar with. `sign` and `verify`
+// use `Blake2bSuite`, and `sign_with` and `verify_with` take the suite as a type
+// parameter, e.g. to interoperate with an implementation that only has SHA-512 or SHA-3.
+// A signature only verifies under the suite it was made with.
+
+use blake2::Blake2b;
+use digest::{consts::U64, Digest, Update};
+use hash_to_group::Expander;
+use sha2::Sha512;
+use sha3::Sha3_512;
+
+pub trait HashSuite {
+    /// The hash challenges are hashed to scalars with
+    type Hash: Digest<OutputSize = U64> + Default + Update;
+
+    /// The expander h and A₀ are hashed to the group with
+    const EXPANDER: Expander;
+
+    /// DST of the tracing base h := H(L)
+    const TRACING_BASE_DST: &'static [u8];
+
+    /// DST of A₀ := H'(L, m)
+    const A0_DST: &'static [u8];
+
+    /// The hash of the suite, separated from its other uses by `domain`
+    fn hash(domain: &[u8]) -> Self::Hash;
+}
+
+/// Blake2b challenges with the `ristretto255_XMD:SHA-512_R255MAP_RO_` hash to the group.
+/// This is the default suite
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2bSuite;
+
+impl HashSuite for Blake2bSuite {
+    type Hash = Blake2b;
+    const EXPANDER: Expander = Expander::XmdSha512;
+    const TRACING_BASE_DST: &'static [u8] = hash_to_group::TRS_TRACING_BASE_DST;
+    const A0_DST: &'static [u8] = hash_to_group::TRS_A0_DST;
+
+    // The domain is the Blake2b personalization, so it must be at most 16 bytes
+    fn hash(domain: &[u8]) -> Blake2b {
+        Blake2b::with_params(b"", b"", domain)
+    }
+}
+
+/// SHA-512 challenges with the `ristretto255_XMD:SHA-512_R255MAP_RO_` hash to the group
+#[derive(Clone, Copy, Debug)]
+pub struct Sha512Suite;
+
+impl HashSuite for Sha512Suite {
+    type Hash = Sha512;
+    const EXPANDER: Expander = Expander::XmdSha512;
+    const TRACING_BASE_DST: &'static [u8] = hash_to_group::TRS_TRACING_BASE_DST;
+    const A0_DST: &'static [u8] = hash_to_group::TRS_A0_DST;
+
+    fn hash(domain: &[u8]) -> Sha512 {
+        prefixed(domain)
+    }
+}
+
+/// SHA3-512 challenges with the `ristretto255_XOF:SHAKE256_R255MAP_RO_` hash to the group
+#[derive(Clone, Copy, Debug)]
+pub struct Sha3Suite;
+
+impl HashSuite for Sha3Suite {
+    type Hash = Sha3_512;
+    const EXPANDER: Expander = Expander::XofShake256;
+    const TRACING_BASE_DST: &'static [u8] = hash_to_group::TRS_TRACING_BASE_XOF_DST;
+    const A0_DST: &'static [u8] = hash_to_group::TRS_A0_XOF_DST;
+
+    fn hash(domain: &[u8]) -> Sha3_512 {
+        prefixed(domain)
+    }
+}
+
+// A hash without a personalization starts with the domain and its length, so that no
+// two domains are a prefix of each other
+fn prefixed<D: Default + Update>(domain: &[u8]) -> D {
+    let mut h = D::default();
+    h.update([domain.len() as u8]);
+    h.update(domain);
+    h
+}
+
+#[cfg(test)]
+fn vector_tag() -> (crate::Tag, crate::PrivateKey) {
+    use curve25519_dalek::{constants::RISTRETTO_BASEPOINT_POINT, scalar::Scalar};
+
+    // The ring is 1G, 2G, 3G and the signer is the second member
+    let pubkeys = (1..=3u64)
+        .map(|x| crate::PublicKey(Scalar::from(x) * RISTRETTO_BASEPOINT_POINT))
+        .collect();
+    let tag = crate::Tag {
+        pubkeys,
+        issue: b"issue".to_vec(),
+        mode: crate::Mode::Traceable,
+    };
+    let x = Scalar::from(2u64);
+    (tag, crate::PrivateKey(x, x * RISTRETTO_BASEPOINT_POINT))
+}
+
+// Test vectors of h and A₀ for the tag above and the message "msg", and a signature by
+// the second member, in hex. These were generated by this implementation, so they are
+// regression vectors which catch a change to the hashes, not correctness vectors. The
+// hash to the group itself is checked against RFC vectors in hash-to-group
+#[cfg(test)]
+fn check_vectors<S: HashSuite>(h: &str, aa0: &str, sig: &str) {
+    let (tag, privkey) = vector_tag();
+    assert_eq!(hex::encode(tag.hash0::<S>().into_point().compress().as_bytes()), h);
+    let mut d = tag.hash1::<S>();
+    d.update(b"msg");
+    assert_eq!(hex::encode(d.into_point().compress().as_bytes()), aa0);
+
+    let sig = crate::Signature::from_bytes(&hex::decode(sig).unwrap()).unwrap();
+    assert!(crate::verify_with::<S>(b"msg", &tag, &sig));
+    assert!(!crate::verify_with::<S>(b"other msg", &tag, &sig));
+
+    // A fresh signature traces to the same signer
+    let other = crate::sign_with::<S, _>(&mut rand::thread_rng(), b"other msg", &tag, &privkey);
+    assert_eq!(crate::trace_with::<S>(&tag, b"msg", b"other msg", &sig, &other), 1);
+}
+
+#[test]
+fn test_blake2b_suite_vectors() {
+    check_vectors::<Blake2bSuite>(
+        "248345d10eedda14d1ad7fe749cdaa54ba8f97fddca933788c858b45784c7f5d",
+        "fa7edbd3230acba1faecdf77334b461efba70252cb95d891407240f56f593f42",
+        "ba8e2beb70f4be58a8b1cf18291a35c414d0ad4213db852b9e436f739160fd1f7c10c58f37103ed80df99d2fbdc46f9e1381a6e2d67d96de13242ea90dccf80378ff4d917d94f3083479e8625e9e0df90331dc1e740b531ae2ac5a6b299356037929b6d3f6b762311716f0cb1e554a98ade9b7bceff36c1960006d6e016fad0817744376fcdb52bb1a33c0d6eb30a686ea2456bb0182b74aadac10badab0a307dd94d9cb9104be89dddc46a45ed7709426c97916fa604765242392645e0c2605c633d2535a8d786babaf519ed1a77431cdd2d9edefd8c311c826ebc97c61820d",
+    );
+}
+
+#[test]
+fn test_sha512_suite_vectors() {
+    check_vectors::<Sha512Suite>(
+        "248345d10eedda14d1ad7fe749cdaa54ba8f97fddca933788c858b45784c7f5d",
+        "fa7edbd3230acba1faecdf77334b461efba70252cb95d891407240f56f593f42",
+        "ba8e2beb70f4be58a8b1cf18291a35c414d0ad4213db852b9e436f739160fd1f0ba2267f81e67e8ed88dfd0e05ce72d3552d0da09de2aae00751ac0d4a1df103d08fe36956ddcca7f4398ffab15de238b02c64b99cb509095f9ad1ebf227300ceb67dc12d1a34c2aa7c309fa7e91540da09d1695ad41ecac07d90a5232c35205ea2a2e5c19b5feea03141af0fc0cdce0a6d9dd7665ed99cb408e1847eda3700989533ee16965457aee6bb4ef2364919492dced2ded7088ae06b37c29fa8db506acbe4c4634cd8e55f1ce320e12fd8f23702abea8ce1a6458b1f2fba15ae24c0c",
+    );
+}
+
+#[test]
+fn test_sha3_suite_vectors() {
+    check_vectors::<Sha3Suite>(
+        "90ec540ce1598adf26ebba2038cc04ed0abdf4da00b3d66748846edbf49c1c0b",
+        "ae400e8aae485351870a7ad30a2a2b0c16ed90415d5c3d7540b5db3d31ba3735",
+        "70ba2a2668208f1151278be23acfe102369e0c73f3dbf8ca1ea9f1cb79f0e71564d9199adf350db3283c52161a8df7cdac578ca7f77fda9da35fef91343c5b089c8aff791fc2a562f3300ae92291df784c929c3bcfd0d33815e68c9e545f8408ba7a605299856e5f7e5afa6a576ef085706e4ffa91744ebf2628142904698905b8ee78061a2c746898122f788014bb25723cb5cb6ed994bd926ee1573ef5cd04b3893caa69f72e969505dcf187cbfac771fa0fc5248c0fc39a234acc980f840cd518dfa529a8ea6b85b0c267e520244bda421ac1857d72a9d6b2f923c53e8909",
+    );
+}
+
+#[test]
+fn test_suites_do_not_cross_verify() {
+    let (tag, privkey) = vector_tag();
+    let mut rng = rand::thread_rng();
+    let sig = crate::sign_with::<Sha512Suite, _>(&mut rng, b"msg", &tag, &privkey);
+    assert!(crate::verify_with::<Sha512Suite>(b"msg", &tag, &sig));
+    assert!(!crate::verify_with::<Blake2bSuite>(b"msg", &tag, &sig));
+    assert!(!crate::verify_with::<Sha3Suite>(b"msg", &tag, &sig));
+
+    // The default functions use Blake2b
+    let sig = crate::sign(&mut rng, b"msg", &tag, &privkey);
+    assert!(crate::verify_with::<Blake2bSuite>(b"msg", &tag, &sig));
+}
@@ -0,0 +1,534 @@
+// Threshold traceable ring signatures, in which t distinct members of a ring of n jointly
+// sign a message, as the threshold OR-proof of Cramer, Damgård and Schoenmakers over the
+// Σ-protocol of a traceable ring signature.
+//
+// Every signer k keeps their own A₁⁽ᵏ⁾ and σ-line σᵢ⁽ᵏ⁾ := A₀ + (i+1)A₁⁽ᵏ⁾, on which
+// their own σ is xₖh, so a member who signs twice in an issue is traced as they would
+// be for an ordinary signature. The proof for member i is an OR over the t σ-lines that
+// they know the x with yᵢ = xG and σᵢ⁽ᵏ⁾ = xh, with a challenge cᵢ⁽ᵏ⁾ and a response
+// zᵢ⁽ᵏ⁾ for each line, and cᵢ := Σₖ cᵢ⁽ᵏ⁾. The n challenges cᵢ must lie on a polynomial
+// f of degree n − t with f(0) := H(L, m, A₀, {A₁⁽ᵏ⁾}, {aᵢ⁽ᵏ⁾}, {bᵢ⁽ᵏ⁾}). Only n − t
+// of them can be chosen before the challenge is known, so the other t must be answered
+// with the keys of t distinct members. A member's σ is on only one line through A₀, and
+// the line of each of them is determined by the message, the tag and their key, so
+// verification checks the A₁⁽ᵏ⁾ are distinct without learning who signed.
+//
+// The challenges are hashed with a `HashSuite`, Blake2b unless another suite is given
+// with the `_with` functions.
+
+use crate::suite::{Blake2bSuite, HashSuite};
+use crate::{sigma_i, Mode, PrivateKey, Tag};
+
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT, ristretto::CompressedRistretto,
+    ristretto::RistrettoPoint, scalar::Scalar, traits::Identity,
+};
+
+use arrayref::array_ref;
+use core::convert::TryFrom;
+use digest::Update;
+use rand_core::{CryptoRng, RngCore};
+
+static DOMAIN_THRESHOLD: &[u8] = b"rust-ringsig-5";
+
+/// A signature on a message by t distinct members of a ring
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ThresholdSignature {
+    // Each signer's A₁, or key image in linkable-only mode
+    aa1s: Vec<RistrettoPoint>,
+    // cᵢ⁽ᵏ⁾ and zᵢ⁽ᵏ⁾ for member i and the σ-line of signer k, at i·t + k
+    cs: Vec<Scalar>,
+    zs: Vec<Scalar>,
+}
+
+impl ThresholdSignature {
+    /// The number of signers
+    pub fn signers(&self) -> usize {
+        self.aa1s.len()
+    }
+
+    /// The number of members of the ring this signature was made for
+    pub fn ring_size(&self) -> usize {
+        self.cs.len() / self.aa1s.len()
+    }
+
+    /// Serialize this signature as the number of signers as 8 little endian bytes, every
+    /// compressed A₁, and then every cᵢ⁽ᵏ⁾ and every zᵢ⁽ᵏ⁾, member by member. This is
+    /// 8 + 32t + 64nt bytes for t signers in a ring of size n
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = (self.aa1s.len() as u64).to_le_bytes().to_vec();
+        for aa1 in &self.aa1s {
+            bytes.extend_from_slice(aa1.compress().as_bytes());
+        }
+        for s in self.cs.iter().chain(&self.zs) {
+            bytes.extend_from_slice(s.as_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize a signature produced by `to_bytes`. The ring size is implied by the length
+    pub fn from_bytes(bytes: &[u8]) -> Option<ThresholdSignature> {
+        if bytes.len() < 8 {
+            return None;
+        }
+        let signers = usize::try_from(u64::from_le_bytes(*array_ref!(bytes, 0, 8))).ok()?;
+        let rest = &bytes[8..];
+        let aa1s_len = signers.checked_mul(32)?;
+        if signers == 0
+            || rest.len() < aa1s_len
+            || !(rest.len() - aa1s_len).is_multiple_of(2 * aa1s_len)
+        {
+            return None;
+        }
+        let (aa1s, scalars) = rest.split_at(aa1s_len);
+
+        let aa1s = aa1s
+            .chunks(32)
+            .map(|chunk| CompressedRistretto::from_slice(chunk).decompress())
+            .collect::<Option<Vec<RistrettoPoint>>>()?;
+        let scalars = scalars
+            .chunks(32)
+            .map(|chunk| Scalar::from_canonical_bytes(*array_ref!(chunk, 0, 32)))
+            .collect::<Option<Vec<Scalar>>>()?;
+        let (cs, zs) = scalars.split_at(scalars.len() / 2);
+
+        Some(ThresholdSignature {
+            aa1s,
+            cs: cs.to_vec(),
+            zs: zs.to_vec(),
+        })
+    }
+}
+
+// A₀ := H'(L, m)
+fn aa0<S: HashSuite>(msg: &[u8], tag: &Tag) -> RistrettoPoint {
+    let mut d = tag.hash1::<S>();
+    d.update(msg);
+    d.into_point()
+}
+
+// The σ-line of every signer, σᵢ⁽ᵏ⁾ at [k][i]
+fn sigma_lines(
+    tag: &Tag,
+    aa0: &RistrettoPoint,
+    aa1s: &[RistrettoPoint],
+) -> Vec<Vec<RistrettoPoint>> {
+    aa1s.iter()
+        .map(|aa1| {
+            (0..tag.pubkeys.len())
+                .map(|i| sigma_i(tag.mode, aa0, aa1, i))
+                .collect()
+        })
+        .collect()
+}
+
+// c := H(L, m, A₀, {A₁⁽ᵏ⁾}, {aᵢ⁽ᵏ⁾}, {bᵢ⁽ᵏ⁾})
+fn challenge<S: HashSuite>(
+    msg: &[u8],
+    tag: &Tag,
+    aa0: &RistrettoPoint,
+    aa1s: &[RistrettoPoint],
+    a: &[RistrettoPoint],
+    b: &[RistrettoPoint],
+) -> Scalar {
+    let mut d = tag.hash_self(S::hash(DOMAIN_THRESHOLD));
+    d.update((msg.len() as u64).to_le_bytes());
+    d.update(msg);
+    d.update((aa1s.len() as u64).to_le_bytes());
+    for p in core::iter::once(aa0).chain(aa1s).chain(a).chain(b) {
+        d.update(p.compress().as_bytes());
+    }
+    Scalar::from_hash(d)
+}
+
+// Member i's x-coordinate on the challenge polynomial, i + 1 as f(0) is the challenge
+fn x(i: usize) -> Scalar {
+    Scalar::from((i + 1) as u64)
+}
+
+// cᵢ := Σₖ cᵢ⁽ᵏ⁾
+fn member_challenge(cs: &[Scalar], t: usize, i: usize) -> Scalar {
+    cs[i * t..(i + 1) * t].iter().sum()
+}
+
+// Evaluates the polynomial of least degree through the points at each of xs, as
+// f(x) = Σⱼ yⱼλⱼ Π_{l ≠ j} (x - xₗ), where λⱼ := Π_{l ≠ j} 1 / (xⱼ - xₗ). The
+// products for each x are taken from running products from either end
+fn interpolate(points: &[(Scalar, Scalar)], xs: &[Scalar]) -> Vec<Scalar> {
+    let weights: Vec<Scalar> = points
+        .iter()
+        .enumerate()
+        .map(|(j, (xj, yj))| {
+            let denom = points
+                .iter()
+                .enumerate()
+                .filter(|&(l, _)| l != j)
+                .fold(Scalar::one(), |acc, (_, (xl, _))| acc * (xj - xl));
+            yj * denom.invert()
+        })
+        .collect();
+
+    xs.iter()
+        .map(|x| {
+            let mut suffix = vec![Scalar::one(); points.len() + 1];
+            for j in (0..points.len()).rev() {
+                suffix[j] = suffix[j + 1] * (x - points[j].0);
+            }
+            let mut prefix = Scalar::one();
+            let mut sum = Scalar::zero();
+            for (j, weight) in weights.iter().enumerate() {
+                sum += weight * prefix * suffix[j + 1];
+                prefix *= x - points[j].0;
+            }
+            sum
+        })
+        .collect()
+}
+
+/// Signs a message with the tag by every one of the private keys. Returns `None` if there
+/// are no keys, a key is not of a member of the ring, or two are of the same member
+pub fn sign<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    msg: &[u8],
+    tag: &Tag,
+    privkeys: &[PrivateKey],
+) -> Option<ThresholdSignature> {
+    sign_with::<Blake2bSuite, R>(rng, msg, tag, privkeys)
+}
+
+/// Signs a message like `sign`, hashing with the suite `S`
+pub fn sign_with<S: HashSuite, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    msg: &[u8],
+    tag: &Tag,
+    privkeys: &[PrivateKey],
+) -> Option<ThresholdSignature> {
+    let (ring_size, t) = (tag.pubkeys.len(), privkeys.len());
+
+    // The ring index of every signer
+    let mut signers: Vec<usize> = Vec::with_capacity(t);
+    for privkey in privkeys {
+        let j = tag
+            .pubkeys
+            .iter()
+            .position(|pubkey| pubkey.0 == privkey.1)?;
+        if signers.contains(&j) {
+            return None;
+        }
+        signers.push(j);
+    }
+    if t == 0 {
+        return None;
+    }
+
+    // h := H(L)
+    let h = tag.hash0::<S>().into_point();
+    let aa0 = aa0::<S>(msg, tag);
+
+    // A₁⁽ᵏ⁾ := (jₖ+1)^{-1} * (xₖh - A₀), or the key image xₖh in linkable-only mode
+    let aa1s: Vec<RistrettoPoint> = privkeys
+        .iter()
+        .zip(&signers)
+        .map(|(privkey, &j)| {
+            let image = privkey.0 * h;
+            match tag.mode {
+                Mode::Traceable => x(j).invert() * (image - aa0),
+                Mode::LinkableOnly => image,
+            }
+        })
+        .collect();
+    let sigma = sigma_lines(tag, &aa0, &aa1s);
+
+    let mut cs = vec![Scalar::zero(); ring_size * t];
+    let mut zs = vec![Scalar::zero(); ring_size * t];
+    let mut a = vec![RistrettoPoint::identity(); ring_size * t];
+    let mut b = vec![RistrettoPoint::identity(); ring_size * t];
+    let ws: Vec<Scalar> = (0..t).map(|_| Scalar::random(rng)).collect();
+
+    for (i, pubkey) in tag.pubkeys.iter().enumerate() {
+        for (k, &j) in signers.iter().enumerate() {
+            let ik = i * t + k;
+            if j == i {
+                // aᵢ⁽ᵏ⁾ := wₖG,  bᵢ⁽ᵏ⁾ := wₖh
+                a[ik] = ws[k] * RISTRETTO_BASEPOINT_POINT;
+                b[ik] = ws[k] * h;
+            } else {
+                cs[ik] = Scalar::random(rng);
+                zs[ik] = Scalar::random(rng);
+
+                // aᵢ⁽ᵏ⁾ := zᵢ⁽ᵏ⁾G + cᵢ⁽ᵏ⁾yᵢ,  bᵢ⁽ᵏ⁾ := zᵢ⁽ᵏ⁾h + cᵢ⁽ᵏ⁾σᵢ⁽ᵏ⁾
+                a[ik] = zs[ik] * RISTRETTO_BASEPOINT_POINT + cs[ik] * pubkey.0;
+                b[ik] = zs[ik] * h + cs[ik] * sigma[k][i];
+            }
+        }
+    }
+
+    let cc = challenge::<S>(msg, tag, &aa0, &aa1s, &a, &b);
+
+    // f(0) = c and the cᵢ of the n - t other members fix f, which gives every signer's cᵢ
+    let mut points = vec![(Scalar::zero(), cc)];
+    points.extend(
+        (0..ring_size)
+            .filter(|i| !signers.contains(i))
+            .map(|i| (x(i), member_challenge(&cs, t, i))),
+    );
+    let xs: Vec<Scalar> = signers.iter().map(|&j| x(j)).collect();
+    let targets = interpolate(&points, &xs);
+
+    for (k, (&j, target)) in signers.iter().zip(targets).enumerate() {
+        let jk = j * t + k;
+        // cⱼ⁽ᵏ⁾ := cⱼ - Σ_{l ≠ k} cⱼ⁽ˡ⁾,  zⱼ⁽ᵏ⁾ := wₖ - cⱼ⁽ᵏ⁾xₖ
+        cs[jk] = target - member_challenge(&cs, t, j);
+        zs[jk] = ws[k] - cs[jk] * privkeys[k].0;
+    }
+
+    Some(ThresholdSignature { aa1s, cs, zs })
+}
+
+/// Verifies that at least `t` distinct members of the ring signed the message
+pub fn verify(msg: &[u8], tag: &Tag, t: usize, sig: &ThresholdSignature) -> bool {
+    verify_with::<Blake2bSuite>(msg, tag, t, sig)
+}
+
+/// Verifies a signature made with the suite `S`
+pub fn verify_with<S: HashSuite>(
+    msg: &[u8],
+    tag: &Tag,
+    t: usize,
+    sig: &ThresholdSignature,
+) -> bool {
+    let (ring_size, signers) = (tag.pubkeys.len(), sig.signers());
+    if signers == 0
+        || signers < t
+        || signers > ring_size
+        || sig.cs.len() != ring_size * signers
+        || sig.zs.len() != ring_size * signers
+    {
+        return false;
+    }
+
+    // Every signer has their own σ-line
+    if (0..signers).any(|k| sig.aa1s[..k].contains(&sig.aa1s[k])) {
+        return false;
+    }
+
+    // h := H(L)
+    let h = tag.hash0::<S>().into_point();
+    let aa0 = aa0::<S>(msg, tag);
+    let sigma = sigma_lines(tag, &aa0, &sig.aa1s);
+
+    // aᵢ⁽ᵏ⁾ := zᵢ⁽ᵏ⁾G + cᵢ⁽ᵏ⁾yᵢ,  bᵢ⁽ᵏ⁾ := zᵢ⁽ᵏ⁾h + cᵢ⁽ᵏ⁾σᵢ⁽ᵏ⁾
+    let mut a = Vec::with_capacity(ring_size * signers);
+    let mut b = Vec::with_capacity(ring_size * signers);
+    for (i, pubkey) in tag.pubkeys.iter().enumerate() {
+        for (k, line) in sigma.iter().enumerate() {
+            let (ci, zi) = (sig.cs[i * signers + k], sig.zs[i * signers + k]);
+            a.push(zi * RISTRETTO_BASEPOINT_POINT + ci * pubkey.0);
+            b.push(zi * h + ci * line[i]);
+        }
+    }
+
+    let cc = challenge::<S>(msg, tag, &aa0, &sig.aa1s, &a, &b);
+
+    // f(0) = c and the first n - t cᵢ fix f, and the other cᵢ must lie on it
+    let free = ring_size - signers;
+    let mut points = vec![(Scalar::zero(), cc)];
+    points.extend((0..free).map(|i| (x(i), member_challenge(&sig.cs, signers, i))));
+    let xs: Vec<Scalar> = (free..ring_size).map(x).collect();
+    interpolate(&points, &xs)
+        .into_iter()
+        .zip(free..ring_size)
+        .all(|(ci, i)| ci == member_challenge(&sig.cs, signers, i))
+}
+
+/// Traces the signers of two signatures with the same tag. Returns the ring index of
+/// every signer of both whose σ-lines reveal it, and -1 for every signer of both who
+/// signed the same message twice, as `trace` does for one pair of signatures
+pub fn trace(
+    tag: &Tag,
+    msg1: &[u8],
+    msg2: &[u8],
+    sig1: &ThresholdSignature,
+    sig2: &ThresholdSignature,
+) -> Vec<i32> {
+    trace_with::<Blake2bSuite>(tag, msg1, msg2, sig1, sig2)
+}
+
+/// Traces two signatures made with the suite `S`
+pub fn trace_with<S: HashSuite>(
+    tag: &Tag,
+    msg1: &[u8],
+    msg2: &[u8],
+    sig1: &ThresholdSignature,
+    sig2: &ThresholdSignature,
+) -> Vec<i32> {
+    let sigma1 = sigma_lines(tag, &aa0::<S>(msg1, tag), &sig1.aa1s);
+    let sigma2 = sigma_lines(tag, &aa0::<S>(msg2, tag), &sig2.aa1s);
+
+    let mut traced = Vec::new();
+    for line1 in &sigma1 {
+        for line2 in &sigma2 {
+            let common: Vec<usize> = (0..tag.pubkeys.len())
+                .filter(|&i| line1[i] == line2[i])
+                .collect();
+            if common.len() == tag.pubkeys.len() {
+                traced.push(-1);
+            } else if common.len() == 1 {
+                traced.push(common[0] as i32);
+            }
+        }
+    }
+    traced
+}
+
+#[cfg(test)]
+fn ring(n: usize) -> (Tag, Vec<PrivateKey>) {
+    let mut rng = rand::thread_rng();
+    let privkeys: Vec<PrivateKey> = (0..n)
+        .map(|_| {
+            let x = Scalar::random(&mut rng);
+            PrivateKey(x, x * RISTRETTO_BASEPOINT_POINT)
+        })
+        .collect();
+    let tag = Tag {
+        pubkeys: privkeys.iter().map(|k| crate::PublicKey(k.1)).collect(),
+        issue: b"issue".to_vec(),
+        mode: Mode::Traceable,
+    };
+    (tag, privkeys)
+}
+
+#[test]
+fn test_threshold_sign_and_verify() {
+    let mut rng = rand::thread_rng();
+    let (tag, privkeys) = ring(5);
+
+    let sig = sign(&mut rng, b"msg", &tag, &privkeys[1..4]).unwrap();
+    assert_eq!(sig.signers(), 3);
+    assert_eq!(sig.ring_size(), 5);
+    assert!(verify(b"msg", &tag, 3, &sig));
+    assert!(verify(b"msg", &tag, 2, &sig));
+    assert!(!verify(b"msg", &tag, 4, &sig));
+    assert!(!verify(b"other msg", &tag, 3, &sig));
+
+    let bytes = sig.to_bytes();
+    assert_eq!(bytes.len(), 8 + 32 * 3 + 64 * 5 * 3);
+    assert_eq!(ThresholdSignature::from_bytes(&bytes).unwrap(), sig);
+    assert!(ThresholdSignature::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+
+    // One signer, and every member signing
+    for signers in [&privkeys[2..3], &privkeys[..]].iter() {
+        let sig = sign(&mut rng, b"msg", &tag, signers).unwrap();
+        assert!(verify(b"msg", &tag, signers.len(), &sig));
+    }
+
+    // Signing twice with one key or with a key outside the ring fails
+    let twice = [
+        privkeys[0].clone(),
+        privkeys[2].clone(),
+        privkeys[0].clone(),
+    ];
+    assert!(sign(&mut rng, b"msg", &tag, &twice).is_none());
+    let (_, outsiders) = ring(1);
+    let outside = [privkeys[0].clone(), outsiders[0].clone()];
+    assert!(sign(&mut rng, b"msg", &tag, &outside).is_none());
+    assert!(sign(&mut rng, b"msg", &tag, &[]).is_none());
+
+    // Linkable-only signers each carry their key image in place of the σ-line
+    let tag = Tag {
+        mode: Mode::LinkableOnly,
+        ..tag
+    };
+    let sig = sign(&mut rng, b"msg", &tag, &privkeys[..2]).unwrap();
+    assert!(verify(b"msg", &tag, 2, &sig));
+    assert_eq!(
+        sig.aa1s[0],
+        privkeys[0].0 * tag.hash0::<Blake2bSuite>().into_point()
+    );
+}
+
+#[test]
+fn test_threshold_forgeries() {
+    let mut rng = rand::thread_rng();
+    let (tag, privkeys) = ring(5);
+    let sig = sign(&mut rng, b"msg", &tag, &privkeys[1..4]).unwrap();
+
+    // Two signers on one σ-line
+    let mut forged = sig.clone();
+    forged.aa1s[1] = forged.aa1s[0];
+    assert!(!verify(b"msg", &tag, 3, &forged));
+
+    // One signer's proof with a second σ-line added that no member's key is on
+    let one = sign(&mut rng, b"msg", &tag, &privkeys[..1]).unwrap();
+    let mut forged = ThresholdSignature {
+        aa1s: vec![one.aa1s[0], RistrettoPoint::random(&mut rng)],
+        cs: Vec::new(),
+        zs: Vec::new(),
+    };
+    for i in 0..5 {
+        forged.cs.extend_from_slice(&[one.cs[i], Scalar::zero()]);
+        forged.zs.extend_from_slice(&[one.zs[i], Scalar::zero()]);
+    }
+    assert!(!verify(b"msg", &tag, 2, &forged));
+
+    // Any changed challenge moves it off the polynomial
+    let mut forged = sig.clone();
+    forged.cs[0] += Scalar::one();
+    assert!(!verify(b"msg", &tag, 3, &forged));
+}
+
+#[test]
+fn test_threshold_trace() {
+    let mut rng = rand::thread_rng();
+    let (tag, privkeys) = ring(5);
+
+    // Member 2 signs both messages, and member 0 signs the first message twice
+    let sig1 = sign(
+        &mut rng,
+        b"msg 1",
+        &tag,
+        &[privkeys[0].clone(), privkeys[2].clone()],
+    )
+    .unwrap();
+    let sig2 = sign(
+        &mut rng,
+        b"msg 2",
+        &tag,
+        &[privkeys[2].clone(), privkeys[4].clone()],
+    )
+    .unwrap();
+    let sig3 = sign(
+        &mut rng,
+        b"msg 1",
+        &tag,
+        &[privkeys[0].clone(), privkeys[3].clone()],
+    )
+    .unwrap();
+
+    assert_eq!(trace(&tag, b"msg 1", b"msg 2", &sig1, &sig2), vec![2]);
+    assert_eq!(trace(&tag, b"msg 1", b"msg 1", &sig1, &sig3), vec![-1]);
+    assert!(trace(&tag, b"msg 2", b"msg 1", &sig2, &sig3).is_empty());
+
+    // A threshold signature traces against an ordinary one by the same member
+    let single = crate::sign(&mut rng, b"msg 3", &tag, &privkeys[4]);
+    let one = sign(&mut rng, b"msg 3", &tag, &privkeys[4..]).unwrap();
+    assert_eq!(trace(&tag, b"msg 2", b"msg 3", &sig2, &one), vec![4]);
+    assert_eq!(one.aa1s[0], single.aa1);
+}
+
+#[test]
+fn test_threshold_suites() {
+    use crate::suite::Sha512Suite;
+
+    let mut rng = rand::thread_rng();
+    let (tag, privkeys) = ring(4);
+
+    let sig1 = sign_with::<Sha512Suite, _>(&mut rng, b"msg 1", &tag, &privkeys[..2]).unwrap();
+    let sig2 = sign_with::<Sha512Suite, _>(&mut rng, b"msg 2", &tag, &privkeys[1..3]).unwrap();
+    assert!(verify_with::<Sha512Suite>(b"msg 1", &tag, 2, &sig1));
+    assert!(!verify(b"msg 1", &tag, 2, &sig1));
+    assert_eq!(
+        trace_with::<Sha512Suite>(&tag, b"msg 1", b"msg 2", &sig1, &sig2),
+        vec![1]
+    );
+}
@@ -0,0 +1,599 @@
+// Traceable ring signatures whose signer only t of n trustees together can identify.
+//
+// The tracing base h = H(L) is replaced by a trustee public key Y = w⁻¹G, and the
+// signature carries the single tag σ = xY in place of the σ-line through A₀ and A₁.
+// Two signatures by one signer under one trustee key have the same σ, so anyone can
+// link them, but σ only reveals the signer's public key as wσ = xG, and w is Shamir
+// shared among the trustees. Each trustee's decryption share of a signature is wₖσ,
+// and any t of them combine to the signer's public key. A share carries a Chaum-Pedersen
+// proof that it is wₖσ for the wₖ of the trustee's published verification key wₖG, so
+// a trustee cannot make the combination point at another member.
+//
+// σ = xY depends only on the signer and the trustee key, not on the issue, so every
+// signature by one signer under one trustee key links, whatever its issue. A trustee
+// key should be generated for each issue. Keys are generated by a trusted dealer, who
+// learns w and can trace every signature alone.
+//
+// The challenges and share proofs are hashed with a `HashSuite`, Blake2b unless another
+// suite is given with the `_with` functions.
+
+use crate::{PrivateKey, PublicKey, Tag};
+
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT, ristretto::CompressedRistretto,
+    ristretto::RistrettoPoint, scalar::Scalar, traits::Identity,
+};
+
+use crate::suite::{Blake2bSuite, HashSuite};
+use arrayref::array_ref;
+use digest::Update;
+use rand_core::{CryptoRng, RngCore};
+
+static DOMAIN_TRUSTEE: &[u8] = b"rust-ringsig-3";
+static DOMAIN_DLEQ: &[u8] = b"rust-ringsig-4";
+
+/// The trustees' public key Y, which replaces the tracing base of a tag
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrusteePublicKey(pub(crate) RistrettoPoint);
+
+impl TrusteePublicKey {
+    pub fn as_bytes(&self) -> Vec<u8> {
+        self.0.compress().as_bytes().to_vec()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<TrusteePublicKey> {
+        if bytes.len() != 32 {
+            return None;
+        }
+        CompressedRistretto::from_slice(bytes)
+            .decompress()
+            .map(TrusteePublicKey)
+    }
+}
+
+/// One trustee's Shamir share wₖ of the decryption key, with its index k, counted from 1
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrusteeShare {
+    pub index: u64,
+    share: Scalar,
+}
+
+/// A trustee's verification key wₖG, published with the trustee key so that anyone can
+/// check the trustee's decryption shares
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrusteeVerificationKey {
+    pub index: u64,
+    point: RistrettoPoint,
+}
+
+/// Generates a trustee key for `n` trustees, any `t` of whom can identify signers,
+/// with the trustees' verification keys and their shares.
+///
+/// Whoever runs this is a trusted dealer: it sees the decryption key w, so it can
+/// identify every signer on its own, and must hand each share to its trustee and then
+/// forget them. The trustee key and the verification keys are public
+pub fn trustee_keygen<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    t: usize,
+    n: usize,
+) -> (
+    TrusteePublicKey,
+    Vec<TrusteeVerificationKey>,
+    Vec<TrusteeShare>,
+) {
+    assert!(
+        0 < t && t <= n,
+        "need 0 < t <= n, got t = {} and n = {}",
+        t,
+        n
+    );
+
+    // f(X) := w + a₁X + ... + a_{t-1}X^{t-1}, with the key w = f(0) nonzero
+    let coefficients: Vec<Scalar> = (0..t)
+        .map(|_| loop {
+            let a = Scalar::random(rng);
+            if a != Scalar::zero() {
+                return a;
+            }
+        })
+        .collect();
+    let shares: Vec<TrusteeShare> = (1..=n as u64)
+        .map(|index| {
+            let x = Scalar::from(index);
+            let share = coefficients
+                .iter()
+                .rev()
+                .fold(Scalar::zero(), |acc, a| acc * x + a);
+            TrusteeShare { index, share }
+        })
+        .collect();
+    let keys = shares
+        .iter()
+        .map(|share| TrusteeVerificationKey {
+            index: share.index,
+            point: share.share * RISTRETTO_BASEPOINT_POINT,
+        })
+        .collect();
+
+    // Y := w⁻¹G
+    let y = coefficients[0].invert() * RISTRETTO_BASEPOINT_POINT;
+    (TrusteePublicKey(y), keys, shares)
+}
+
+/// A ring signature with the tag σ = xY
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrusteeSignature {
+    sigma: RistrettoPoint,
+    cs: Vec<Scalar>,
+    zs: Vec<Scalar>,
+}
+
+impl TrusteeSignature {
+    pub fn ring_size(&self) -> usize {
+        self.cs.len()
+    }
+
+    /// Serialize this signature as the compressed σ followed by every cᵢ and then every zᵢ.
+    /// This is 32 + 64n bytes for a ring of size n, the same as a traceable signature
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 + 32 * (self.cs.len() + self.zs.len()));
+        bytes.extend_from_slice(self.sigma.compress().as_bytes());
+        for s in self.cs.iter().chain(&self.zs) {
+            bytes.extend_from_slice(s.as_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize a signature produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Option<TrusteeSignature> {
+        if bytes.len() < 32 || !(bytes.len() - 32).is_multiple_of(64) {
+            return None;
+        }
+        let ring_size = (bytes.len() - 32) / 64;
+
+        let sigma = CompressedRistretto::from_slice(&bytes[..32]).decompress()?;
+        let scalars = bytes[32..]
+            .chunks(32)
+            .map(|chunk| Scalar::from_canonical_bytes(*array_ref!(chunk, 0, 32)))
+            .collect::<Option<Vec<Scalar>>>()?;
+        let (cs, zs) = scalars.split_at(ring_size);
+
+        Some(TrusteeSignature {
+            sigma,
+            cs: cs.to_vec(),
+            zs: zs.to_vec(),
+        })
+    }
+}
+
+// c := H(L, Y, m, σ, {aᵢ}, {bᵢ})
+fn challenge<S: HashSuite>(
+    msg: &[u8],
+    tag: &Tag,
+    trustee: &TrusteePublicKey,
+    sigma: &RistrettoPoint,
+    a: &[RistrettoPoint],
+    b: &[RistrettoPoint],
+) -> Scalar {
+    let mut d = tag.hash_self(S::hash(DOMAIN_TRUSTEE));
+    d.update(trustee.0.compress().as_bytes());
+    d.update((msg.len() as u64).to_le_bytes());
+    d.update(msg);
+    d.update(sigma.compress().as_bytes());
+    for p in a.iter().chain(b) {
+        d.update(p.compress().as_bytes());
+    }
+    Scalar::from_hash(d)
+}
+
+/// Signs a message for the ring of the tag, with the trustee key as the tracing base.
+/// The signature links to every other signature by the same signer under this trustee
+/// key, including those in other issues
+pub fn sign<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    msg: &[u8],
+    tag: &Tag,
+    trustee: &TrusteePublicKey,
+    privkey: &PrivateKey,
+) -> TrusteeSignature {
+    sign_with::<Blake2bSuite, R>(rng, msg, tag, trustee, privkey)
+}
+
+/// Signs a message like `sign`, hashing with the suite `S`
+pub fn sign_with<S: HashSuite, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    msg: &[u8],
+    tag: &Tag,
+    trustee: &TrusteePublicKey,
+    privkey: &PrivateKey,
+) -> TrusteeSignature {
+    let ring_size = tag.pubkeys.len();
+    let privkey_idx = tag
+        .pubkeys
+        .iter()
+        .position(|pubkey| pubkey.0 == privkey.1)
+        .expect("Could not find private key position in ring");
+
+    // h := Y,  σ := xh
+    let h = trustee.0;
+    let sigma = privkey.0 * h;
+
+    let mut c: Vec<Scalar> = vec![Scalar::zero(); ring_size];
+    let mut z: Vec<Scalar> = vec![Scalar::zero(); ring_size];
+    let mut a: Vec<RistrettoPoint> = vec![RistrettoPoint::identity(); ring_size];
+    let mut b: Vec<RistrettoPoint> = vec![RistrettoPoint::identity(); ring_size];
+
+    // aⱼ := wG,  bⱼ := wh
+    let w = Scalar::random(rng);
+    a[privkey_idx] = w * RISTRETTO_BASEPOINT_POINT;
+    b[privkey_idx] = w * h;
+
+    // aᵢ := zᵢG + cᵢyᵢ,  bᵢ := zᵢh + cᵢσ
+    for i in (0..ring_size).filter(|&i| i != privkey_idx) {
+        c[i] = Scalar::random(rng);
+        z[i] = Scalar::random(rng);
+        a[i] = z[i] * RISTRETTO_BASEPOINT_POINT + c[i] * tag.pubkeys[i].0;
+        b[i] = z[i] * h + c[i] * sigma;
+    }
+
+    // cⱼ := c - Σ_{i ≠ j} cᵢ,  zⱼ := w - cⱼx
+    let cc = challenge::<S>(msg, tag, trustee, &sigma, &a, &b);
+    let sum: Scalar = c.iter().sum();
+    c[privkey_idx] = cc - sum;
+    z[privkey_idx] = w - c[privkey_idx] * privkey.0;
+
+    TrusteeSignature {
+        sigma,
+        cs: c,
+        zs: z,
+    }
+}
+
+pub fn verify(msg: &[u8], tag: &Tag, trustee: &TrusteePublicKey, sig: &TrusteeSignature) -> bool {
+    verify_with::<Blake2bSuite>(msg, tag, trustee, sig)
+}
+
+/// Verifies a signature made with the suite `S`
+pub fn verify_with<S: HashSuite>(
+    msg: &[u8],
+    tag: &Tag,
+    trustee: &TrusteePublicKey,
+    sig: &TrusteeSignature,
+) -> bool {
+    let ring_size = tag.pubkeys.len();
+    if sig.cs.len() != ring_size || sig.zs.len() != ring_size {
+        return false;
+    }
+
+    let h = trustee.0;
+    let a: Vec<RistrettoPoint> = (0..ring_size)
+        .map(|i| sig.zs[i] * RISTRETTO_BASEPOINT_POINT + sig.cs[i] * tag.pubkeys[i].0)
+        .collect();
+    let b: Vec<RistrettoPoint> = (0..ring_size)
+        .map(|i| sig.zs[i] * h + sig.cs[i] * sig.sigma)
+        .collect();
+
+    // c == Σcᵢ
+    let sum: Scalar = sig.cs.iter().sum();
+    sum == challenge::<S>(msg, tag, trustee, &sig.sigma, &a, &b)
+}
+
+/// Whether two signatures under one trustee key were made by the same signer. This
+/// does not say who the signer is
+pub fn link(sig1: &TrusteeSignature, sig2: &TrusteeSignature) -> bool {
+    sig1.sigma == sig2.sigma
+}
+
+/// A trustee's share wₖσ of the signer's public key, with a proof (c, z) that
+/// log_G(wₖG) = log_σ(wₖσ)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecryptionShare {
+    pub index: u64,
+    point: RistrettoPoint,
+    c: Scalar,
+    z: Scalar,
+}
+
+// c := H(k, wₖG, σ, wₖσ, a, b)
+fn dleq_challenge<S: HashSuite>(
+    index: u64,
+    key: &RistrettoPoint,
+    sigma: &RistrettoPoint,
+    point: &RistrettoPoint,
+    a: &RistrettoPoint,
+    b: &RistrettoPoint,
+) -> Scalar {
+    let mut d = S::hash(DOMAIN_DLEQ);
+    d.update(index.to_le_bytes());
+    for p in &[key, sigma, point, a, b] {
+        d.update(p.compress().as_bytes());
+    }
+    Scalar::from_hash(d)
+}
+
+pub fn decryption_share<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    share: &TrusteeShare,
+    sig: &TrusteeSignature,
+) -> DecryptionShare {
+    decryption_share_with::<Blake2bSuite, R>(rng, share, sig)
+}
+
+/// A decryption share of a signature made with the suite `S`, proved with the same suite
+pub fn decryption_share_with<S: HashSuite, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    share: &TrusteeShare,
+    sig: &TrusteeSignature,
+) -> DecryptionShare {
+    let key = share.share * RISTRETTO_BASEPOINT_POINT;
+    let point = share.share * sig.sigma;
+
+    // a := rG,  b := rσ,  z := r - cwₖ
+    let r = Scalar::random(rng);
+    let a = r * RISTRETTO_BASEPOINT_POINT;
+    let b = r * sig.sigma;
+    let c = dleq_challenge::<S>(share.index, &key, &sig.sigma, &point, &a, &b);
+
+    DecryptionShare {
+        index: share.index,
+        point,
+        c,
+        z: r - c * share.share,
+    }
+}
+
+/// Whether a decryption share of the signature was made with the share of the
+/// verification key's trustee
+pub fn verify_share(
+    share: &DecryptionShare,
+    key: &TrusteeVerificationKey,
+    sig: &TrusteeSignature,
+) -> bool {
+    verify_share_with::<Blake2bSuite>(share, key, sig)
+}
+
+/// Verifies a decryption share made with the suite `S`
+pub fn verify_share_with<S: HashSuite>(
+    share: &DecryptionShare,
+    key: &TrusteeVerificationKey,
+    sig: &TrusteeSignature,
+) -> bool {
+    if share.index != key.index {
+        return false;
+    }
+
+    // a = zG + c·wₖG,  b = zσ + c·wₖσ
+    let a = share.z * RISTRETTO_BASEPOINT_POINT + share.c * key.point;
+    let b = share.z * sig.sigma + share.c * share.point;
+    share.c == dleq_challenge::<S>(share.index, &key.point, &sig.sigma, &share.point, &a, &b)
+}
+
+/// Combines the first `t` decryption shares of a signature whose proofs verify under
+/// the trustees' verification keys into the signer's public key xG = wσ. Shares whose
+/// proof fails, or whose trustee has no key, are rejected. Returns `None` if fewer than
+/// `t` shares remain or two have the same index. `t` must be the key's threshold, as
+/// fewer shares combine to the wrong point
+pub fn combine(
+    sig: &TrusteeSignature,
+    keys: &[TrusteeVerificationKey],
+    shares: &[DecryptionShare],
+    t: usize,
+) -> Option<PublicKey> {
+    combine_with::<Blake2bSuite>(sig, keys, shares, t)
+}
+
+/// Combines decryption shares made with the suite `S`
+pub fn combine_with<S: HashSuite>(
+    sig: &TrusteeSignature,
+    keys: &[TrusteeVerificationKey],
+    shares: &[DecryptionShare],
+    t: usize,
+) -> Option<PublicKey> {
+    let shares: Vec<&DecryptionShare> = shares
+        .iter()
+        .filter(|share| {
+            keys.iter()
+                .any(|key| key.index == share.index && verify_share_with::<S>(share, key, sig))
+        })
+        .take(t)
+        .collect();
+    if t == 0 || shares.len() < t {
+        return None;
+    }
+    for (k, share) in shares.iter().enumerate() {
+        if share.index == 0 || shares[..k].iter().any(|s| s.index == share.index) {
+            return None;
+        }
+    }
+
+    // wσ = Σ λₖwₖσ, where λₖ := Π_{m ≠ k} m / (m - k) is the Lagrange coefficient at 0
+    let point = shares
+        .iter()
+        .map(|share| {
+            let k = Scalar::from(share.index);
+            let lambda = shares
+                .iter()
+                .filter(|s| s.index != share.index)
+                .map(|s| Scalar::from(s.index))
+                .fold(Scalar::one(), |acc, m| acc * m * (m - k).invert());
+            lambda * share.point
+        })
+        .sum();
+    Some(PublicKey(point))
+}
+
+/// The index in the ring of the signer of the signature the decryption shares are of,
+/// or -1 if the shares do not combine to a member's key
+pub fn trace(
+    tag: &Tag,
+    sig: &TrusteeSignature,
+    keys: &[TrusteeVerificationKey],
+    shares: &[DecryptionShare],
+    t: usize,
+) -> i32 {
+    trace_with::<Blake2bSuite>(tag, sig, keys, shares, t)
+}
+
+/// Traces a signature with decryption shares made with the suite `S`
+pub fn trace_with<S: HashSuite>(
+    tag: &Tag,
+    sig: &TrusteeSignature,
+    keys: &[TrusteeVerificationKey],
+    shares: &[DecryptionShare],
+    t: usize,
+) -> i32 {
+    combine_with::<S>(sig, keys, shares, t)
+        .and_then(|pubkey| tag.pubkeys.iter().position(|p| *p == pubkey))
+        .map_or(-1, |i| i as i32)
+}
+
+#[cfg(test)]
+fn ring(n: usize) -> (Tag, Vec<PrivateKey>) {
+    let mut rng = rand::thread_rng();
+    let privkeys: Vec<PrivateKey> = (0..n)
+        .map(|_| {
+            let x = Scalar::random(&mut rng);
+            PrivateKey(x, x * RISTRETTO_BASEPOINT_POINT)
+        })
+        .collect();
+    let tag = Tag {
+        pubkeys: privkeys.iter().map(|k| PublicKey(k.1)).collect(),
+        issue: b"issue".to_vec(),
+        mode: crate::Mode::Traceable,
+    };
+    (tag, privkeys)
+}
+
+#[test]
+fn test_trustee_sign_and_verify() {
+    let mut rng = rand::thread_rng();
+    let (tag, privkeys) = ring(4);
+    let (trustee, _, _) = trustee_keygen(&mut rng, 2, 3);
+
+    let sig = sign(&mut rng, b"msg", &tag, &trustee, &privkeys[2]);
+    assert!(verify(b"msg", &tag, &trustee, &sig));
+    assert!(!verify(b"other msg", &tag, &trustee, &sig));
+    let (other_trustee, _, _) = trustee_keygen(&mut rng, 2, 3);
+    assert!(!verify(b"msg", &tag, &other_trustee, &sig));
+
+    let bytes = sig.to_bytes();
+    assert_eq!(bytes.len(), 32 + 64 * 4);
+    assert_eq!(TrusteeSignature::from_bytes(&bytes).unwrap(), sig);
+}
+
+#[test]
+fn test_trustee_trace() {
+    let mut rng = rand::thread_rng();
+    let (tag, privkeys) = ring(5);
+    let (trustee, keys, shares) = trustee_keygen(&mut rng, 3, 5);
+
+    let sig1 = sign(&mut rng, b"msg 1", &tag, &trustee, &privkeys[3]);
+    let sig2 = sign(&mut rng, b"msg 2", &tag, &trustee, &privkeys[3]);
+    let sig3 = sign(&mut rng, b"msg 1", &tag, &trustee, &privkeys[1]);
+    assert!(link(&sig1, &sig2));
+    assert!(!link(&sig1, &sig3));
+
+    // Any 3 of the 5 trustees identify the signer
+    let ds: Vec<DecryptionShare> = shares
+        .iter()
+        .map(|s| decryption_share(&mut rng, s, &sig1))
+        .collect();
+    assert_eq!(trace(&tag, &sig1, &keys, &ds[..3], 3), 3);
+    assert_eq!(
+        trace(
+            &tag,
+            &sig1,
+            &keys,
+            &[ds[4].clone(), ds[0].clone(), ds[2].clone()],
+            3
+        ),
+        3
+    );
+    assert_eq!(
+        combine(&sig1, &keys, &ds[1..4], 3),
+        Some(tag.pubkeys[3].clone())
+    );
+
+    // Fewer, repeated or mismatched shares do not
+    assert_eq!(trace(&tag, &sig1, &keys, &ds[..2], 3), -1);
+    assert_eq!(
+        combine(
+            &sig1,
+            &keys,
+            &[ds[0].clone(), ds[0].clone(), ds[1].clone()],
+            3
+        ),
+        None
+    );
+    assert_ne!(
+        combine(&sig1, &keys, &ds[..2], 2),
+        Some(tag.pubkeys[3].clone())
+    );
+    let ds3: Vec<DecryptionShare> = shares
+        .iter()
+        .map(|s| decryption_share(&mut rng, s, &sig3))
+        .collect();
+    assert_eq!(trace(&tag, &sig3, &keys, &ds3[2..], 3), 1);
+}
+
+#[test]
+fn test_trustee_share_proofs() {
+    let mut rng = rand::thread_rng();
+    let (tag, privkeys) = ring(4);
+    let (trustee, keys, shares) = trustee_keygen(&mut rng, 2, 3);
+    let sig = sign(&mut rng, b"msg", &tag, &trustee, &privkeys[0]);
+    let other = sign(&mut rng, b"msg", &tag, &trustee, &privkeys[1]);
+
+    let ds: Vec<DecryptionShare> = shares
+        .iter()
+        .map(|s| decryption_share(&mut rng, s, &sig))
+        .collect();
+    assert!(verify_share(&ds[0], &keys[0], &sig));
+    assert!(!verify_share(&ds[0], &keys[1], &sig));
+    assert!(!verify_share(&ds[0], &keys[0], &other));
+
+    // A trustee who replaces its share with another member's key is rejected, and
+    // the remaining honest shares still identify the signer
+    let mut forged = ds[0].clone();
+    forged.point = ds[0].point + (privkeys[1].1 - privkeys[0].1);
+    assert!(!verify_share(&forged, &keys[0], &sig));
+    assert_eq!(
+        combine(&sig, &keys, &[forged.clone(), ds[1].clone()], 2),
+        None
+    );
+    assert_eq!(
+        trace(
+            &tag,
+            &sig,
+            &keys,
+            &[forged, ds[1].clone(), ds[2].clone()],
+            2
+        ),
+        0
+    );
+
+    // Shares of another signature, or from a trustee without a key, are rejected
+    let ds_other = decryption_share(&mut rng, &shares[0], &other);
+    assert_eq!(combine(&sig, &keys, &[ds_other, ds[1].clone()], 2), None);
+    assert_eq!(combine(&sig, &keys[1..], &ds[..2], 2), None);
+}
+
+#[test]
+fn test_trustee_suites() {
+    use crate::suite::Sha3Suite;
+
+    let mut rng = rand::thread_rng();
+    let (tag, privkeys) = ring(3);
+    let (trustee, keys, shares) = trustee_keygen(&mut rng, 2, 3);
+
+    let sig = sign_with::<Sha3Suite, _>(&mut rng, b"msg", &tag, &trustee, &privkeys[1]);
+    assert!(verify_with::<Sha3Suite>(b"msg", &tag, &trustee, &sig));
+    assert!(!verify(b"msg", &tag, &trustee, &sig));
+
+    let ds: Vec<DecryptionShare> = shares[..2]
+        .iter()
+        .map(|s| decryption_share_with::<Sha3Suite, _>(&mut rng, s, &sig))
+        .collect();
+    assert_eq!(trace_with::<Sha3Suite>(&tag, &sig, &keys, &ds, 2), 1);
+    assert_eq!(trace(&tag, &sig, &keys, &ds, 2), -1);
+}
@@ -0,0 +1,257 @@
+// Drives trs-cli end to end through files, as a script would
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn trs_cli(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_trs-cli"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("trs-cli runs")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("trs-cli-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn cli_sign_verify_trace() {
+    for encoding in ["hex", "base64"] {
+        let dir = temp_dir(encoding);
+        let run = |args: &[&str]| trs_cli(&dir, &[&["--encoding", encoding], args].concat());
+
+        for key in ["alice", "bob", "carol"] {
+            assert!(run(&["keygen", "--out", key]).status.success());
+        }
+        let build = run(&[
+            "ring",
+            "build",
+            "--issue",
+            "poll 1",
+            "--out",
+            "ring",
+            "alice.pub",
+            "bob.pub",
+            "carol.pub",
+        ]);
+        assert!(build.status.success());
+
+        std::fs::write(dir.join("yes"), "yes").unwrap();
+        std::fs::write(dir.join("no"), "no").unwrap();
+        for (key, message, out) in [
+            ("bob", "yes", "sig1"),
+            ("bob", "no", "sig2"),
+            ("carol", "no", "sig3"),
+        ] {
+            let sign = run(&[
+                "sign",
+                "--key",
+                key,
+                "--ring",
+                "ring",
+                "--message",
+                message,
+                "--out",
+                out,
+            ]);
+            assert!(sign.status.success());
+        }
+
+        let verify = run(&[
+            "verify",
+            "--ring",
+            "ring",
+            "--message",
+            "yes",
+            "--signature",
+            "sig1",
+        ]);
+        assert_eq!(verify.status.code(), Some(0));
+        assert_eq!(stdout(&verify), "valid");
+        let verify = run(&[
+            "verify",
+            "--ring",
+            "ring",
+            "--message",
+            "no",
+            "--signature",
+            "sig1",
+        ]);
+        assert_eq!(verify.status.code(), Some(1));
+        assert_eq!(stdout(&verify), "invalid");
+
+        // Bob signed two different messages, which reveals him
+        let trace = run(&[
+            "trace",
+            "--ring",
+            "ring",
+            "--message1",
+            "yes",
+            "--signature1",
+            "sig1",
+            "--message2",
+            "no",
+            "--signature2",
+            "sig2",
+        ]);
+        assert!(trace.status.success());
+        assert_eq!(stdout(&trace).lines().last(), Some("signer 1"));
+        let trace = run(&[
+            "trace",
+            "--ring",
+            "ring",
+            "--message1",
+            "no",
+            "--signature1",
+            "sig2",
+            "--message2",
+            "no",
+            "--signature2",
+            "sig3",
+        ]);
+        assert_eq!(stdout(&trace).lines().last(), Some("independent"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn cli_linkable_only() {
+    let dir = temp_dir("linkable-only");
+    let run = |args: &[&str]| trs_cli(&dir, args);
+
+    for key in ["alice", "bob"] {
+        assert!(run(&["keygen", "--out", key]).status.success());
+    }
+    let build = run(&[
+        "ring",
+        "build",
+        "--issue",
+        "poll 1",
+        "--linkable-only",
+        "--out",
+        "ring",
+        "alice.pub",
+        "bob.pub",
+    ]);
+    assert!(build.status.success());
+
+    std::fs::write(dir.join("yes"), "yes").unwrap();
+    std::fs::write(dir.join("no"), "no").unwrap();
+    for (message, out) in [("yes", "sig1"), ("no", "sig2")] {
+        let sign = run(&[
+            "sign",
+            "--key",
+            "bob",
+            "--ring",
+            "ring",
+            "--message",
+            message,
+            "--out",
+            out,
+        ]);
+        assert!(sign.status.success());
+    }
+
+    // Bob's two signatures are linked, but do not reveal him
+    let trace = run(&[
+        "trace",
+        "--ring",
+        "ring",
+        "--message1",
+        "yes",
+        "--signature1",
+        "sig1",
+        "--message2",
+        "no",
+        "--signature2",
+        "sig2",
+    ]);
+    assert!(trace.status.success());
+    assert_eq!(stdout(&trace).lines().last(), Some("linked"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_errors() {
+    let dir = temp_dir("errors");
+    assert!(trs_cli(&dir, &["keygen", "--out", "alice"])
+        .status
+        .success());
+    assert!(trs_cli(&dir, &["keygen", "--out", "mallory"])
+        .status
+        .success());
+    assert!(trs_cli(
+        &dir,
+        &[
+            "ring",
+            "build",
+            "--issue",
+            "poll",
+            "--out",
+            "ring",
+            "alice.pub"
+        ]
+    )
+    .status
+    .success());
+    std::fs::write(dir.join("msg"), "hello").unwrap();
+
+    // Not a member of the ring
+    let sign = trs_cli(
+        &dir,
+        &[
+            "sign",
+            "--key",
+            "mallory",
+            "--ring",
+            "ring",
+            "--message",
+            "msg",
+            "--out",
+            "sig",
+        ],
+    );
+    assert_eq!(sign.status.code(), Some(2));
+
+    // Files in the wrong encoding, and missing files
+    let sign = trs_cli(
+        &dir,
+        &[
+            "--encoding",
+            "base64",
+            "sign",
+            "--key",
+            "alice",
+            "--ring",
+            "ring",
+            "--message",
+            "msg",
+            "--out",
+            "sig",
+        ],
+    );
+    assert_eq!(sign.status.code(), Some(2));
+    let verify = trs_cli(
+        &dir,
+        &[
+            "verify",
+            "--ring",
+            "ring",
+            "--message",
+            "msg",
+            "--signature",
+            "missing",
+        ],
+    );
+    assert_eq!(verify.status.code(), Some(2));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
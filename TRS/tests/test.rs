@@ -1,6 +1,12 @@
 use rand::rngs::OsRng;
 use trs::*;
-use std::time::Instant;
+use bench_harness::alloc::CountingAlloc;
+use bench_harness::timing::{black_box, Bench};
+use bench_harness::{emit, Record, DEFAULT_MESSAGE_LENGTHS};
+
+// Lets the benchmarks report heap usage alongside their timings
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
 
 #[test]
 fn strawman() {
@@ -26,7 +32,7 @@ fn strawman() {
         let public_key= PublicKey::from_bytes(&set_publickey[i]).unwrap();
         pubkeys.push(public_key);
     }
-    let tag = Tag{issue, pubkeys};
+    let tag = Tag{issue, pubkeys, mode: Mode::Traceable};
 
     // everyone signs the same message // TODO: message size also impacts this probably
     let msg = vec![1u8; 32];
@@ -42,7 +48,7 @@ fn strawman() {
     // black_box(sum); // prevent compiler optimizations on unused variable
 }
 
-pub fn proof_time(ring_size: usize, set_publickey: Vec<[u8;32]>, set_secretkey: Vec<[u8;32]>, tag: Tag, msg: Vec<u8>) -> Vec<Signature> {
+pub fn proof_time(ring_size: usize, set_publickey: &[[u8;32]], set_secretkey: &[[u8;32]], tag: &Tag, msg: &[u8]) -> Vec<Signature> {
     // Times the signing of messages, in other words, proof generation time 
 
     // PrivateKey expects the scalar concatenated with the public key
@@ -52,124 +58,148 @@ pub fn proof_time(ring_size: usize, set_publickey: Vec<[u8;32]>, set_secretkey:
     // for i in 0..ring_size {
     let secretkey = [&set_secretkey[0][..], &set_publickey[0][..]].concat();
     // sign(&mut rng, &msg, &tag, &PrivateKey::from_bytes(&secretkey).unwrap())
-    sigs.push(sign(&mut rng, &msg, &tag, &PrivateKey::from_bytes(&secretkey).unwrap()));
+    sigs.push(sign(&mut rng, msg, tag, &PrivateKey::from_bytes(&secretkey).unwrap()));
     // }
     sigs
 }
 
 #[test]
 fn proof_time_bench() {
-    println!("proof time");
-    for &n in &RING_SIZES {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
         let tup = generate_keys_and_message(n);
         let set_publickey = tup.0;
         let set_secretkey = tup.1;
         let tag = tup.2;
         let msg = tup.3;
         
-        let trials = 10;
-        let mut times: Vec<f64> = Vec::with_capacity(trials);
-
-        for _ in 0..trials {
-            let start = Instant::now();
-            let _ = proof_time(n, set_publickey.clone(), set_secretkey.clone(), tag.clone(), msg.clone());
-            let elapsed = start.elapsed();
-            times.push(elapsed.as_millis() as f64);
-        }
-
-        let mean: f64 = times.iter().sum::<f64>() / trials as f64;
-        let variance: f64 = times
-            .iter()
-            .map(|t| (t - mean).powi(2))
-            .sum::<f64>()
-            / (trials-1) as f64;
-
-        let std_dev: f64 = variance.sqrt();
-
-        println!("n: {}, ms: {}+{:.3}", n, mean, std_dev);
+        let measurement = Bench::from_env(10).run(|| {
+            proof_time(n, black_box(&set_publickey), black_box(&set_secretkey), black_box(&tag), black_box(&msg))
+        });
+
+        emit(&Record::measured("trs", "sign", n, &measurement));
     }
 }
 
-pub fn verification_time(msg: Vec<u8>, tag: Tag, sigs: Vec<Signature>, ring_size: usize) {
+pub fn verification_time(msg: &[u8], tag: &Tag, sigs: &[Signature], ring_size: usize) -> bool {
     // Times the verification of messages, in other words, proof verification time 
     // a bunch of users sign the same message
     // for i in 0..ring_size {
-    verify(&msg, &tag, &sigs[0]);
+    verify(msg, tag, &sigs[0])
     // }
 }
 
 #[test]
 
 fn verification_time_bench() {
-    println!("verify time");
-    for &n in &RING_SIZES {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
         let tup = generate_keys_and_message(n);
         let set_publickey = tup.0;
         let set_secretkey = tup.1;
         let tag = tup.2;
         let msg = tup.3;
 
-        let sigs = proof_time(n, set_publickey.clone(), set_secretkey.clone(), tag.clone(), msg.clone());
-
-        let trials = 10;
-        let mut times: Vec<f64> = Vec::with_capacity(trials);
-
-        for _ in 0..trials {
-            let start = Instant::now();
-            verification_time(msg.clone(), tag.clone(), sigs.clone(), n);
-            let elapsed = start.elapsed();
-            times.push(elapsed.as_millis() as f64);
-        }
+        let sigs = proof_time(n, &set_publickey, &set_secretkey, &tag, &msg);
 
-        let mean: f64 = times.iter().sum::<f64>() / trials as f64;
-        let variance: f64 = times
-            .iter()
-            .map(|t| (t - mean).powi(2))
-            .sum::<f64>()
-            / (trials-1) as f64;
+        assert!(verification_time(&msg, &tag, &sigs, n));
+        let measurement = Bench::from_env(10).run(|| {
+            verification_time(black_box(&msg), black_box(&tag), black_box(&sigs), n)
+        });
 
-        let std_dev: f64 = variance.sqrt();
-
-        println!("n: {}, ms: {}+{:.3}", n, mean, std_dev);
+        emit(&Record::measured("trs", "verify", n, &measurement));
     }
 }
 
-pub fn trace_time(msg1: Vec<u8>, msg2: Vec<u8>, tag: Tag, sig1: Signature, sig2: Signature) {
-    trace(&tag, &msg1, &msg2, &sig1, &sig2);
+pub fn trace_time(msg1: &[u8], msg2: &[u8], tag: &Tag, sig1: &Signature, sig2: &Signature) -> i32 {
+    trace(tag, msg1, msg2, sig1, sig2)
 }
 
 #[test]
 fn trace_time_bench() {
-    println!("trace time");
-    for &n in &RING_SIZES {
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
         let tup = generate_keys_and_message(n);
         let set_publickey = tup.0;
         let set_secretkey = tup.1;
         let tag = tup.2;
         let msg = tup.3;
 
-        let sigs = proof_time(n, set_publickey.clone(), set_secretkey.clone(), tag.clone(), msg.clone());
-        let sigs2 = proof_time(n, set_publickey.clone(), set_secretkey.clone(), tag.clone(), msg.clone());
-
-        let trials = 10;
-        let mut times: Vec<f64> = Vec::with_capacity(trials);
-
-        for _ in 0..trials {
-            let start = Instant::now();
-            trace_time(msg.clone(), msg.clone(), tag.clone(), sigs[0].clone(), sigs2[0].clone());
-            let elapsed = start.elapsed();
-            times.push(elapsed.as_millis() as f64);
-        }
+        let sigs = proof_time(n, &set_publickey, &set_secretkey, &tag, &msg);
+        let sigs2 = proof_time(n, &set_publickey, &set_secretkey, &tag, &msg);
 
-        let mean: f64 = times.iter().sum::<f64>() / trials as f64;
-        let variance: f64 = times
-            .iter()
-            .map(|t| (t - mean).powi(2))
-            .sum::<f64>()
-            / (trials-1) as f64;
+        let measurement = Bench::from_env(10).run(|| {
+            trace_time(black_box(&msg), black_box(&msg), black_box(&tag), black_box(&sigs[0]), black_box(&sigs2[0]))
+        });
 
-        let std_dev: f64 = variance.sqrt();
+        emit(&Record::measured("trs", "trace", n, &measurement));
+    }
+}
+#[test]
+fn message_length_bench() {
+    // The message only affects the hash into A₀ and the challenge, so it is swept at
+    // the smallest ring size, where hashing it is the largest share of the time
+    let n = bench_harness::ring_sizes(&RING_SIZES)[0];
+
+    for len in bench_harness::message_lengths(&DEFAULT_MESSAGE_LENGTHS) {
+        let (set_publickey, set_secretkey, tag, msg) = generate_keys_and_message_of_len(n, len);
+        let secretkey = [&set_secretkey[0][..], &set_publickey[0][..]].concat();
+        let secretkey = PrivateKey::from_bytes(&secretkey).unwrap();
+        let mut rng = rand::thread_rng();
+
+        // Signing a pre-hashed message includes hashing it
+        let sign_measurement = Bench::from_env(10).run(|| {
+            sign(&mut rng, black_box(&msg), black_box(&tag), black_box(&secretkey))
+        });
+        let sign_prehashed_measurement = Bench::from_env(10).run(|| {
+            sign(&mut rng, &prehash(black_box(&msg)), black_box(&tag), black_box(&secretkey))
+        });
+
+        let sig = sign(&mut rng, &msg, &tag, &secretkey);
+        let prehashed_sig = sign(&mut rng, &prehash(&msg), &tag, &secretkey);
+        assert!(verify(&msg, &tag, &sig));
+        assert!(verify(&prehash(&msg), &tag, &prehashed_sig));
+
+        let verify_measurement = Bench::from_env(10).run(|| {
+            verify(black_box(&msg), black_box(&tag), black_box(&sig))
+        });
+        let verify_prehashed_measurement = Bench::from_env(10).run(|| {
+            verify(&prehash(black_box(&msg)), black_box(&tag), black_box(&prehashed_sig))
+        });
+
+        emit(&Record::measured("trs", "sign", n, &sign_measurement).with_message_bytes(len));
+        emit(&Record::measured("trs", "sign_prehashed", n, &sign_prehashed_measurement).with_message_bytes(len));
+        emit(&Record::measured("trs", "verify", n, &verify_measurement).with_message_bytes(len));
+        emit(&Record::measured("trs", "verify_prehashed", n, &verify_prehashed_measurement).with_message_bytes(len));
+    }
+}
 
-        println!("n: {}, ms: {}+{:.3}", n, mean, std_dev);
+#[test]
+fn linkable_only_bench() {
+    // Signing, verifying and tracing with a linkable-only tag, reported as the
+    // trs-linkable scheme beside the traceable mode's trs
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
+        let (set_publickey, set_secretkey, mut tag, msg) = generate_keys_and_message(n);
+        tag.mode = Mode::LinkableOnly;
+
+        let secretkey = [&set_secretkey[0][..], &set_publickey[0][..]].concat();
+        let secretkey = PrivateKey::from_bytes(&secretkey).unwrap();
+        let mut rng = rand::thread_rng();
+
+        let sign_measurement = Bench::from_env(10).run(|| {
+            sign(&mut rng, black_box(&msg), black_box(&tag), black_box(&secretkey))
+        });
+
+        let sig = sign(&mut rng, &msg, &tag, &secretkey);
+        let sig2 = sign(&mut rng, &msg, &tag, &secretkey);
+        assert!(verify(&msg, &tag, &sig));
+        let verify_measurement = Bench::from_env(10).run(|| {
+            verify(black_box(&msg), black_box(&tag), black_box(&sig))
+        });
+        let trace_measurement = Bench::from_env(10).run(|| {
+            trace(black_box(&tag), black_box(&msg), black_box(&msg), black_box(&sig), black_box(&sig2))
+        });
+
+        emit(&Record::measured("trs-linkable", "sign", n, &sign_measurement));
+        emit(&Record::measured("trs-linkable", "verify", n, &verify_measurement));
+        emit(&Record::measured("trs-linkable", "trace", n, &trace_measurement));
+        emit(&Record::size("trs-linkable", "size", n, sig.to_bytes().len()));
     }
-}
\ No newline at end of file
+}
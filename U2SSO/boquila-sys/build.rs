@@ -0,0 +1,32 @@
+// Compiles the secp256k1 fork in ../crypto-dbpoe with the ringcip module, which it
+// always includes, and the generator module it needs. The defines replace those
+// configure writes to libsecp256k1-config.h, without GMP, so the build only needs a
+// C compiler. The precomputed tables in src/ecmult_static_context.h are used as the
+// makefile build does.
+
+use std::path::Path;
+
+fn main() {
+    let root = Path::new("../crypto-dbpoe");
+    cc::Build::new()
+        .file("src/boquila.c")
+        .include(root)
+        .include(root.join("src"))
+        .define("USE_NUM_NONE", "1")
+        .define("USE_FIELD_INV_BUILTIN", "1")
+        .define("USE_SCALAR_INV_BUILTIN", "1")
+        .define("USE_FIELD_5X52", "1")
+        .define("USE_SCALAR_4X64", "1")
+        .define("HAVE___INT128", "1")
+        .define("USE_ECMULT_STATIC_PRECOMPUTATION", "1")
+        .define("ENABLE_MODULE_GENERATOR", "1")
+        .flag("-std=gnu99")
+        .opt_level(3)
+        .warnings(false)
+        .compile("boquila");
+
+    println!("cargo:rerun-if-changed=src/boquila.c");
+    for dir in &["src", "include"] {
+        println!("cargo:rerun-if-changed={}", root.join(dir).display());
+    }
+}
@@ -0,0 +1,8 @@
+/* The library as one translation unit, as src/secp256k1.c builds it, with a way to
+ * seed the random number generator the ringcip module keeps as a static. */
+
+#include "src/secp256k1.c"
+
+void boquila_rng_seed(const unsigned char *seed32) {
+    secp256k1_rfc6979_hmac_sha256_initialize(&secp256k1_test_rng, seed32, 32);
+}
@@ -0,0 +1,132 @@
+//! Raw bindings to the Boquila ring membership proofs of the secp256k1 fork in
+//! `../crypto-dbpoe`.
+//!
+//! See `include/secp256k1_ringcip.h` for the functions. Only the DBPoE variant is
+//! bound, in which a master public key commits to one nullifier per topic. The proofs
+//! draw their randomness from a generator the C code keeps in global state, seeded by
+//! [`boquila_rng_seed`], so proofs must not be made from several threads at once. The
+//! `boquila` crate wraps these safely.
+
+#![allow(non_camel_case_types, non_snake_case)]
+
+use libc::{c_int, c_uint};
+
+pub const SECP256K1_CONTEXT_VERIFY: c_uint = (1 << 0) | (1 << 8);
+pub const SECP256K1_CONTEXT_SIGN: c_uint = (1 << 0) | (1 << 9);
+
+/// The length of a serialized point, a master or service public key
+pub const PK_LEN: usize = 33;
+
+#[repr(C)]
+pub struct secp256k1_context {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct secp256k1_generator {
+    pub data: [u8; 64],
+}
+
+/// The generators of proofs for rings of up to N = nᵐ members and `topic_size` topics,
+/// each of 32 bytes
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ringcip_DBPoE_context {
+    pub L: c_int,
+    pub n: c_int,
+    pub m: c_int,
+    pub N: c_int,
+    pub multigen: *mut secp256k1_generator,
+    pub geng: *mut secp256k1_generator,
+    pub genh: secp256k1_generator,
+    pub genmu: secp256k1_generator,
+    pub topics: *mut u8,
+    pub topic_size: c_int,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct pk_t {
+    pub buf: [u8; PK_LEN],
+}
+
+extern "C" {
+    pub fn secp256k1_context_create(flags: c_uint) -> *mut secp256k1_context;
+
+    pub fn secp256k1_context_destroy(ctx: *mut secp256k1_context);
+
+    /// Seeds the generator the proofs draw their randomness from, defined in `src/boquila.c`
+    pub fn boquila_rng_seed(seed32: *const u8);
+
+    /// Keeps a pointer to `topics`, which must outlive the context
+    pub fn secp256k1_ringcip_DBPoE_context_create(
+        ctx: *const secp256k1_context,
+        L: c_int,
+        n: c_int,
+        m: c_int,
+        gen_seed: *mut u8,
+        topics: *mut u8,
+        topic_size: c_int,
+    ) -> ringcip_DBPoE_context;
+
+    pub fn secp256k1_ringcip_DBPoE_context_clear(rctx: *mut ringcip_DBPoE_context);
+
+    /// The length of a proof for a ring of up to nᵐ members
+    pub fn secp256k1_zero_mcom_DBPoE_get_size(rctx: *const ringcip_DBPoE_context, m: c_int)
+        -> c_int;
+
+    pub fn secp256k1_boquila_gen_DBPoE_mpk(
+        ctx: *const secp256k1_context,
+        rctx: *const ringcip_DBPoE_context,
+        mpk: *mut pk_t,
+        msk: *const u8,
+    ) -> c_int;
+
+    pub fn secp256k1_boquila_derive_ssk(
+        ctx: *const secp256k1_context,
+        csk: *mut u8,
+        msk: *const u8,
+        name: *const u8,
+        name_len: c_int,
+    ) -> c_int;
+
+    pub fn secp256k1_boquila_derive_DBPoE_spk(
+        ctx: *const secp256k1_context,
+        rctx: *const ringcip_DBPoE_context,
+        cpk: *mut pk_t,
+        csk: *const u8,
+    ) -> c_int;
+
+    pub fn secp256k1_boquila_prove_DBPoE_memmpk(
+        ctx: *const secp256k1_context,
+        rctx: *const ringcip_DBPoE_context,
+        proof: *mut u8,
+        nullifier: *mut u8,
+        mpks: *mut pk_t,
+        msk: *const u8,
+        W: *const u8,
+        name: *const u8,
+        name_len: c_int,
+        spk: *mut pk_t,
+        j: i32,
+        topic_index: c_int,
+        N: i32,
+        m: c_int,
+    ) -> c_int;
+
+    pub fn secp256k1_boquila_verify_DBPoE_memmpk(
+        ctx: *const secp256k1_context,
+        rctx: *const ringcip_DBPoE_context,
+        proof: *mut u8,
+        nullifier: *mut u8,
+        mpks: *mut pk_t,
+        W: *const u8,
+        name: *const u8,
+        name_len: c_int,
+        spk: *mut pk_t,
+        topic_index: c_int,
+        N: i32,
+        m: c_int,
+    ) -> c_int;
+}
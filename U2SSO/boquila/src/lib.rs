@@ -0,0 +1,460 @@
+//! Safe wrapper over the Boquila ring membership proofs of the U2SSO secp256k1 fork.
+//!
+//! A user registers a master public key, and proves to a service that one of a set of
+//! master public keys is theirs without saying which. The DBPoE variant bound here
+//! commits each master key to one nullifier per topic, a list fixed with the
+//! parameters, and a proof for a topic reveals that topic's nullifier, so a service
+//! sees the same nullifier every time one user registers with it. Each proof also
+//! binds a service public key derived from the master key and the service's name.
+//!
+//! The C code draws the proofs' randomness from a generator in global state, so every
+//! proof holds a lock, and the generator is seeded from the operating system on first
+//! use. Invalid arguments abort in the C code, so they are checked here first.
+
+use boquila_sys as sys;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+use std::os::raw::c_int;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+// The range proof bit length the C contexts are made with, unused by membership proofs
+const L: c_int = 10;
+
+// Whether the C random number generator has been seeded
+static LOCK: Mutex<bool> = Mutex::new(false);
+
+fn lock() -> MutexGuard<'static, bool> {
+    let mut seeded = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    if !*seeded {
+        let mut seed = [0u8; 32];
+        OsRng.fill_bytes(&mut seed);
+        unsafe { sys::boquila_rng_seed(seed.as_ptr()) };
+        *seeded = true;
+    }
+    seeded
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The base is less than 2, there are no digits, or there are fewer than two topics
+    InvalidParams,
+    EmptyRing,
+    RingTooLarge {
+        size: usize,
+        max: usize,
+    },
+    /// The prover's index is not in the ring, or its key there is not theirs
+    NotInRing,
+    /// Topics are numbered from 1 to one less than the number of topics
+    InvalidTopic(usize),
+    EmptyName,
+    Prove,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidParams => write!(f, "invalid parameters"),
+            Error::EmptyRing => write!(f, "the ring has no members"),
+            Error::RingTooLarge { size, max } => write!(
+                f,
+                "a ring of {} members is larger than the parameters allow ({})",
+                size, max
+            ),
+            Error::NotInRing => write!(f, "the prover's key is not at its index in the ring"),
+            Error::InvalidTopic(topic) => write!(f, "no topic {}", topic),
+            Error::EmptyName => write!(f, "the service name is empty"),
+            Error::Prove => write!(f, "proving failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The generators for rings of up to nᵐ members and the list of topics
+pub struct Params {
+    ctx: *mut sys::secp256k1_context,
+    rctx: sys::ringcip_DBPoE_context,
+    // The C context points into this, so it is never resized
+    topics: Vec<u8>,
+}
+
+// The contexts are only read after they are made, and proofs hold the lock
+unsafe impl Send for Params {}
+unsafe impl Sync for Params {}
+
+impl Params {
+    /// Makes the parameters for rings of up to `n`ᵐ members, with base `n`, for the
+    /// topics. Every party derives the same generators, from a fixed seed as the C code does
+    pub fn new(n: usize, m: usize, topics: &[[u8; 32]]) -> Result<Params, Error> {
+        let max = n
+            .checked_pow(m as u32)
+            .filter(|&max| max <= i32::MAX as usize);
+        if n < 2 || m == 0 || max.is_none() || topics.len() < 2 {
+            return Err(Error::InvalidParams);
+        }
+        let mut topics: Vec<u8> = topics.concat();
+        let mut gen_seed = [0xffu8; 32];
+        unsafe {
+            let ctx = sys::secp256k1_context_create(
+                sys::SECP256K1_CONTEXT_SIGN | sys::SECP256K1_CONTEXT_VERIFY,
+            );
+            let rctx = sys::secp256k1_ringcip_DBPoE_context_create(
+                ctx,
+                L,
+                n as c_int,
+                m as c_int,
+                gen_seed.as_mut_ptr(),
+                topics.as_mut_ptr(),
+                (topics.len() / 32) as c_int,
+            );
+            Ok(Params { ctx, rctx, topics })
+        }
+    }
+
+    /// The largest ring a proof can be made for
+    pub fn max_ring_size(&self) -> usize {
+        self.rctx.N as usize
+    }
+
+    pub fn topic_count(&self) -> usize {
+        self.topics.len() / 32
+    }
+
+    // The number of base-n digits of an index into a ring of `size` members
+    fn digits(&self, size: usize) -> Result<c_int, Error> {
+        if size == 0 {
+            return Err(Error::EmptyRing);
+        }
+        if size > self.max_ring_size() {
+            return Err(Error::RingTooLarge {
+                size,
+                max: self.max_ring_size(),
+            });
+        }
+        let (n, mut m, mut capacity) = (self.rctx.n as usize, 1, self.rctx.n as usize);
+        while capacity < size {
+            capacity *= n;
+            m += 1;
+        }
+        Ok(m)
+    }
+
+    /// The length of a proof for a ring of `size` members
+    pub fn proof_size(&self, size: usize) -> Result<usize, Error> {
+        let m = self.digits(size)?;
+        Ok(unsafe { sys::secp256k1_zero_mcom_DBPoE_get_size(&self.rctx, m) } as usize)
+    }
+
+    fn check_topic(&self, topic: usize) -> Result<(), Error> {
+        if topic == 0 || topic >= self.topic_count() {
+            return Err(Error::InvalidTopic(topic));
+        }
+        Ok(())
+    }
+
+    /// Generates a master key pair, whose public key commits to a nullifier for every topic
+    pub fn keygen<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> (MasterPublicKey, MasterSecretKey) {
+        loop {
+            let mut msk = MasterSecretKey([0u8; 32]);
+            rng.fill_bytes(&mut msk.0);
+            // Fails only for keys of at least the group order
+            if let Some(mpk) = self.master_public_key(&msk) {
+                return (mpk, msk);
+            }
+        }
+    }
+
+    fn master_public_key(&self, msk: &MasterSecretKey) -> Option<MasterPublicKey> {
+        let mut mpk = sys::pk_t {
+            buf: [0u8; sys::PK_LEN],
+        };
+        let ok = unsafe {
+            sys::secp256k1_boquila_gen_DBPoE_mpk(self.ctx, &self.rctx, &mut mpk, msk.0.as_ptr())
+        };
+        if ok == 1 {
+            Some(MasterPublicKey(mpk.buf))
+        } else {
+            None
+        }
+    }
+
+    /// The service public key of a master key for a service name
+    pub fn service_public_key(
+        &self,
+        msk: &MasterSecretKey,
+        name: &[u8],
+    ) -> Result<ServicePublicKey, Error> {
+        if name.is_empty() {
+            return Err(Error::EmptyName);
+        }
+        let mut csk = [0u8; 32];
+        let mut spk = sys::pk_t {
+            buf: [0u8; sys::PK_LEN],
+        };
+        let ok = unsafe {
+            sys::secp256k1_boquila_derive_ssk(
+                self.ctx,
+                csk.as_mut_ptr(),
+                msk.0.as_ptr(),
+                name.as_ptr(),
+                name.len() as c_int,
+            ) == 1
+                && sys::secp256k1_boquila_derive_DBPoE_spk(
+                    self.ctx,
+                    &self.rctx,
+                    &mut spk,
+                    csk.as_ptr(),
+                ) == 1
+        };
+        if !ok {
+            return Err(Error::Prove);
+        }
+        Ok(ServicePublicKey(spk.buf))
+    }
+
+    /// Proves the master key at `index` of the ring is the prover's, for the service
+    /// `name` and its `challenge`, revealing the nullifier for `topic`
+    pub fn prove(
+        &self,
+        ring: &[MasterPublicKey],
+        index: usize,
+        msk: &MasterSecretKey,
+        topic: usize,
+        name: &[u8],
+        challenge: &[u8; 32],
+    ) -> Result<Proof, Error> {
+        let size = self.proof_size(ring.len())?;
+        self.check_topic(topic)?;
+        if ring.get(index) != self.master_public_key(msk).as_ref() {
+            return Err(Error::NotInRing);
+        }
+        let spk = self.service_public_key(msk, name)?;
+
+        let mut mpks = to_pks(ring);
+        let mut proof = vec![0u8; size];
+        let mut nullifier = Nullifier([0u8; 32]);
+        let mut spk_pk = sys::pk_t { buf: spk.0 };
+        let _lock = lock();
+        let ok = unsafe {
+            sys::secp256k1_boquila_prove_DBPoE_memmpk(
+                self.ctx,
+                &self.rctx,
+                proof.as_mut_ptr(),
+                nullifier.0.as_mut_ptr(),
+                mpks.as_mut_ptr(),
+                msk.0.as_ptr(),
+                challenge.as_ptr(),
+                name.as_ptr(),
+                name.len() as c_int,
+                &mut spk_pk,
+                index as i32,
+                topic as c_int,
+                ring.len() as i32,
+                self.digits(ring.len())?,
+            )
+        };
+        if ok != 1 {
+            return Err(Error::Prove);
+        }
+        Ok(Proof {
+            proof,
+            nullifier,
+            spk,
+        })
+    }
+
+    /// Verifies a proof that one of the ring's master keys made the proof's service
+    /// public key and nullifier, for the topic, service `name` and `challenge`
+    pub fn verify(
+        &self,
+        ring: &[MasterPublicKey],
+        topic: usize,
+        name: &[u8],
+        challenge: &[u8; 32],
+        proof: &Proof,
+    ) -> bool {
+        if self.check_topic(topic).is_err()
+            || name.is_empty()
+            || self.proof_size(ring.len()) != Ok(proof.proof.len())
+        {
+            return false;
+        }
+        let m = match self.digits(ring.len()) {
+            Ok(m) => m,
+            Err(_) => return false,
+        };
+
+        let mut mpks = to_pks(ring);
+        let (mut bytes, mut nullifier) = (proof.proof.clone(), proof.nullifier.0);
+        let mut spk = sys::pk_t { buf: proof.spk.0 };
+        unsafe {
+            sys::secp256k1_boquila_verify_DBPoE_memmpk(
+                self.ctx,
+                &self.rctx,
+                bytes.as_mut_ptr(),
+                nullifier.as_mut_ptr(),
+                mpks.as_mut_ptr(),
+                challenge.as_ptr(),
+                name.as_ptr(),
+                name.len() as c_int,
+                &mut spk,
+                topic as c_int,
+                ring.len() as i32,
+                m,
+            ) == 1
+        }
+    }
+}
+
+impl Drop for Params {
+    fn drop(&mut self) {
+        unsafe {
+            sys::secp256k1_ringcip_DBPoE_context_clear(&mut self.rctx);
+            sys::secp256k1_context_destroy(self.ctx);
+        }
+    }
+}
+
+fn to_pks(ring: &[MasterPublicKey]) -> Vec<sys::pk_t> {
+    ring.iter().map(|mpk| sys::pk_t { buf: mpk.0 }).collect()
+}
+
+/// A master secret key, a scalar less than the group order
+#[derive(Clone)]
+pub struct MasterSecretKey([u8; 32]);
+
+impl fmt::Debug for MasterSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MasterSecretKey(..)")
+    }
+}
+
+/// A master public key, the identity a user registers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MasterPublicKey([u8; sys::PK_LEN]);
+
+impl MasterPublicKey {
+    pub fn to_bytes(&self) -> [u8; sys::PK_LEN] {
+        self.0
+    }
+
+    pub fn from_bytes(bytes: [u8; sys::PK_LEN]) -> MasterPublicKey {
+        MasterPublicKey(bytes)
+    }
+}
+
+/// The public key a user is known by to one service
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ServicePublicKey([u8; sys::PK_LEN]);
+
+impl ServicePublicKey {
+    pub fn to_bytes(&self) -> [u8; sys::PK_LEN] {
+        self.0
+    }
+}
+
+/// The nullifier of a master key for a topic, the same in every proof for that topic
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Nullifier([u8; 32]);
+
+impl Nullifier {
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+/// A ring membership proof with the nullifier and service public key it proves
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    proof: Vec<u8>,
+    nullifier: Nullifier,
+    spk: ServicePublicKey,
+}
+
+impl Proof {
+    /// The proof as the C code writes it, without the nullifier and service public key
+    pub fn to_bytes(&self) -> &[u8] {
+        &self.proof
+    }
+
+    pub fn nullifier(&self) -> &Nullifier {
+        &self.nullifier
+    }
+
+    pub fn service_public_key(&self) -> &ServicePublicKey {
+        &self.spk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topics(k: u8) -> Vec<[u8; 32]> {
+        (0..k).map(|t| [t; 32]).collect()
+    }
+
+    #[test]
+    fn prove_and_verify() {
+        let mut rng = rand::thread_rng();
+        let params = Params::new(2, 3, &topics(4)).unwrap();
+        let keys: Vec<_> = (0..6).map(|_| params.keygen(&mut rng)).collect();
+        let ring: Vec<MasterPublicKey> = keys.iter().map(|(mpk, _)| *mpk).collect();
+        let (challenge, other) = ([1u8; 32], [2u8; 32]);
+
+        let proof = params
+            .prove(&ring, 4, &keys[4].1, 1, b"service", &challenge)
+            .unwrap();
+        assert_eq!(proof.to_bytes().len(), params.proof_size(6).unwrap());
+        assert!(params.verify(&ring, 1, b"service", &challenge, &proof));
+        assert!(!params.verify(&ring, 1, b"service", &other, &proof));
+        assert!(!params.verify(&ring, 2, b"service", &challenge, &proof));
+        assert!(!params.verify(&ring, 1, b"other service", &challenge, &proof));
+        assert!(!params.verify(&ring[1..], 1, b"service", &challenge, &proof));
+
+        assert_eq!(
+            params.prove(&ring, 3, &keys[4].1, 1, b"service", &challenge),
+            Err(Error::NotInRing)
+        );
+        assert_eq!(
+            params.prove(&ring, 4, &keys[4].1, 4, b"service", &challenge),
+            Err(Error::InvalidTopic(4))
+        );
+        assert_eq!(
+            params.prove(
+                &[ring.clone(), ring].concat(),
+                4,
+                &keys[4].1,
+                1,
+                b"service",
+                &challenge
+            ),
+            Err(Error::RingTooLarge { size: 12, max: 8 })
+        );
+    }
+
+    #[test]
+    fn nullifiers_are_per_topic() {
+        let mut rng = rand::thread_rng();
+        let params = Params::new(2, 2, &topics(3)).unwrap();
+        let keys: Vec<_> = (0..4).map(|_| params.keygen(&mut rng)).collect();
+        let ring: Vec<MasterPublicKey> = keys.iter().map(|(mpk, _)| *mpk).collect();
+        let prove = |i: usize, topic: usize, challenge: u8| {
+            params
+                .prove(&ring, i, &keys[i].1, topic, b"service", &[challenge; 32])
+                .unwrap()
+        };
+
+        let (p1, p2) = (prove(0, 1, 1), prove(0, 1, 2));
+        assert_eq!(p1.nullifier(), p2.nullifier());
+        assert_eq!(p1.service_public_key(), p2.service_public_key());
+        assert_ne!(p1.nullifier(), prove(0, 2, 1).nullifier());
+        assert_ne!(p1.nullifier(), prove(1, 1, 1).nullifier());
+        assert!(params.verify(&ring, 2, b"service", &[1; 32], &prove(3, 2, 1)));
+    }
+}
@@ -0,0 +1,81 @@
+use bench_harness::alloc::CountingAlloc;
+use bench_harness::timing::{black_box, Bench};
+use bench_harness::{emit, Record};
+use boquila::*;
+
+// Lets the benchmarks report heap usage alongside their timings
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+// The powers of two the C DBPoE benchmark measures
+const RING_SIZES: [usize; 8] = [8, 16, 32, 64, 128, 256, 512, 1024];
+
+// The C DBPoE benchmark's first topic count, with proofs for topic 1
+const TOPICS: u8 = 100;
+const TOPIC: usize = 1;
+
+const NAME: &[u8] = b"abcd";
+const CHALLENGE: [u8; 32] = [0xff; 32];
+
+// Parameters for rings of up to 1024 members in base 2
+fn params() -> Params {
+    let topics: Vec<[u8; 32]> = (0..TOPICS).map(|t| [t; 32]).collect();
+    Params::new(2, 10, &topics).unwrap()
+}
+
+// Enough master keys for the largest ring measured. The prover is the first member,
+// as in the C benchmark
+fn setup(sizes: &[usize]) -> (Params, Vec<MasterPublicKey>, MasterSecretKey) {
+    let params = params();
+    let mut rng = rand::thread_rng();
+    let (mpk, msk) = params.keygen(&mut rng);
+    let mut ring = vec![mpk];
+    let max = sizes.iter().copied().max().unwrap_or(1);
+    ring.extend((1..max).map(|_| params.keygen(&mut rng).0));
+    (params, ring, msk)
+}
+
+#[test]
+fn prove_bench() {
+    let sizes = bench_harness::ring_sizes(&RING_SIZES);
+    let (params, ring, msk) = setup(&sizes);
+    for n in sizes {
+        let ring = &ring[..n];
+
+        let measurement = Bench::from_env(10).run(|| {
+            params
+                .prove(black_box(ring), 0, &msk, TOPIC, NAME, &CHALLENGE)
+                .unwrap()
+        });
+        emit(&Record::measured("boquila-rust", "sign", n, &measurement));
+    }
+}
+
+#[test]
+fn verify_bench() {
+    let sizes = bench_harness::ring_sizes(&RING_SIZES);
+    let (params, ring, msk) = setup(&sizes);
+    for n in sizes {
+        let ring = &ring[..n];
+        let proof = params
+            .prove(ring, 0, &msk, TOPIC, NAME, &CHALLENGE)
+            .unwrap();
+
+        let measurement = Bench::from_env(10)
+            .run(|| assert!(params.verify(black_box(ring), TOPIC, NAME, &CHALLENGE, &proof)));
+        emit(&Record::measured("boquila-rust", "verify", n, &measurement));
+    }
+}
+
+#[test]
+fn size_bench() {
+    let params = params();
+    for n in bench_harness::ring_sizes(&RING_SIZES) {
+        emit(&Record::size(
+            "boquila-rust",
+            "size",
+            n,
+            params.proof_size(n).unwrap(),
+        ));
+    }
+}
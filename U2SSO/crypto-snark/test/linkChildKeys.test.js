@@ -6,6 +6,22 @@ const { Identity } = require("@semaphore-protocol/identity");
 const { Group } = require("@semaphore-protocol/group");
 const buildBabyjub = require("circomlibjs").buildBabyjub;
 
+// The ring sizes in BENCH_RING_SIZES, a comma separated list set by bench-runner,
+// or the powers of two from 8 to 1024 if it is not set
+function benchRingSizes() {
+    const env = process.env.BENCH_RING_SIZES;
+    if (!env) {
+        return [8, 16, 32, 64, 128, 256, 512, 1024];
+    }
+    return env.split(",").map((size) => {
+        const n = parseInt(size.trim(), 10);
+        if (!(n > 0)) {
+            throw new Error(`invalid BENCH_RING_SIZES: ${env}`);
+        }
+        return n;
+    });
+}
+
 const {
     genMasterPk,
     deriveWebKey,
@@ -192,7 +208,7 @@ describe("Boquila", function () {
             let provingTime = 0;
             let verificationTime = 0;
             let proofSize = 0;
-            for(let groupSize = 8; groupSize <= 1024; groupSize = groupSize*2) {
+            for (const groupSize of benchRingSizes()) {
                 let group = new Group([]);
                 let identities = [];
                 let msks = [];
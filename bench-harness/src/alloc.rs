@@ -0,0 +1,118 @@
+//! Heap profiling for the test based benchmarks.
+//!
+//! [`CountingAlloc`] wraps the system allocator and counts allocations and the bytes
+//! in use. A benchmark installs it as its global allocator with
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOC: bench_harness::alloc::CountingAlloc = bench_harness::alloc::CountingAlloc;
+//! ```
+//!
+//! after which [`measure`] reports the peak heap usage and number of allocations of an
+//! operation, and [`crate::timing::Bench::run`] includes them in every measurement.
+//! The counters are shared by every thread, so benchmarks should be run one at a time.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// A global allocator counting heap usage, see the [module documentation](self)
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+            allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+fn allocated(size: usize) {
+    INSTALLED.store(true, Ordering::Relaxed);
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+/// Heap usage of an operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocStats {
+    /// Most bytes in use at once, beyond those in use when the operation started
+    pub peak_bytes: usize,
+    /// Number of allocations, counting reallocations
+    pub allocations: usize,
+}
+
+/// Whether [`CountingAlloc`] is the global allocator
+pub fn is_installed() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Runs `f`, returning its result and heap usage, or `None` for the usage if
+/// [`CountingAlloc`] is not the global allocator
+pub fn measure<R, F: FnOnce() -> R>(f: F) -> (R, Option<AllocStats>) {
+    let start = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(start, Ordering::Relaxed);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+
+    let result = f();
+
+    let stats = AllocStats {
+        peak_bytes: PEAK.load(Ordering::Relaxed).saturating_sub(start),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+    };
+    (result, Some(stats).filter(|_| is_installed()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[global_allocator]
+    static ALLOC: CountingAlloc = CountingAlloc;
+
+    #[test]
+    fn counts_peak_and_allocations() {
+        let (len, stats) = measure(|| {
+            let a = vec![0u8; 1 << 20];
+            let b = vec![0u8; 1 << 10];
+            drop(a);
+            let c = vec![0u8; 1 << 19];
+            b.len() + c.len()
+        });
+        assert_eq!(len, (1 << 10) + (1 << 19));
+
+        // Other test threads allocate and free at the same time
+        let stats = stats.unwrap();
+        assert!(stats.peak_bytes >= 1 << 20);
+        assert!(stats.allocations >= 3);
+    }
+}
@@ -0,0 +1,354 @@
+//! Stored benchmark baselines and regression reports.
+//!
+//! A baseline is a results file saved under the git revision it was measured at.
+//! Comparing new results against a baseline matches records by scheme, operation and
+//! ring size, and flags timings which changed by more than a threshold where the change
+//! is also statistically significant under Welch's t-test, and any change in size.
+
+use crate::timing::t_critical;
+use crate::{read_records, write_records, Record};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Records are matched across runs by scheme, operation, ring size and message length
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Key {
+    pub scheme: String,
+    pub operation: String,
+    pub ring_size: usize,
+    pub message_bytes: Option<usize>,
+}
+
+impl Key {
+    fn of(record: &Record) -> Key {
+        Key {
+            scheme: record.scheme.clone(),
+            operation: record.operation.clone(),
+            ring_size: record.ring_size,
+            message_bytes: record.message_bytes,
+        }
+    }
+}
+
+/// How a benchmark changed from the baseline
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// Significantly slower, by the given ratio of the means
+    Slower(f64),
+    /// Significantly faster, by the given ratio of the means
+    Faster(f64),
+    /// The size changed from the first to the second number of bytes
+    Size(usize, usize),
+    Unchanged,
+    /// Only in the new results
+    Added,
+    /// Only in the baseline
+    Removed,
+}
+
+impl Change {
+    /// Whether this change should fail a comparison
+    pub fn is_regression(&self) -> bool {
+        match self {
+            Change::Slower(_) => true,
+            Change::Size(before, after) => after > before,
+            _ => false,
+        }
+    }
+}
+
+/// The comparison of one benchmark
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    pub key: Key,
+    pub baseline: Option<Record>,
+    pub current: Option<Record>,
+    pub change: Change,
+}
+
+/// Returns the path of the baseline for a git revision
+pub fn path(dir: &Path, git_rev: &str) -> PathBuf {
+    dir.join(format!("{}.jsonl", git_rev))
+}
+
+/// Saves results as the baseline for the git revision they were measured at,
+/// replacing any results already saved for the same benchmarks at that revision
+pub fn save(dir: &Path, records: &[Record]) -> io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+
+    let mut by_rev: BTreeMap<&str, Vec<Record>> = BTreeMap::new();
+    for record in records {
+        by_rev
+            .entry(record.git_rev.as_str())
+            .or_default()
+            .push(record.clone());
+    }
+
+    let mut paths = Vec::new();
+    for (rev, records) in by_rev {
+        let path = path(dir, rev);
+        let path_str = path.to_str().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "baseline path is not UTF-8")
+        })?;
+
+        let existing = if path.exists() {
+            read_records(path_str)?
+        } else {
+            Vec::new()
+        };
+        let merged: Vec<Record> = latest(&existing)
+            .into_iter()
+            .chain(latest(&records))
+            .collect::<BTreeMap<Key, Record>>()
+            .into_values()
+            .collect();
+
+        write_records(path_str, &merged)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+// Keys the records, keeping the last record of any benchmark that was run more than once
+fn latest(records: &[Record]) -> BTreeMap<Key, Record> {
+    records
+        .iter()
+        .map(|record| (Key::of(record), record.clone()))
+        .collect()
+}
+
+/// Compares new results against a baseline.
+/// Timings must change by more than `threshold`, e.g. 0.05 for 5%, to be flagged
+pub fn compare(baseline: &[Record], current: &[Record], threshold: f64) -> Vec<Comparison> {
+    let mut baseline = latest(baseline);
+    let mut comparisons: Vec<Comparison> = latest(current)
+        .into_iter()
+        .map(|(key, current)| {
+            let baseline = baseline.remove(&key);
+            let change = match &baseline {
+                Some(baseline) => change(baseline, &current, threshold),
+                None => Change::Added,
+            };
+            Comparison {
+                key,
+                baseline,
+                current: Some(current),
+                change,
+            }
+        })
+        .collect();
+
+    comparisons.extend(baseline.into_iter().map(|(key, baseline)| Comparison {
+        key,
+        baseline: Some(baseline),
+        current: None,
+        change: Change::Removed,
+    }));
+    comparisons.sort_by(|a, b| a.key.cmp(&b.key));
+    comparisons
+}
+
+fn change(baseline: &Record, current: &Record, threshold: f64) -> Change {
+    if let (Some(before), Some(after)) = (baseline.bytes, current.bytes) {
+        return if before == after {
+            Change::Unchanged
+        } else {
+            Change::Size(before, after)
+        };
+    }
+
+    let (before, after) = match (baseline.mean_ms, current.mean_ms) {
+        (Some(before), Some(after)) if before > 0.0 => (before, after),
+        _ => return Change::Unchanged,
+    };
+    let ratio = after / before;
+    if (ratio - 1.0).abs() <= threshold || !is_significant(baseline, current) {
+        return Change::Unchanged;
+    }
+    if ratio > 1.0 {
+        Change::Slower(ratio)
+    } else {
+        Change::Faster(ratio)
+    }
+}
+
+// Welch's t-test for a difference in the means at the 95% level
+fn is_significant(baseline: &Record, current: &Record) -> bool {
+    let stats = |r: &Record| {
+        (
+            r.mean_ms.unwrap_or(0.0),
+            r.stddev_ms.unwrap_or(0.0),
+            r.trials.max(1) as f64,
+        )
+    };
+    let (m1, s1, n1) = stats(baseline);
+    let (m2, s2, n2) = stats(current);
+
+    let (v1, v2) = (s1 * s1 / n1, s2 * s2 / n2);
+    if v1 + v2 == 0.0 {
+        // Without any spread, every difference is significant
+        return m1 != m2;
+    }
+    let t = (m2 - m1).abs() / (v1 + v2).sqrt();
+
+    // Welch–Satterthwaite degrees of freedom
+    let df = if n1 > 1.0 && n2 > 1.0 {
+        (v1 + v2).powi(2) / (v1 * v1 / (n1 - 1.0) + v2 * v2 / (n2 - 1.0))
+    } else {
+        1.0
+    };
+    t > t_critical(df.floor().max(1.0) as usize)
+}
+
+/// Formats the comparisons as a table, listing regressions first
+pub fn report(comparisons: &[Comparison]) -> String {
+    let mut out = String::new();
+    let regressions = comparisons
+        .iter()
+        .filter(|c| c.change.is_regression())
+        .count();
+    writeln!(
+        out,
+        "{} benchmarks compared, {} regressions",
+        comparisons.len(),
+        regressions
+    )
+    .unwrap();
+
+    let mut sorted: Vec<&Comparison> = comparisons.iter().collect();
+    sorted.sort_by_key(|c| !c.change.is_regression());
+
+    writeln!(
+        out,
+        "{:<10} {:<14} {:>6} {:>8} {:>14} {:>14}  change",
+        "scheme", "operation", "n", "msg", "baseline", "current"
+    )
+    .unwrap();
+    for c in sorted {
+        let value = |r: &Option<Record>| match r {
+            Some(Record {
+                bytes: Some(bytes), ..
+            }) => format!("{} B", bytes),
+            Some(Record {
+                mean_ms: Some(mean),
+                stddev_ms,
+                ..
+            }) => format!("{:.3}±{:.3}", mean, stddev_ms.unwrap_or(0.0)),
+            _ => "-".to_string(),
+        };
+        let change = match &c.change {
+            Change::Slower(ratio) => format!("REGRESSION {:+.1}% slower", (ratio - 1.0) * 100.0),
+            Change::Faster(ratio) => format!("{:.1}% faster", (1.0 - ratio) * 100.0),
+            Change::Size(before, after) if after > before => {
+                format!("REGRESSION {:+} bytes", *after as i64 - *before as i64)
+            }
+            Change::Size(before, after) => {
+                format!("{:+} bytes", *after as i64 - *before as i64)
+            }
+            Change::Unchanged => "unchanged".to_string(),
+            Change::Added => "new".to_string(),
+            Change::Removed => "missing".to_string(),
+        };
+        writeln!(
+            out,
+            "{:<10} {:<14} {:>6} {:>8} {:>14} {:>14}  {}",
+            c.key.scheme,
+            c.key.operation,
+            c.key.ring_size,
+            c.key
+                .message_bytes
+                .map(|m| m.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            value(&c.baseline),
+            value(&c.current),
+            change
+        )
+        .unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(operation: &str, mean: f64, stddev: f64) -> Record {
+        Record::summary("trs", operation, 16, 10, mean, stddev)
+    }
+
+    #[test]
+    fn flags_significant_slowdowns() {
+        let baseline = vec![
+            timing("sign", 10.0, 0.1),
+            timing("verify", 10.0, 5.0),
+            timing("trace", 10.0, 0.1),
+        ];
+        let current = vec![
+            // 20% slower with little noise
+            timing("sign", 12.0, 0.1),
+            // 20% slower but within the noise
+            timing("verify", 12.0, 5.0),
+            // Significant, but below the threshold
+            timing("trace", 10.2, 0.01),
+        ];
+
+        let comparisons = compare(&baseline, &current, 0.05);
+        let change = |op: &str| {
+            comparisons
+                .iter()
+                .find(|c| c.key.operation == op)
+                .unwrap()
+                .change
+                .clone()
+        };
+        assert!(matches!(change("sign"), Change::Slower(r) if (r - 1.2).abs() < 1e-9));
+        assert_eq!(change("verify"), Change::Unchanged);
+        assert_eq!(change("trace"), Change::Unchanged);
+
+        let report = report(&comparisons);
+        assert!(report.contains("1 regressions"));
+    }
+
+    #[test]
+    fn flags_size_changes_and_missing_benchmarks() {
+        let baseline = vec![
+            Record::size("trs", "size", 16, 1056),
+            timing("sign", 10.0, 0.1),
+        ];
+        let current = vec![
+            Record::size("trs", "size", 16, 1100),
+            Record::size("trs", "size", 32, 2080),
+        ];
+
+        let comparisons = compare(&baseline, &current, 0.05);
+        let changes: Vec<Change> = comparisons.iter().map(|c| c.change.clone()).collect();
+        assert!(changes.contains(&Change::Size(1056, 1100)));
+        assert!(changes.contains(&Change::Added));
+        assert!(changes.contains(&Change::Removed));
+        assert!(Change::Size(1056, 1100).is_regression());
+        assert!(!Change::Size(1100, 1056).is_regression());
+    }
+
+    #[test]
+    fn save_merges_by_revision() {
+        let dir =
+            std::env::temp_dir().join(format!("bench-harness-baselines-{}", std::process::id()));
+
+        let mut first = timing("sign", 10.0, 0.1);
+        first.git_rev = "abc123".to_string();
+        let mut second = timing("verify", 8.0, 0.1);
+        second.git_rev = "abc123".to_string();
+
+        save(&dir, &[first.clone()]).unwrap();
+        let paths = save(&dir, &[second.clone()]).unwrap();
+        assert_eq!(paths, vec![path(&dir, "abc123")]);
+
+        let saved = read_records(paths[0].to_str().unwrap()).unwrap();
+        assert_eq!(saved.len(), 2);
+        assert!(saved.contains(&first) && saved.contains(&second));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
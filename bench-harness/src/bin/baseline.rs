@@ -0,0 +1,92 @@
+//! Saves benchmark results as baselines and compares new results against them.
+//!
+//! Baselines are stored in a directory with one file per git revision. Comparing
+//! prints a report of every benchmark and exits with an error if any regressed.
+
+use bench_harness::baseline::{self, Change};
+use bench_harness::read_records;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::process;
+
+#[derive(Parser, Debug)]
+#[command(about = "Saves benchmark baselines and reports regressions against them")]
+struct Args {
+    /// Directory the baselines are stored in
+    #[arg(long, default_value = "baselines", global = true)]
+    dir: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Save results as the baseline for the git revision they were measured at
+    Save {
+        /// Results written by bench-runner or the benchmarks, as JSON Lines or CSV
+        #[arg(long, default_value = "results.jsonl")]
+        input: String,
+    },
+    /// Compare results against a saved baseline
+    Compare {
+        /// Git revision of a saved baseline, or the path of a results file
+        #[arg(long)]
+        baseline: String,
+        /// Results written by bench-runner or the benchmarks, as JSON Lines or CSV
+        #[arg(long, default_value = "results.jsonl")]
+        input: String,
+        /// Relative change in mean time below which timings are never flagged
+        #[arg(long, default_value_t = 0.05)]
+        threshold: f64,
+    },
+}
+
+fn main() {
+    let args = Args::parse();
+
+    match args.command {
+        Command::Save { input } => {
+            let records = read(&input);
+            match baseline::save(&args.dir, &records) {
+                Ok(paths) => {
+                    for path in paths {
+                        eprintln!("saved {}", path.display());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("could not save to {}: {}", args.dir.display(), e);
+                    process::exit(1);
+                }
+            }
+        }
+        Command::Compare {
+            baseline,
+            input,
+            threshold,
+        } => {
+            let path = if Path::new(&baseline).is_file() {
+                PathBuf::from(&baseline)
+            } else {
+                baseline::path(&args.dir, &baseline)
+            };
+            let before = read(&path.to_string_lossy());
+            let after = read(&input);
+
+            let comparisons = baseline::compare(&before, &after, threshold);
+            print!("{}", baseline::report(&comparisons));
+            if comparisons.iter().any(|c| c.change.is_regression()) {
+                process::exit(1);
+            }
+            if comparisons.iter().all(|c| c.change == Change::Added) {
+                eprintln!("no benchmarks in common with {}", path.display());
+            }
+        }
+    }
+}
+
+fn read(path: &str) -> Vec<bench_harness::Record> {
+    read_records(path).unwrap_or_else(|e| {
+        eprintln!("could not read {}: {}", path, e);
+        process::exit(1);
+    })
+}
@@ -0,0 +1,260 @@
+//! Runs the benchmarks of every configured scheme and writes the results to one file.
+//!
+//! Rust schemes are run as `cargo test` benchmarks which print records directly.
+//! Other schemes are run as external programs and their output is parsed.
+
+use bench_harness::config::{Config, Kind, Scheme};
+use bench_harness::parse::{parse_records, Parser};
+use bench_harness::{
+    parse_message_lengths, parse_ring_sizes, write_records, Record, MESSAGE_LENGTHS_ENV,
+    OUTPUT_ENV, RING_SIZES_ENV, TRIALS_ENV,
+};
+use clap::Parser as ClapParser;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+#[derive(ClapParser, Debug)]
+#[command(about = "Benchmarks every configured anonymous group signature scheme")]
+struct Args {
+    /// Benchmark configuration file
+    #[arg(long, default_value = "benches.toml")]
+    config: PathBuf,
+    /// Only run these schemes, may be repeated
+    #[arg(long = "scheme")]
+    schemes: Vec<String>,
+    /// Ring sizes, overriding the configuration. A comma separated list of sizes and
+    /// sweeps, e.g. `16..65536*2` for powers of two or `100..1000+100` for a linear sweep
+    #[arg(long)]
+    ring_sizes: Option<String>,
+    /// Message lengths in bytes for the message length benchmarks, overriding the
+    /// configuration. Given like the ring sizes, up to 1048576
+    #[arg(long)]
+    message_lengths: Option<String>,
+    /// Number of timed trials, overriding the configuration
+    #[arg(long)]
+    trials: Option<usize>,
+    /// Number of warmup runs, overriding the configuration
+    #[arg(long)]
+    warmup: Option<usize>,
+    /// Results file, overriding the configuration. Written as CSV if it ends in `.csv`
+    #[arg(long)]
+    output: Option<String>,
+    /// List the configured schemes and exit
+    #[arg(long)]
+    list: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let mut config = Config::load(&args.config).unwrap_or_else(|e| {
+        eprintln!("{}: {}", args.config.display(), e);
+        process::exit(1);
+    });
+    if let Some(sizes) = args.ring_sizes {
+        config.ring_sizes = parse_ring_sizes(&sizes).unwrap_or_else(|| {
+            eprintln!("invalid ring sizes: {}", sizes);
+            process::exit(1);
+        });
+    }
+    if let Some(lengths) = args.message_lengths {
+        config.message_lengths = parse_message_lengths(&lengths).unwrap_or_else(|| {
+            eprintln!("invalid message lengths: {}", lengths);
+            process::exit(1);
+        });
+    }
+    if let Some(trials) = args.trials {
+        config.trials = trials;
+    }
+    if let Some(warmup) = args.warmup {
+        config.warmup = warmup;
+    }
+    if let Some(output) = args.output {
+        config.output = output;
+    }
+
+    if args.list {
+        for scheme in &config.schemes {
+            println!("{} ({})", scheme.name, scheme.dir);
+        }
+        return;
+    }
+
+    if let Some(unknown) = args
+        .schemes
+        .iter()
+        .find(|name| !config.schemes.iter().any(|s| &s.name == *name))
+    {
+        eprintln!("unknown scheme: {}", unknown);
+        process::exit(1);
+    }
+
+    // Scheme directories are relative to the configuration file
+    let root = args
+        .config
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+
+    let mut records = Vec::new();
+    let mut failed = Vec::new();
+    for scheme in &config.schemes {
+        if !args.schemes.is_empty() && !args.schemes.contains(&scheme.name) {
+            continue;
+        }
+        eprintln!("==> {}", scheme.name);
+        match run_scheme(&config, root, scheme) {
+            Ok(scheme_records) => {
+                let scheme_records = match_ring_sizes(&config.ring_sizes, scheme_records);
+                eprintln!("{}: {} records", scheme.name, scheme_records.len());
+                records.extend(scheme_records);
+            }
+            Err(e) => {
+                eprintln!("{}: {}", scheme.name, e);
+                failed.push(scheme.name.clone());
+            }
+        }
+    }
+
+    if let Err(e) = write_records(&config.output, &records) {
+        eprintln!("could not write {}: {}", config.output, e);
+        process::exit(1);
+    }
+    eprintln!("wrote {} records to {}", records.len(), config.output);
+
+    if !failed.is_empty() {
+        eprintln!("failed schemes: {}", failed.join(", "));
+        process::exit(1);
+    }
+}
+
+// Runs the warmups and then the timed run of each of the scheme's benchmarks
+fn run_scheme(config: &Config, root: &Path, scheme: &Scheme) -> Result<Vec<Record>, String> {
+    let dir = root.join(&scheme.dir);
+
+    match &scheme.kind {
+        Kind::Cargo { tests } => {
+            let mut records = Vec::new();
+            for test in tests {
+                let mut args = vec!["test", "--release", test.as_str()];
+                args.extend(["--", "--nocapture", "--test-threads=1"]);
+                let output = run_with_warmup(config, &dir, "cargo", &args)?;
+                records.extend(parse_records(&output));
+            }
+            Ok(records)
+        }
+        Kind::Command {
+            command,
+            parser,
+            ring_size_args,
+        } => {
+            let (program, rest) = command.split_first().ok_or("empty command")?;
+            let mut args: Vec<String> = rest.to_vec();
+            if *ring_size_args {
+                args.extend(config.ring_sizes.iter().map(|n| n.to_string()));
+            }
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let output = run_with_warmup(config, &dir, program, &args)?;
+
+            // The configuration was validated when it was loaded
+            let parser = Parser::from_name(parser).expect("known parser");
+            Ok(parser.parse(&scheme.name, &output, &config.ring_sizes))
+        }
+    }
+}
+
+// Every scheme must be measured at exactly the configured ring sizes, so records at other
+// sizes are dropped, and sizes a scheme could not be measured at are reported.
+// Message length sweeps are only run at one ring size, so are not reported
+fn match_ring_sizes(ring_sizes: &[usize], records: Vec<Record>) -> Vec<Record> {
+    let (records, dropped): (Vec<Record>, Vec<Record>) = records
+        .into_iter()
+        .partition(|r| ring_sizes.contains(&r.ring_size));
+    for record in dropped {
+        eprintln!(
+            "{} {}: ignoring unconfigured ring size {}",
+            record.scheme, record.operation, record.ring_size
+        );
+    }
+
+    let mut measured: BTreeMap<(&str, &str), Vec<usize>> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.message_bytes.is_none()) {
+        measured
+            .entry((&record.scheme, &record.operation))
+            .or_default()
+            .push(record.ring_size);
+    }
+    for ((scheme, operation), sizes) in measured {
+        let missing: Vec<String> = ring_sizes
+            .iter()
+            .filter(|n| !sizes.contains(n))
+            .map(|n| n.to_string())
+            .collect();
+        if !missing.is_empty() {
+            eprintln!(
+                "{} {}: not measured at ring sizes {}",
+                scheme,
+                operation,
+                missing.join(", ")
+            );
+        }
+    }
+    records
+}
+
+// Runs the command `config.warmup` times, discarding its output, and then once more,
+// returning its stdout
+fn run_with_warmup(
+    config: &Config,
+    dir: &Path,
+    program: &str,
+    args: &[&str],
+) -> Result<String, String> {
+    let sizes: Vec<String> = config.ring_sizes.iter().map(|n| n.to_string()).collect();
+    let lengths: Vec<String> = config
+        .message_lengths
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+
+    for i in 0..=config.warmup {
+        let is_warmup = i < config.warmup;
+        if is_warmup {
+            eprintln!(
+                "warmup {}/{}: {} {}",
+                i + 1,
+                config.warmup,
+                program,
+                args.join(" ")
+            );
+        } else {
+            eprintln!("timed run: {} {}", program, args.join(" "));
+        }
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(dir)
+            .env(RING_SIZES_ENV, sizes.join(","))
+            .env(TRIALS_ENV, config.trials.to_string())
+            // The runner writes the consolidated results itself
+            .env_remove(OUTPUT_ENV)
+            .stderr(Stdio::inherit());
+        if !lengths.is_empty() {
+            command.env(MESSAGE_LENGTHS_ENV, lengths.join(","));
+        }
+
+        let output = command
+            .output()
+            .map_err(|e| format!("could not run {} in {}: {}", program, dir.display(), e))?;
+
+        if !output.status.success() {
+            return Err(format!("{} exited with {}", program, output.status));
+        }
+        if !is_warmup {
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+    }
+    unreachable!("the timed run always returns")
+}
@@ -0,0 +1,226 @@
+//! Plots benchmark results against ring size for every scheme.
+//!
+//! One chart is drawn per operation, on log-log axes, with error bars of one
+//! standard deviation. Each chart is written as both PNG and SVG.
+//! Records from message length sweeps are plotted against message length instead,
+//! with and without pre-hashing, in `<operation>_by_message.png`.
+
+use bench_harness::{read_records, Record};
+use clap::Parser;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+#[derive(Parser, Debug)]
+#[command(about = "Plots benchmark results against ring size")]
+struct Args {
+    /// Results written by bench-runner or the benchmarks, as JSON Lines or CSV
+    #[arg(long, default_value = "results.jsonl")]
+    input: String,
+    /// Directory the plots are written to
+    #[arg(long, default_value = "plots")]
+    out_dir: PathBuf,
+    /// Only plot these schemes, may be repeated
+    #[arg(long = "scheme")]
+    schemes: Vec<String>,
+}
+
+// The operations which are plotted, with the label of their y axis
+const OPERATIONS: [(&str, &str, &str); 5] = [
+    ("sign", "Signing time", "Time (ms)"),
+    ("verify", "Verification time", "Time (ms)"),
+    ("trace", "Trace time", "Time (ms)"),
+    ("size", "Proof size", "Size (bytes)"),
+    ("size_with_tag", "Proof size including the ring", "Size (bytes)"),
+];
+
+// The operations of the message length sweeps, each also measured with pre-hashing
+const MESSAGE_OPERATIONS: [(&str, &str); 2] = [
+    ("sign", "Signing time by message length"),
+    ("verify", "Verification time by message length"),
+];
+
+const SIZE: (u32, u32) = (800, 600);
+
+// A point on a chart, with the half height of its error bar
+struct Point {
+    x: f64,
+    y: f64,
+    err: f64,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let records = read_records(&args.input).unwrap_or_else(|e| {
+        eprintln!("could not read {}: {}", args.input, e);
+        process::exit(1);
+    });
+    if let Err(e) = fs::create_dir_all(&args.out_dir) {
+        eprintln!("could not create {}: {}", args.out_dir.display(), e);
+        process::exit(1);
+    }
+
+    let mut charts = Vec::new();
+    for (operation, title, y_desc) in OPERATIONS {
+        let series = series(&records, &args.schemes, |r| {
+            (r.operation == operation && r.message_bytes.is_none())
+                .then(|| (r.scheme.clone(), r.ring_size))
+        });
+        charts.push((operation.to_string(), title, "Ring size", y_desc, series));
+    }
+    for (operation, title) in MESSAGE_OPERATIONS {
+        let prehashed = format!("{}_prehashed", operation);
+        let series = series(&records, &args.schemes, |r| {
+            let label = if r.operation == operation {
+                r.scheme.clone()
+            } else if r.operation == prehashed {
+                format!("{} (prehashed)", r.scheme)
+            } else {
+                return None;
+            };
+            r.message_bytes.map(|bytes| (label, bytes))
+        });
+        let name = format!("{}_by_message", operation);
+        charts.push((name, title, "Message length (bytes)", "Time (ms)", series));
+    }
+
+    for (name, title, x_desc, y_desc, series) in charts {
+        if series.is_empty() {
+            continue;
+        }
+
+        for extension in ["png", "svg"] {
+            let path = args.out_dir.join(format!("{}.{}", name, extension));
+            let result = if extension == "png" {
+                draw(
+                    BitMapBackend::new(&path, SIZE).into_drawing_area(),
+                    title,
+                    (x_desc, y_desc),
+                    &series,
+                )
+            } else {
+                draw(
+                    SVGBackend::new(&path, SIZE).into_drawing_area(),
+                    title,
+                    (x_desc, y_desc),
+                    &series,
+                )
+            };
+            match result {
+                Ok(()) => eprintln!("wrote {}", path.display()),
+                Err(e) => {
+                    eprintln!("could not draw {}: {}", path.display(), e);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+// Groups the records selected by `select`, which returns the label of their series and
+// their x value, into series sorted by x.
+// If a point was measured more than once, the last record is used
+fn series<F>(records: &[Record], schemes: &[String], select: F) -> BTreeMap<String, Vec<Point>>
+where
+    F: Fn(&Record) -> Option<(String, usize)>,
+{
+    let mut latest: BTreeMap<(String, usize), &Record> = BTreeMap::new();
+    for record in records {
+        if !schemes.is_empty() && !schemes.contains(&record.scheme) {
+            continue;
+        }
+        if let Some(point) = select(record) {
+            latest.insert(point, record);
+        }
+    }
+
+    let mut series: BTreeMap<String, Vec<Point>> = BTreeMap::new();
+    for ((label, x), record) in latest {
+        let (y, err) = match (record.mean_ms, record.bytes) {
+            (Some(mean), _) => (mean, record.stddev_ms.unwrap_or(0.0)),
+            (None, Some(bytes)) => (bytes as f64, 0.0),
+            (None, None) => continue,
+        };
+        // Log axes cannot show zero
+        if x == 0 || y <= 0.0 {
+            continue;
+        }
+        series.entry(label).or_default().push(Point {
+            x: x as f64,
+            y,
+            err,
+        });
+    }
+    series
+}
+
+fn draw<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    title: &str,
+    (x_desc, y_desc): (&str, &str),
+    series: &BTreeMap<String, Vec<Point>>,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+
+    let points = || series.values().flatten();
+    let x_min = points().map(|p| p.x).fold(f64::INFINITY, f64::min);
+    let x_max = points().map(|p| p.x).fold(0.0, f64::max);
+    let y_min = points().map(|p| p.y).fold(f64::INFINITY, f64::min);
+    let y_max = points().map(|p| p.y + p.err).fold(0.0, f64::max);
+    let (y_low, y_high) = (y_min / 2.0, y_max * 2.0);
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", 28))
+        .margin(20)
+        .x_label_area_size(50)
+        .y_label_area_size(70)
+        .build_cartesian_2d(
+            (x_min / 1.5..x_max * 1.5).log_scale(),
+            (y_low..y_high).log_scale(),
+        )?;
+
+    chart
+        .configure_mesh()
+        .x_desc(x_desc)
+        .y_desc(y_desc)
+        .x_label_formatter(&|x| format!("{:.0}", x))
+        .draw()?;
+
+    for (i, (scheme, points)) in series.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+
+        chart
+            .draw_series(LineSeries::new(
+                points.iter().map(|p| (p.x, p.y)),
+                color.stroke_width(2),
+            ))?
+            .label(scheme)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        chart.draw_series(
+            points
+                .iter()
+                .map(|p| Circle::new((p.x, p.y), 3, color.filled())),
+        )?;
+        chart.draw_series(points.iter().filter(|p| p.err > 0.0).map(|p| {
+            ErrorBar::new_vertical(p.x, (p.y - p.err).max(y_low), p.y, p.y + p.err, color, 8)
+        }))?;
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    root.present()?;
+    Ok(())
+}
@@ -0,0 +1,237 @@
+//! The benchmark configuration read by `bench-runner`.
+//!
+//! The configuration lists every scheme in the repo and how to benchmark it,
+//! along with the ring sizes, trials and warmups shared by all of them.
+
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::Path;
+
+/// The top level of a benchmark configuration file
+#[derive(Clone, Debug, Deserialize)]
+pub struct Config {
+    /// Ring sizes every scheme is benchmarked at, given either as a list or as a sweep
+    /// such as `"16..65536*2"`, see [`crate::parse_ring_sizes`]
+    #[serde(deserialize_with = "ring_sizes")]
+    pub ring_sizes: Vec<usize>,
+    /// Message lengths in bytes swept by the message length benchmarks, given like the ring
+    /// sizes. If empty, each benchmark uses its own defaults
+    #[serde(default, deserialize_with = "message_lengths")]
+    pub message_lengths: Vec<usize>,
+    /// Number of timed trials per ring size
+    #[serde(default = "default_trials")]
+    pub trials: usize,
+    /// Number of times each benchmark is run, with its results discarded, before the timed run
+    #[serde(default)]
+    pub warmup: usize,
+    /// File the consolidated results are written to
+    #[serde(default = "default_output")]
+    pub output: String,
+    #[serde(rename = "scheme")]
+    pub schemes: Vec<Scheme>,
+}
+
+fn ring_sizes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<usize>, D::Error> {
+    sizes(
+        deserializer,
+        crate::check_ring_sizes,
+        crate::parse_ring_sizes,
+        crate::MAX_RING_SIZE,
+    )
+}
+
+fn message_lengths<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<usize>, D::Error> {
+    sizes(
+        deserializer,
+        crate::check_message_lengths,
+        crate::parse_message_lengths,
+        crate::MAX_MESSAGE_LENGTH,
+    )
+}
+
+// Deserializes either a list of sizes or a sweep. An empty list is returned as it is
+fn sizes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    check: fn(Vec<usize>) -> Option<Vec<usize>>,
+    parse: fn(&str) -> Option<Vec<usize>>,
+    max: usize,
+) -> Result<Vec<usize>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Sizes {
+        List(Vec<usize>),
+        Sweep(String),
+    }
+
+    let sizes = match Sizes::deserialize(deserializer)? {
+        Sizes::List(sizes) if sizes.is_empty() => return Ok(sizes),
+        Sizes::List(sizes) => check(sizes),
+        Sizes::Sweep(sweep) => parse(&sweep),
+    };
+    sizes.ok_or_else(|| {
+        serde::de::Error::custom(format!("invalid sizes, each must be between 1 and {}", max))
+    })
+}
+
+fn default_trials() -> usize {
+    10
+}
+
+fn default_output() -> String {
+    "results.jsonl".to_string()
+}
+
+/// A scheme and how to benchmark it
+#[derive(Clone, Debug, Deserialize)]
+pub struct Scheme {
+    pub name: String,
+    /// Directory the benchmark is run from, relative to the configuration file
+    pub dir: String,
+    #[serde(flatten)]
+    pub kind: Kind,
+}
+
+/// How a scheme is benchmarked
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Kind {
+    /// Rust benchmarks which are tests emitting records, run with `cargo test`.
+    /// Each entry in `tests` is a test name filter
+    Cargo { tests: Vec<String> },
+    /// An external program whose output is parsed into records
+    Command {
+        command: Vec<String>,
+        /// The parser for the program's output, see [`crate::parse::Parser`]
+        parser: String,
+        /// Pass the ring sizes to the program as extra arguments, as well as in
+        /// `BENCH_RING_SIZES`
+        #[serde(default)]
+        ring_size_args: bool,
+    },
+}
+
+/// Errors from loading a configuration
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Parse(String),
+    UnknownParser(String),
+    EmptyRingSizes,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "could not read config: {}", e),
+            Error::Parse(e) => write!(f, "could not parse config: {}", e),
+            Error::UnknownParser(name) => write!(f, "unknown output parser: {}", name),
+            Error::EmptyRingSizes => write!(f, "no ring sizes configured"),
+        }
+    }
+}
+
+impl Config {
+    /// Reads a configuration from a TOML file
+    pub fn load(path: &Path) -> Result<Config, Error> {
+        let contents = fs::read_to_string(path).map_err(Error::Io)?;
+        Config::from_toml(&contents)
+    }
+
+    /// Parses and validates a configuration
+    pub fn from_toml(contents: &str) -> Result<Config, Error> {
+        let config: Config = toml::from_str(contents).map_err(|e| Error::Parse(e.to_string()))?;
+
+        if config.ring_sizes.is_empty() {
+            return Err(Error::EmptyRingSizes);
+        }
+        for scheme in &config.schemes {
+            if let Kind::Command { parser, .. } = &scheme.kind {
+                if crate::parse::Parser::from_name(parser).is_none() {
+                    return Err(Error::UnknownParser(parser.clone()));
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        ring_sizes = [16, 32]
+        warmup = 2
+
+        [[scheme]]
+        name = "trs"
+        dir = "TRS"
+        kind = "cargo"
+        tests = ["proof_time_bench"]
+
+        [[scheme]]
+        name = "raptor"
+        dir = "Raptor"
+        kind = "command"
+        command = ["./bench.sh"]
+        parser = "raptor"
+        ring_size_args = true
+    "#;
+
+    #[test]
+    fn parse_config() {
+        let config = Config::from_toml(CONFIG).unwrap();
+        assert_eq!(config.ring_sizes, vec![16, 32]);
+        assert_eq!(config.trials, 10);
+        assert_eq!(config.warmup, 2);
+        assert_eq!(config.output, "results.jsonl");
+        assert!(config.message_lengths.is_empty());
+        assert_eq!(config.schemes.len(), 2);
+
+        match &config.schemes[1].kind {
+            Kind::Command {
+                parser,
+                ring_size_args,
+                ..
+            } => {
+                assert_eq!(parser, "raptor");
+                assert!(ring_size_args);
+            }
+            _ => panic!("expected a command scheme"),
+        }
+    }
+
+    #[test]
+    fn ring_size_sweep() {
+        let config = CONFIG.replace("[16, 32]", "\"16..65536*2\"");
+        let config = Config::from_toml(&config).unwrap();
+        assert_eq!(config.ring_sizes.len(), 13);
+        assert_eq!(config.ring_sizes[12], 65536);
+
+        let config = CONFIG.replace("[16, 32]", "[32, 16, 32]");
+        assert_eq!(Config::from_toml(&config).unwrap().ring_sizes, vec![16, 32]);
+
+        for invalid in ["\"16..1024*1\"", "[0, 16]", "[131072]"] {
+            let config = CONFIG.replace("[16, 32]", invalid);
+            assert!(matches!(Config::from_toml(&config), Err(Error::Parse(_))));
+        }
+        let config = CONFIG.replace("warmup = 2", "message_lengths = \"32..1048576*32\"");
+        let config = Config::from_toml(&config).unwrap();
+        assert_eq!(config.message_lengths, crate::DEFAULT_MESSAGE_LENGTHS);
+
+        let config = CONFIG.replace("[16, 32]", "[]");
+        assert!(matches!(
+            Config::from_toml(&config),
+            Err(Error::EmptyRingSizes)
+        ));
+    }
+
+    #[test]
+    fn reject_unknown_parser() {
+        let config = CONFIG.replace("parser = \"raptor\"", "parser = \"nope\"");
+        assert!(matches!(
+            Config::from_toml(&config),
+            Err(Error::UnknownParser(_))
+        ));
+    }
+}
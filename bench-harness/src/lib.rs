@@ -0,0 +1,630 @@
+//! Common benchmark output for every scheme in this repo.
+//!
+//! Each measurement is a [`Record`], printed to stdout as one JSON object per line
+//! so that results from different schemes can be compared and plotted directly.
+//! Set `BENCH_OUTPUT` to a file path to also append the records to that file,
+//! as CSV if the path ends in `.csv` and as JSON Lines otherwise.
+//!
+//! The ring sizes and number of trials can be overridden with `BENCH_RING_SIZES`
+//! and `BENCH_TRIALS`, which is how the `bench-runner` binary configures each scheme.
+
+pub mod alloc;
+pub mod baseline;
+pub mod config;
+pub mod parse;
+pub mod timing;
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Environment variable naming a file that records are appended to
+pub const OUTPUT_ENV: &str = "BENCH_OUTPUT";
+
+/// Environment variable holding the ring sizes to benchmark, see [`parse_ring_sizes`]
+pub const RING_SIZES_ENV: &str = "BENCH_RING_SIZES";
+
+/// Ring sizes benchmarked when none are configured
+pub const DEFAULT_RING_SIZES: [usize; 7] = [16, 32, 64, 128, 256, 512, 1024];
+
+/// The largest ring size a sweep may include
+pub const MAX_RING_SIZE: usize = 1 << 16;
+
+/// Environment variable holding the message lengths to benchmark, see [`parse_message_lengths`]
+pub const MESSAGE_LENGTHS_ENV: &str = "BENCH_MESSAGE_LENGTHS";
+
+/// Message lengths in bytes benchmarked when none are configured
+pub const DEFAULT_MESSAGE_LENGTHS: [usize; 4] = [32, 1 << 10, 1 << 15, 1 << 20];
+
+/// The longest message a sweep may include, 1 MiB
+pub const MAX_MESSAGE_LENGTH: usize = 1 << 20;
+
+/// Environment variable holding the number of timed trials per ring size
+pub const TRIALS_ENV: &str = "BENCH_TRIALS";
+
+/// Column order used when writing records as CSV
+pub const CSV_HEADER: &str =
+    "scheme,operation,ring_size,message_bytes,trials,mean_ms,stddev_ms,median_ms,ci95_ms,outliers,bytes,peak_bytes,allocations,git_rev,cpu";
+
+/// One benchmark measurement.
+///
+/// Timing records have `mean_ms`, `stddev_ms` and `median_ms` set, and records from a
+/// [`timing::Measurement`] also have the 95% confidence interval and number of outliers.
+/// Size records have `bytes` set and zero trials.
+/// Timing records of the Rust benchmarks also have the heap usage of one run when
+/// [`alloc::CountingAlloc`] is installed. Records of message length sweeps have
+/// `message_bytes` set, see [`Record::with_message_bytes`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub scheme: String,
+    pub operation: String,
+    pub ring_size: usize,
+    /// Length of the signed message, for benchmarks which sweep it
+    pub message_bytes: Option<usize>,
+    pub trials: usize,
+    pub mean_ms: Option<f64>,
+    pub stddev_ms: Option<f64>,
+    pub median_ms: Option<f64>,
+    /// Half width of the 95% confidence interval of the mean
+    pub ci95_ms: Option<f64>,
+    /// Number of samples rejected as outliers
+    pub outliers: Option<usize>,
+    pub bytes: Option<usize>,
+    /// Most heap bytes in use at once during one run
+    pub peak_bytes: Option<usize>,
+    /// Number of heap allocations in one run
+    pub allocations: Option<usize>,
+    pub git_rev: String,
+    pub cpu: String,
+}
+
+impl Record {
+    /// Builds a timing record from per-trial samples in milliseconds
+    pub fn timing(scheme: &str, operation: &str, ring_size: usize, samples_ms: &[f64]) -> Record {
+        Record {
+            scheme: scheme.to_string(),
+            operation: operation.to_string(),
+            ring_size,
+            message_bytes: None,
+            trials: samples_ms.len(),
+            mean_ms: Some(mean(samples_ms)),
+            stddev_ms: Some(std_dev(samples_ms)),
+            median_ms: Some(median(samples_ms)),
+            ci95_ms: None,
+            outliers: None,
+            bytes: None,
+            peak_bytes: None,
+            allocations: None,
+            git_rev: git_rev().to_string(),
+            cpu: cpu_info().to_string(),
+        }
+    }
+
+    /// Builds a timing record from a measurement
+    pub fn measured(
+        scheme: &str,
+        operation: &str,
+        ring_size: usize,
+        measurement: &timing::Measurement,
+    ) -> Record {
+        const NS_PER_MS: f64 = 1e6;
+        Record {
+            scheme: scheme.to_string(),
+            operation: operation.to_string(),
+            ring_size,
+            message_bytes: None,
+            trials: measurement.samples_ns.len() + measurement.outliers,
+            mean_ms: Some(measurement.mean_ns() / NS_PER_MS),
+            stddev_ms: Some(measurement.std_dev_ns() / NS_PER_MS),
+            median_ms: Some(measurement.median_ns() / NS_PER_MS),
+            ci95_ms: Some(measurement.ci95_ns() / NS_PER_MS),
+            outliers: Some(measurement.outliers),
+            bytes: None,
+            peak_bytes: measurement.alloc.map(|a| a.peak_bytes),
+            allocations: measurement.alloc.map(|a| a.allocations),
+            git_rev: git_rev().to_string(),
+            cpu: cpu_info().to_string(),
+        }
+    }
+
+    /// Builds a timing record from a mean and standard deviation that were
+    /// computed elsewhere, e.g. by an external benchmark program
+    pub fn summary(
+        scheme: &str,
+        operation: &str,
+        ring_size: usize,
+        trials: usize,
+        mean_ms: f64,
+        stddev_ms: f64,
+    ) -> Record {
+        Record {
+            scheme: scheme.to_string(),
+            operation: operation.to_string(),
+            ring_size,
+            message_bytes: None,
+            trials,
+            mean_ms: Some(mean_ms),
+            stddev_ms: Some(stddev_ms),
+            median_ms: None,
+            ci95_ms: None,
+            outliers: None,
+            bytes: None,
+            peak_bytes: None,
+            allocations: None,
+            git_rev: git_rev().to_string(),
+            cpu: cpu_info().to_string(),
+        }
+    }
+
+    /// Builds a size record, e.g. for the size of a signature
+    pub fn size(scheme: &str, operation: &str, ring_size: usize, bytes: usize) -> Record {
+        Record {
+            scheme: scheme.to_string(),
+            operation: operation.to_string(),
+            ring_size,
+            message_bytes: None,
+            trials: 0,
+            mean_ms: None,
+            stddev_ms: None,
+            median_ms: None,
+            ci95_ms: None,
+            outliers: None,
+            bytes: Some(bytes),
+            peak_bytes: None,
+            allocations: None,
+            git_rev: git_rev().to_string(),
+            cpu: cpu_info().to_string(),
+        }
+    }
+
+    /// Sets the length of the message the operation was measured with
+    pub fn with_message_bytes(mut self, message_bytes: usize) -> Record {
+        self.message_bytes = Some(message_bytes);
+        self
+    }
+
+    /// Serializes the record as a single JSON line
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("records always serialize")
+    }
+
+    /// Parses a record from a single JSON line
+    pub fn from_json(line: &str) -> Option<Record> {
+        serde_json::from_str(line).ok()
+    }
+
+    /// Serializes the record as a CSV row in the order of [`CSV_HEADER`]
+    pub fn to_csv(&self) -> String {
+        fn opt<T: ToString>(v: &Option<T>) -> String {
+            v.as_ref().map(|v| v.to_string()).unwrap_or_default()
+        }
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&self.scheme),
+            csv_field(&self.operation),
+            self.ring_size,
+            opt(&self.message_bytes),
+            self.trials,
+            opt(&self.mean_ms),
+            opt(&self.stddev_ms),
+            opt(&self.median_ms),
+            opt(&self.ci95_ms),
+            opt(&self.outliers),
+            opt(&self.bytes),
+            opt(&self.peak_bytes),
+            opt(&self.allocations),
+            csv_field(&self.git_rev),
+            csv_field(&self.cpu),
+        )
+    }
+}
+
+// Builds a record from CSV fields, named by the columns of the header
+fn record_from_csv(header: &[String], fields: &[String]) -> Option<Record> {
+    let get = |name: &str| {
+        header
+            .iter()
+            .position(|column| column == name)
+            .and_then(|i| fields.get(i))
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    };
+    Some(Record {
+        scheme: get("scheme")?.to_string(),
+        operation: get("operation")?.to_string(),
+        ring_size: get("ring_size")?.parse().ok()?,
+        message_bytes: get("message_bytes").and_then(|v| v.parse().ok()),
+        trials: get("trials").and_then(|v| v.parse().ok()).unwrap_or(0),
+        mean_ms: get("mean_ms").and_then(|v| v.parse().ok()),
+        stddev_ms: get("stddev_ms").and_then(|v| v.parse().ok()),
+        median_ms: get("median_ms").and_then(|v| v.parse().ok()),
+        ci95_ms: get("ci95_ms").and_then(|v| v.parse().ok()),
+        outliers: get("outliers").and_then(|v| v.parse().ok()),
+        bytes: get("bytes").and_then(|v| v.parse().ok()),
+        peak_bytes: get("peak_bytes").and_then(|v| v.parse().ok()),
+        allocations: get("allocations").and_then(|v| v.parse().ok()),
+        git_rev: get("git_rev").unwrap_or("unknown").to_string(),
+        cpu: get("cpu").unwrap_or("unknown").to_string(),
+    })
+}
+
+// Splits a CSV row into fields, handling quoted fields
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// Quotes a CSV field if it contains a separator or a quote
+fn csv_field(s: &str) -> String {
+    if s.contains(',') || s.contains('"') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Prints the record to stdout as JSON, and appends it to `BENCH_OUTPUT` if set
+pub fn emit(record: &Record) {
+    println!("{}", record.to_json());
+
+    if let Ok(path) = env::var(OUTPUT_ENV) {
+        if let Err(e) = append(&path, record) {
+            eprintln!("could not write benchmark record to {}: {}", path, e);
+        }
+    }
+}
+
+/// Appends the record to a file, as CSV if the path ends in `.csv`, otherwise as JSON Lines
+pub fn append(path: &str, record: &Record) -> io::Result<()> {
+    let is_csv = path.ends_with(".csv");
+    let is_new = fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true);
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if is_csv {
+        if is_new {
+            writeln!(file, "{}", CSV_HEADER)?;
+        }
+        writeln!(file, "{}", record.to_csv())
+    } else {
+        writeln!(file, "{}", record.to_json())
+    }
+}
+
+/// Writes the records to a new file, as CSV if the path ends in `.csv`, otherwise as JSON Lines
+pub fn write_records(path: &str, records: &[Record]) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    if path.ends_with(".csv") {
+        writeln!(file, "{}", CSV_HEADER)?;
+        for record in records {
+            writeln!(file, "{}", record.to_csv())?;
+        }
+    } else {
+        for record in records {
+            writeln!(file, "{}", record.to_json())?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads records from a file written by [`write_records`], [`append`] or [`emit`].
+/// Lines which are not records are skipped
+pub fn read_records(path: &str) -> io::Result<Vec<Record>> {
+    let contents = fs::read_to_string(path)?;
+
+    if path.ends_with(".csv") {
+        let mut lines = contents.lines();
+        let header = split_csv(lines.next().unwrap_or_default());
+        Ok(lines
+            .filter_map(|line| record_from_csv(&header, &split_csv(line)))
+            .collect())
+    } else {
+        Ok(parse::parse_records(&contents))
+    }
+}
+
+/// Returns the ring sizes to benchmark, read from `BENCH_RING_SIZES` if it is set
+pub fn ring_sizes(default: &[usize]) -> Vec<usize> {
+    env::var(RING_SIZES_ENV)
+        .ok()
+        .and_then(|sizes| parse_ring_sizes(&sizes))
+        .unwrap_or_else(|| default.to_vec())
+}
+
+/// Parses a comma separated sweep of ring sizes, returning them sorted without duplicates.
+///
+/// Each entry is one of
+/// - a single size, e.g. `100`
+/// - an inclusive range, e.g. `16..64` for every size from 16 to 64
+/// - a range with a linear step, e.g. `100..1000+100`
+/// - a range with a geometric step, e.g. `16..65536*2` for the powers of two
+///
+/// Sizes must be between 1 and [`MAX_RING_SIZE`].
+pub fn parse_ring_sizes(sizes: &str) -> Option<Vec<usize>> {
+    parse_sizes(sizes, MAX_RING_SIZE)
+}
+
+/// Sorts ring sizes and removes duplicates, or returns `None` if there are none
+/// or any is out of range
+pub fn check_ring_sizes(sizes: Vec<usize>) -> Option<Vec<usize>> {
+    check_sizes(sizes, MAX_RING_SIZE)
+}
+
+/// Returns the message lengths to benchmark, read from `BENCH_MESSAGE_LENGTHS` if it is set
+pub fn message_lengths(default: &[usize]) -> Vec<usize> {
+    env::var(MESSAGE_LENGTHS_ENV)
+        .ok()
+        .and_then(|lengths| parse_message_lengths(&lengths))
+        .unwrap_or_else(|| default.to_vec())
+}
+
+/// Parses a sweep of message lengths in bytes, in the same format as [`parse_ring_sizes`].
+/// Lengths must be between 1 and [`MAX_MESSAGE_LENGTH`]
+pub fn parse_message_lengths(lengths: &str) -> Option<Vec<usize>> {
+    parse_sizes(lengths, MAX_MESSAGE_LENGTH)
+}
+
+/// Sorts message lengths and removes duplicates, or returns `None` if there are none
+/// or any is out of range
+pub fn check_message_lengths(lengths: Vec<usize>) -> Option<Vec<usize>> {
+    check_sizes(lengths, MAX_MESSAGE_LENGTH)
+}
+
+fn parse_sizes(sizes: &str, max: usize) -> Option<Vec<usize>> {
+    let mut parsed = Vec::new();
+    for entry in sizes.split(',') {
+        parsed.extend(parse_sweep(entry.trim(), max)?);
+    }
+    check_sizes(parsed, max)
+}
+
+fn check_sizes(mut sizes: Vec<usize>, max: usize) -> Option<Vec<usize>> {
+    if sizes.is_empty() || sizes.iter().any(|&n| n == 0 || n > max) {
+        return None;
+    }
+    sizes.sort_unstable();
+    sizes.dedup();
+    Some(sizes)
+}
+
+// Parses one entry of a sweep
+fn parse_sweep(entry: &str, max: usize) -> Option<Vec<usize>> {
+    let (start, rest) = match entry.split_once("..") {
+        Some(range) => range,
+        None => return Some(vec![entry.parse().ok()?]),
+    };
+    let start: usize = start.trim().parse().ok()?;
+
+    let (end, step) = if let Some((end, step)) = rest.split_once('+') {
+        (end, Step::Add(step.trim().parse().ok()?))
+    } else if let Some((end, factor)) = rest.split_once('*') {
+        (end, Step::Mul(factor.trim().parse().ok()?))
+    } else {
+        (rest, Step::Add(1))
+    };
+    let end: usize = end.trim().parse().ok()?;
+
+    // Reject sweeps which would never finish, or would be too long to be useful
+    match step {
+        Step::Add(0) | Step::Mul(0..=1) => return None,
+        _ if start == 0 || start > end || end > max => return None,
+        _ => {}
+    }
+
+    // A step which overflows is past the end, so it ends the sweep
+    let mut sizes = Vec::new();
+    let mut n = Some(start);
+    while let Some(size) = n.filter(|&size| size <= end) {
+        sizes.push(size);
+        n = match step {
+            Step::Add(step) => size.checked_add(step),
+            Step::Mul(factor) => size.checked_mul(factor),
+        };
+    }
+    Some(sizes)
+}
+
+enum Step {
+    Add(usize),
+    Mul(usize),
+}
+
+/// Returns the number of timed trials, read from `BENCH_TRIALS` if it is set
+pub fn trials(default: usize) -> usize {
+    env::var(TRIALS_ENV)
+        .ok()
+        .and_then(|trials| trials.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// Returns the arithmetic mean of the samples
+pub fn mean(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+/// Returns the sample standard deviation
+pub fn std_dev(samples: &[f64]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(samples);
+    let variance =
+        samples.iter().map(|t| (t - m).powi(2)).sum::<f64>() / (samples.len() - 1) as f64;
+    variance.sqrt()
+}
+
+/// Returns the median of the samples
+pub fn median(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).expect("samples are never NaN"));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Returns the short git revision of the working tree, or "unknown"
+pub fn git_rev() -> &'static str {
+    static REV: OnceLock<String> = OnceLock::new();
+    REV.get_or_init(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .and_then(|out| String::from_utf8(out.stdout).ok())
+            .map(|rev| rev.trim().to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    })
+}
+
+/// Returns the CPU model name, falling back to the architecture
+pub fn cpu_info() -> &'static str {
+    static CPU: OnceLock<String> = OnceLock::new();
+    CPU.get_or_init(|| {
+        fs::read_to_string("/proc/cpuinfo")
+            .ok()
+            .and_then(|info| {
+                info.lines()
+                    .find(|line| line.starts_with("model name"))
+                    .and_then(|line| line.split(':').nth(1))
+                    .map(|name| name.trim().to_string())
+            })
+            .unwrap_or_else(|| env::consts::ARCH.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let samples = [4.0, 1.0, 3.0, 2.0];
+        assert_eq!(mean(&samples), 2.5);
+        assert_eq!(median(&samples), 2.5);
+        assert_eq!(median(&samples[..3]), 3.0);
+        assert!((std_dev(&samples) - 1.2909944).abs() < 1e-6);
+        assert_eq!(std_dev(&samples[..1]), 0.0);
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let record = Record::timing("trs", "sign", 16, &[1.0, 2.0, 3.0]);
+        let parsed = Record::from_json(&record.to_json()).unwrap();
+        assert_eq!(record, parsed);
+
+        let record = Record::size("trs", "size", 16, 1056);
+        let parsed = Record::from_json(&record.to_json()).unwrap();
+        assert_eq!(parsed.bytes, Some(1056));
+        assert_eq!(parsed.mean_ms, None);
+    }
+
+    #[test]
+    fn read_written_records() {
+        let mut records = vec![
+            Record::timing("trs", "sign", 16, &[1.0, 2.5]),
+            Record::size("clsag", "size", 8, 400),
+            Record::timing("trs", "sign", 16, &[4.0]).with_message_bytes(1024),
+        ];
+        records[1].cpu = "Some \"CPU\", 4 cores".to_string();
+
+        let dir = env::temp_dir();
+        for name in ["records.csv", "records.jsonl"] {
+            let path = dir.join(format!("bench-harness-{}-{}", std::process::id(), name));
+            let path = path.to_str().unwrap();
+            write_records(path, &records).unwrap();
+            assert_eq!(read_records(path).unwrap(), records);
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn ring_sizes_list() {
+        assert_eq!(parse_ring_sizes("16, 32,64"), Some(vec![16, 32, 64]));
+        assert_eq!(parse_ring_sizes("64,16,32,16"), Some(vec![16, 32, 64]));
+        assert_eq!(parse_ring_sizes("16,abc"), None);
+        assert_eq!(parse_ring_sizes(""), None);
+    }
+
+    #[test]
+    fn message_length_sweeps() {
+        assert_eq!(
+            parse_message_lengths("32..1048576*32"),
+            Some(DEFAULT_MESSAGE_LENGTHS.to_vec())
+        );
+        assert_eq!(parse_message_lengths("2097152"), None);
+    }
+
+    #[test]
+    fn ring_sizes_sweeps() {
+        assert_eq!(parse_ring_sizes("6..9"), Some(vec![6, 7, 8, 9]));
+        assert_eq!(
+            parse_ring_sizes("100..400+100, 1000"),
+            Some(vec![100, 200, 300, 400, 1000])
+        );
+
+        let powers = parse_ring_sizes("16..65536*2").unwrap();
+        assert_eq!(powers.len(), 13);
+        assert_eq!(powers.last(), Some(&MAX_RING_SIZE));
+        assert_eq!(parse_ring_sizes("3..100*3"), Some(vec![3, 9, 27, 81]));
+
+        assert_eq!(parse_ring_sizes("16..131072*2"), None);
+        assert_eq!(parse_ring_sizes("0..8"), None);
+        assert_eq!(parse_ring_sizes("64..16"), None);
+        assert_eq!(parse_ring_sizes("16..64*1"), None);
+        assert_eq!(parse_ring_sizes("16..64+0"), None);
+    }
+
+    #[test]
+    fn sweeps_with_overflowing_steps() {
+        assert_eq!(
+            parse_ring_sizes(&format!("1..10+{}", usize::MAX)),
+            Some(vec![1])
+        );
+        assert_eq!(
+            parse_ring_sizes(&format!("2..10*{}", usize::MAX)),
+            Some(vec![2])
+        );
+        assert_eq!(
+            parse_message_lengths(&format!("1048576..1048576*{}", usize::MAX)),
+            Some(vec![1048576])
+        );
+    }
+
+    #[test]
+    fn csv_row_matches_header() {
+        let mut record = Record::size("clsag", "size", 8, 320);
+        record.cpu = "Some CPU, 4 cores".to_string();
+        let row = record.to_csv();
+        assert!(row.starts_with("clsag,size,8,,0,,,,,,320,"));
+        assert!(row.ends_with("\"Some CPU, 4 cores\""));
+        assert_eq!(CSV_HEADER.split(',').count(), 15);
+    }
+}
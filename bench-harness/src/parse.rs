@@ -0,0 +1,375 @@
+//! Parsers turning the output of benchmark programs into records.
+//!
+//! The Rust benchmarks print records directly, see [`parse_records`].
+//! The Go, C and JavaScript benchmarks each have their own output format,
+//! which is handled by a [`Parser`].
+
+use crate::Record;
+
+/// The number of trials the external benchmark programs run, which they do not print
+pub const EXTERNAL_TRIALS: usize = 10;
+
+/// Extracts the records printed by a Rust benchmark.
+///
+/// A record may follow other output on the same line, as libtest prints
+/// `test name ... ` before the test's own output when run with `--nocapture`.
+pub fn parse_records(output: &str) -> Vec<Record> {
+    output
+        .lines()
+        .filter_map(|line| line.find('{').map(|start| &line[start..]))
+        .filter_map(Record::from_json)
+        .collect()
+}
+
+/// The output formats of the external benchmark programs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Parser {
+    /// `DualDory/bench/main.go`
+    DualDory,
+    /// `LRS/example.go`
+    Lrs,
+    /// `Raptor/bench.sh`
+    Raptor,
+    /// The `./tests` binary of `U2SSO/crypto-dbpoe`
+    Boquila,
+    /// `npm test` in `U2SSO/crypto-snark`
+    Snark,
+}
+
+impl Parser {
+    pub fn from_name(name: &str) -> Option<Parser> {
+        match name {
+            "dualdory" => Some(Parser::DualDory),
+            "lrs" => Some(Parser::Lrs),
+            "raptor" => Some(Parser::Raptor),
+            "boquila" => Some(Parser::Boquila),
+            "snark" => Some(Parser::Snark),
+            _ => None,
+        }
+    }
+
+    /// Parses a program's output into records for the scheme.
+    /// `ring_sizes` are the sizes the program ran at, used when it does not print them
+    pub fn parse(&self, scheme: &str, output: &str, ring_sizes: &[usize]) -> Vec<Record> {
+        match self {
+            Parser::DualDory => parse_dualdory(scheme, output),
+            Parser::Lrs => parse_lrs(scheme, output, ring_sizes),
+            Parser::Raptor => parse_raptor(scheme, output),
+            Parser::Boquila => parse_boquila(scheme, output),
+            Parser::Snark => parse_snark(scheme, output),
+        }
+    }
+}
+
+// Parses `mean+stddev`
+fn mean_std(s: &str) -> Option<(f64, f64)> {
+    let mut parts = s.trim().splitn(2, '+');
+    let mean = parts.next()?.trim().parse().ok()?;
+    let std_dev = parts.next()?.trim().parse().ok()?;
+    Some((mean, std_dev))
+}
+
+// Lines look like `>>> n, sign+std, verify+std`, followed by a line of sizes
+// after `Sizes:` that looks like `(n, bytes)(n, bytes)`
+fn parse_dualdory(scheme: &str, output: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut lines = output.lines();
+
+    while let Some(line) = lines.next() {
+        if let Some(rest) = line.strip_prefix(">>>") {
+            let fields: Vec<&str> = rest.split(',').collect();
+            if fields.len() != 3 {
+                continue;
+            }
+            let n = match fields[0].trim().parse() {
+                Ok(n) => n,
+                Err(_) => continue,
+            };
+            for (operation, field) in [("sign", fields[1]), ("verify", fields[2])] {
+                if let Some((mean, std_dev)) = mean_std(field) {
+                    records.push(Record::summary(
+                        scheme,
+                        operation,
+                        n,
+                        EXTERNAL_TRIALS,
+                        mean,
+                        std_dev,
+                    ));
+                }
+            }
+        } else if line.trim() == "Sizes:" {
+            let sizes = lines.next().unwrap_or_default();
+            for pair in sizes.split(')') {
+                let mut fields = pair.trim_start_matches('(').split(',');
+                let n = fields.next().and_then(|n| n.trim().parse().ok());
+                let bytes = fields.next().and_then(|b| b.trim().parse().ok());
+                if let (Some(n), Some(bytes)) = (n, bytes) {
+                    records.push(Record::size(scheme, "size", n, bytes));
+                }
+            }
+        }
+    }
+    records
+}
+
+// The program prints `signing time` and `verify time`, each followed by one `mean+std`
+// line per ring size, then one size per ring size. The first value is printed on the
+// same line as the heading
+fn parse_lrs(scheme: &str, output: &str, ring_sizes: &[usize]) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut operation = None;
+    let mut index = 0;
+    let mut size_index = 0;
+
+    for line in output.lines() {
+        let mut line = line.trim();
+        for (heading, op) in [("signing time", "sign"), ("verify time", "verify")] {
+            if let Some(rest) = line.strip_prefix(heading) {
+                operation = Some(op);
+                index = 0;
+                line = rest.trim();
+            }
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        if let (Some(op), Some((mean, std_dev))) = (operation, mean_std(line)) {
+            if let Some(&n) = ring_sizes.get(index) {
+                records.push(Record::summary(
+                    scheme,
+                    op,
+                    n,
+                    EXTERNAL_TRIALS,
+                    mean,
+                    std_dev,
+                ));
+            }
+            index += 1;
+        } else if let (Some("verify"), Ok(bytes)) = (operation, line.parse()) {
+            if let Some(&n) = ring_sizes.get(size_index) {
+                records.push(Record::size(scheme, "size", n, bytes));
+            }
+            size_index += 1;
+        }
+    }
+    records
+}
+
+// bench.sh prints `Setting NOU = n` before building and running the tests for each
+// ring size, which print `sign (ms) :mean+std`, `verify (ms) :mean+std` and
+// `proof size (B): bytes`
+fn parse_raptor(scheme: &str, output: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut ring_size = None;
+
+    for line in output.lines() {
+        if let Some((_, n)) = line.split_once("Setting NOU =") {
+            ring_size = n.trim().parse().ok();
+            continue;
+        }
+        let n = match ring_size {
+            Some(n) => n,
+            None => continue,
+        };
+
+        if let Some(rest) = line.strip_prefix("sign (ms) :") {
+            if let Some((mean, std_dev)) = mean_std(rest) {
+                records.push(Record::summary(
+                    scheme,
+                    "sign",
+                    n,
+                    EXTERNAL_TRIALS,
+                    mean,
+                    std_dev,
+                ));
+            }
+        } else if let Some(rest) = line.strip_prefix("verify (ms) :") {
+            if let Some((mean, std_dev)) = mean_std(rest) {
+                records.push(Record::summary(
+                    scheme,
+                    "verify",
+                    n,
+                    EXTERNAL_TRIALS,
+                    mean,
+                    std_dev,
+                ));
+            }
+        } else if let Some(rest) = line.strip_prefix("proof size (B):") {
+            if let Ok(bytes) = rest.trim().parse() {
+                records.push(Record::size(scheme, "size", n, bytes));
+            }
+        }
+    }
+    records
+}
+
+// Each benchmark prints its name, e.g. `test_boquila_3_bench`, followed by lines of
+// `n, m, bytes, prove+std, verify+std` with times in seconds.
+// Each benchmark is reported as its own scheme, e.g. `boquila-3`
+fn parse_boquila(scheme: &str, output: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut current = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+        if line.starts_with("test_boquila") && line.ends_with("_bench") {
+            let variant = line
+                .trim_start_matches("test_boquila")
+                .trim_end_matches("_bench")
+                .trim_start_matches('_')
+                .to_lowercase();
+            current = Some(if variant.is_empty() {
+                scheme.to_string()
+            } else {
+                format!("{}-{}", scheme, variant)
+            });
+            continue;
+        }
+        let name = match &current {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() < 5 {
+            continue;
+        }
+        let n = fields[0].parse();
+        let bytes = fields[2].parse();
+        let prove = mean_std(fields[3]);
+        let verify = mean_std(fields[4]);
+        if let (Ok(n), Ok(bytes), Some(prove), Some(verify)) = (n, bytes, prove, verify) {
+            for (operation, (mean, std_dev)) in [("sign", prove), ("verify", verify)] {
+                records.push(Record::summary(
+                    name,
+                    operation,
+                    n,
+                    EXTERNAL_TRIALS,
+                    mean * 1000.0,
+                    std_dev * 1000.0,
+                ));
+            }
+            records.push(Record::size(name, "size", n, bytes));
+        }
+    }
+    records
+}
+
+// Lines look like `group size: n, prove+std, verify+std, bytes+std`.
+// The size is an estimate of the proof object's size, so it is rounded
+fn parse_snark(scheme: &str, output: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+
+    for line in output.lines() {
+        let rest = match line.trim().strip_prefix("group size:") {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let fields: Vec<&str> = rest.split(',').collect();
+        if fields.len() != 4 {
+            continue;
+        }
+        let n = match fields[0].trim().parse() {
+            Ok(n) => n,
+            Err(_) => continue,
+        };
+        for (operation, field) in [("sign", fields[1]), ("verify", fields[2])] {
+            if let Some((mean, std_dev)) = mean_std(field) {
+                records.push(Record::summary(
+                    scheme,
+                    operation,
+                    n,
+                    EXTERNAL_TRIALS,
+                    mean,
+                    std_dev,
+                ));
+            }
+        }
+        if let Some((bytes, _)) = mean_std(fields[3]) {
+            records.push(Record::size(scheme, "size", n, bytes.round() as usize));
+        }
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(records: &'a [Record], scheme: &str, operation: &str, n: usize) -> &'a Record {
+        records
+            .iter()
+            .find(|r| r.scheme == scheme && r.operation == operation && r.ring_size == n)
+            .unwrap()
+    }
+
+    #[test]
+    fn rust_records() {
+        let record = Record::timing("trs", "sign", 16, &[1.0, 2.0]);
+        let output = format!(
+            "running 1 test\ntest proof_time_bench ... {}\n{}\ntest result: ok.",
+            record.to_json(),
+            record.to_json()
+        );
+        assert_eq!(parse_records(&output), vec![record.clone(), record]);
+    }
+
+    #[test]
+    fn dualdory() {
+        let output = ">>> 2, 31+16.090, 36+19.264\n>>> 4, 53+27.889, 54+28.304\n\
+                      Sizes:\n(2, 8412)(4, 15548)\nPre-processing:\n(2, 9)(4, 21)\n";
+        let records = Parser::DualDory.parse("dualdory", output, &[]);
+        assert_eq!(records.len(), 6);
+        assert_eq!(find(&records, "dualdory", "sign", 4).mean_ms, Some(53.0));
+        assert_eq!(
+            find(&records, "dualdory", "verify", 2).stddev_ms,
+            Some(19.264)
+        );
+        assert_eq!(find(&records, "dualdory", "size", 4).bytes, Some(15548));
+    }
+
+    #[test]
+    fn lrs() {
+        let output = "\nsigning time1.000+0.000\n3.000+0.000\n\nverify time1.100+0.316\n2.900+0.316\n881\n1328\n";
+        let records = Parser::Lrs.parse("lrs", output, &[8, 16]);
+        assert_eq!(records.len(), 6);
+        assert_eq!(find(&records, "lrs", "sign", 16).mean_ms, Some(3.0));
+        assert_eq!(find(&records, "lrs", "verify", 8).mean_ms, Some(1.1));
+        assert_eq!(find(&records, "lrs", "size", 16).bytes, Some(1328));
+    }
+
+    #[test]
+    fn raptor() {
+        let output =
+            "⇒ Setting NOU = 8\n⇒ Building...\nkeygen (ms) :8635.400\nsign (ms) :1.343+0.055\n\
+                      verify (ms) :0.950+0.026\nproof size (B): 132626\n";
+        let records = Parser::Raptor.parse("raptor", output, &[]);
+        assert_eq!(records.len(), 3);
+        assert_eq!(find(&records, "raptor", "sign", 8).mean_ms, Some(1.343));
+        assert_eq!(find(&records, "raptor", "size", 8).bytes, Some(132626));
+    }
+
+    #[test]
+    fn boquila() {
+        let output =
+            "passed ring cip tests\ntest_boquila_bench\n16, 3, 423, 0.004+0.000, 0.002+0.000\n\
+                      test_boquila_DBPoE_bench\n16, 3, 3591, 0.025+0.000, 0.009+0.000, 100\n";
+        let records = Parser::Boquila.parse("boquila", output, &[]);
+        assert_eq!(records.len(), 6);
+        assert_eq!(find(&records, "boquila", "sign", 16).mean_ms, Some(4.0));
+        assert_eq!(
+            find(&records, "boquila-dbpoe", "size", 16).bytes,
+            Some(3591)
+        );
+    }
+
+    #[test]
+    fn snark() {
+        let output = "group size: 8, 261.788+12.055, 9.86+0.461, 1552.4+2.951\n";
+        let records = Parser::Snark.parse("snark", output, &[]);
+        assert_eq!(records.len(), 3);
+        assert_eq!(find(&records, "snark", "verify", 8).mean_ms, Some(9.86));
+        assert_eq!(find(&records, "snark", "size", 8).bytes, Some(1552));
+    }
+}
@@ -0,0 +1,207 @@
+//! Timing for the test based benchmarks.
+//!
+//! Each sample is timed with nanosecond resolution. Fast operations are repeated
+//! within a sample so that every sample is long enough to time accurately, and the
+//! number of repetitions is found from a warmup. Outliers are rejected with Tukey's
+//! fences before the statistics are computed.
+//!
+//! If [`crate::alloc::CountingAlloc`] is the global allocator, the heap usage of one run
+//! is also measured.
+
+use crate::alloc::{self, AllocStats};
+use crate::{mean, median, std_dev};
+use std::time::{Duration, Instant};
+
+pub use std::hint::black_box;
+
+/// Settings for measuring an operation
+#[derive(Clone, Debug)]
+pub struct Bench {
+    /// Number of timed samples
+    pub trials: usize,
+    /// The operation is run for at least this long before any samples are taken
+    pub warmup: Duration,
+    /// Each sample repeats the operation until it takes at least this long
+    pub min_sample: Duration,
+}
+
+impl Bench {
+    pub fn new(trials: usize) -> Bench {
+        Bench {
+            trials,
+            warmup: Duration::from_millis(100),
+            min_sample: Duration::from_millis(10),
+        }
+    }
+
+    /// Creates settings with the number of trials read from `BENCH_TRIALS` if it is set
+    pub fn from_env(default_trials: usize) -> Bench {
+        Bench::new(crate::trials(default_trials))
+    }
+
+    /// Measures `f`, passing every result through `black_box` so it is not optimised away.
+    /// Inputs captured by `f` should also be passed through `black_box`
+    pub fn run<R, F: FnMut() -> R>(&self, mut f: F) -> Measurement {
+        // Warm up, and estimate how long a single run takes
+        let start = Instant::now();
+        let mut runs = 0u32;
+        while runs == 0 || start.elapsed() < self.warmup {
+            black_box(f());
+            runs += 1;
+        }
+        let per_run = start.elapsed() / runs;
+
+        let alloc = alloc::measure(|| black_box(f())).1;
+
+        let iterations = if per_run >= self.min_sample {
+            1
+        } else {
+            let per_run = per_run.as_nanos().max(1);
+            self.min_sample.as_nanos().div_ceil(per_run) as u64
+        };
+
+        let samples_ns: Vec<f64> = (0..self.trials.max(1))
+            .map(|_| {
+                let start = Instant::now();
+                for _ in 0..iterations {
+                    black_box(f());
+                }
+                start.elapsed().as_nanos() as f64 / iterations as f64
+            })
+            .collect();
+
+        Measurement {
+            alloc,
+            ..Measurement::new(samples_ns, iterations)
+        }
+    }
+}
+
+/// The result of measuring an operation
+#[derive(Clone, Debug)]
+pub struct Measurement {
+    /// Time per run of each sample in nanoseconds, with outliers removed
+    pub samples_ns: Vec<f64>,
+    /// Number of samples rejected as outliers
+    pub outliers: usize,
+    /// Number of runs in each sample
+    pub iterations: u64,
+    /// Heap usage of a single run, if it was measured
+    pub alloc: Option<AllocStats>,
+}
+
+impl Measurement {
+    /// Creates a measurement from per-run sample times, rejecting outliers
+    pub fn new(samples_ns: Vec<f64>, iterations: u64) -> Measurement {
+        let total = samples_ns.len();
+        let samples_ns = reject_outliers(samples_ns);
+        Measurement {
+            outliers: total - samples_ns.len(),
+            samples_ns,
+            iterations,
+            alloc: None,
+        }
+    }
+
+    pub fn mean_ns(&self) -> f64 {
+        mean(&self.samples_ns)
+    }
+
+    pub fn std_dev_ns(&self) -> f64 {
+        std_dev(&self.samples_ns)
+    }
+
+    pub fn median_ns(&self) -> f64 {
+        median(&self.samples_ns)
+    }
+
+    /// Half width of the 95% confidence interval of the mean
+    pub fn ci95_ns(&self) -> f64 {
+        let n = self.samples_ns.len();
+        if n < 2 {
+            return 0.0;
+        }
+        t_critical(n - 1) * self.std_dev_ns() / (n as f64).sqrt()
+    }
+}
+
+// Removes samples outside Tukey's fences, 1.5 interquartile ranges beyond the quartiles.
+// Too few samples to estimate the quartiles are kept as they are
+fn reject_outliers(samples: Vec<f64>) -> Vec<f64> {
+    if samples.len() < 4 {
+        return samples;
+    }
+    let mut sorted = samples.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).expect("samples are never NaN"));
+
+    let q1 = quantile(&sorted, 0.25);
+    let q3 = quantile(&sorted, 0.75);
+    let iqr = q3 - q1;
+    let (low, high) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+
+    samples
+        .into_iter()
+        .filter(|&t| t >= low && t <= high)
+        .collect()
+}
+
+// Linearly interpolated quantile of sorted samples
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
+
+// Two sided 95% critical value of Student's t distribution
+pub(crate) fn t_critical(degrees_of_freedom: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match degrees_of_freedom {
+        0 => f64::INFINITY,
+        df if df <= TABLE.len() => TABLE[df - 1],
+        _ => 1.96,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outliers_are_rejected() {
+        let samples = vec![10.0, 11.0, 10.5, 9.5, 10.2, 100.0];
+        let measurement = Measurement::new(samples, 1);
+        assert_eq!(measurement.outliers, 1);
+        assert_eq!(measurement.samples_ns.len(), 5);
+        assert!(measurement.mean_ns() < 11.0);
+    }
+
+    #[test]
+    fn confidence_interval() {
+        let measurement = Measurement::new(vec![1.0, 2.0, 3.0, 4.0], 1);
+        // t(3) * s / sqrt(4) with s = 1.29
+        assert!((measurement.ci95_ns() - 3.182 * 1.2909944 / 2.0).abs() < 1e-6);
+        assert_eq!(Measurement::new(vec![1.0], 1).ci95_ns(), 0.0);
+    }
+
+    #[test]
+    fn fast_operations_are_repeated() {
+        let bench = Bench {
+            trials: 5,
+            warmup: Duration::from_millis(1),
+            min_sample: Duration::from_millis(1),
+        };
+        let mut runs = 0u64;
+        let measurement = bench.run(|| {
+            runs += 1;
+            black_box(runs)
+        });
+        assert!(measurement.iterations > 1);
+        assert!(runs >= 5 * measurement.iterations);
+        assert_eq!(measurement.samples_ns.len() + measurement.outliers, 5);
+    }
+}
@@ -1,5 +1,5 @@
 start=$(date +%s.%N)
-./run_benches.sh &
+cargo run --release --manifest-path bench-harness/Cargo.toml --bin bench-runner -- --config benches.toml &
 pid=$!
 
 wait $pid
@@ -0,0 +1,340 @@
+//! Hashing to ristretto255 with the RFC 9380 `ristretto255_XMD:SHA-512_R255MAP_RO_` suite,
+//! or its `ristretto255_XOF:SHAKE256_R255MAP_RO_` variant.
+//!
+//! A message is expanded to 64 uniform bytes with `expand_message_xmd` and SHA-512 under
+//! a domain separation tag, and the bytes are mapped to the group with the ristretto255
+//! one-way map, which is `RistrettoPoint::from_uniform_bytes` in every curve25519-dalek
+//! release. This crate only produces the bytes so that TRS and CLSAG can share it while
+//! depending on different versions of curve25519-dalek.
+//!
+//! Every use of the hash has its own DST, so no two uses can output the same point.
+
+use sha2::{Digest, Sha512};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+
+/// DST of the tracing base h := H(L) in TRS
+pub const TRS_TRACING_BASE_DST: &[u8] =
+    b"TRS-TRACING-BASE-V01-CS01-with-ristretto255_XMD:SHA-512_R255MAP_RO_";
+
+/// DST of A₀ := H'(L, m) in TRS
+pub const TRS_A0_DST: &[u8] = b"TRS-A0-V01-CS01-with-ristretto255_XMD:SHA-512_R255MAP_RO_";
+
+/// DST of the tracing base in TRS with the SHAKE256 expander
+pub const TRS_TRACING_BASE_XOF_DST: &[u8] =
+    b"TRS-TRACING-BASE-V01-CS02-with-ristretto255_XOF:SHAKE256_R255MAP_RO_";
+
+/// DST of A₀ in TRS with the SHAKE256 expander
+pub const TRS_A0_XOF_DST: &[u8] = b"TRS-A0-V01-CS02-with-ristretto255_XOF:SHAKE256_R255MAP_RO_";
+
+/// DST of the key image base H(P₀) in CLSAG
+pub const CLSAG_KEY_IMAGE_BASE_DST: &[u8] =
+    b"CLSAG-KEY-IMAGE-BASE-V01-CS01-with-ristretto255_XMD:SHA-512_R255MAP_RO_";
+
+/// DST of the Pedersen value generator in CLSAG
+pub const CLSAG_VALUE_GENERATOR_DST: &[u8] =
+    b"CLSAG-VALUE-GENERATOR-V01-CS01-with-ristretto255_XMD:SHA-512_R255MAP_RO_";
+
+/// The number of bytes the suite hashes a message to
+pub const UNIFORM_BYTES_LEN: usize = 64;
+
+// SHA-512 output and block sizes
+const B_IN_BYTES: usize = 64;
+const S_IN_BYTES: usize = 128;
+
+const OVERSIZE_DST_PREFIX: &[u8] = b"H2C-OVERSIZE-DST-";
+
+/// How a message is expanded to uniform bytes
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Expander {
+    /// `expand_message_xmd` with SHA-512, as in `ristretto255_XMD:SHA-512_R255MAP_RO_`
+    XmdSha512,
+    /// `expand_message_xof` with SHAKE256, as in `ristretto255_XOF:SHAKE256_R255MAP_RO_`
+    XofShake256,
+}
+
+/// Hashes `msg` under `dst` to 64 bytes, to be mapped to the group with
+/// `RistrettoPoint::from_uniform_bytes`
+pub fn hash_to_ristretto255(msg: &[u8], dst: &[u8]) -> [u8; 64] {
+    let mut h = Hasher::new(dst);
+    h.update(msg);
+    h.finalize()
+}
+
+/// `expand_message_xmd` with SHA-512, as in section 5.3.1 of RFC 9380. DSTs longer than
+/// 255 bytes are hashed first, as in section 5.3.3.
+///
+/// Panics if `len` is 0 or more than 255 * 64
+pub fn expand_message_xmd(msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    let mut h = Hasher::new(dst);
+    h.update(msg);
+    h.expand(len)
+}
+
+/// `expand_message_xof` with SHAKE256, as in section 5.3.2 of RFC 9380. DSTs longer than
+/// 255 bytes are hashed first, as in section 5.3.3.
+///
+/// Panics if `len` is 0 or more than 65535
+pub fn expand_message_xof(msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    let mut h = Hasher::with_expander(Expander::XofShake256, dst);
+    h.update(msg);
+    h.expand(len)
+}
+
+/// Incrementally hashes a message that is fed in pieces, as `hash_to_ristretto255` would
+/// hash the pieces concatenated
+#[derive(Clone)]
+pub struct Hasher {
+    state: State,
+    dst_prime: Vec<u8>,
+}
+
+#[derive(Clone)]
+enum State {
+    Xmd(Sha512),
+    Xof(Shake256),
+}
+
+impl Hasher {
+    /// A hasher with the `expand_message_xmd` SHA-512 expander
+    pub fn new(dst: &[u8]) -> Hasher {
+        Hasher::with_expander(Expander::XmdSha512, dst)
+    }
+
+    pub fn with_expander(expander: Expander, dst: &[u8]) -> Hasher {
+        let mut dst_prime = if dst.len() <= 255 {
+            dst.to_vec()
+        } else {
+            match expander {
+                Expander::XmdSha512 => Sha512::new()
+                    .chain_update(OVERSIZE_DST_PREFIX)
+                    .chain_update(dst)
+                    .finalize()
+                    .to_vec(),
+                // ceil(2k / 8) bytes for the 128-bit security level of ristretto255
+                Expander::XofShake256 => {
+                    let mut out = vec![0u8; 32];
+                    Shake256::default()
+                        .chain(OVERSIZE_DST_PREFIX)
+                        .chain(dst)
+                        .finalize_xof()
+                        .read(&mut out);
+                    out
+                }
+            }
+        };
+        dst_prime.push(dst_prime.len() as u8);
+
+        let state = match expander {
+            // Z_pad is a block of zeros so that msg starts a new block
+            Expander::XmdSha512 => State::Xmd(Sha512::new().chain_update([0u8; S_IN_BYTES])),
+            Expander::XofShake256 => State::Xof(Shake256::default()),
+        };
+        Hasher { state, dst_prime }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            State::Xmd(h) => Digest::update(h, data),
+            State::Xof(h) => h.update(data),
+        }
+    }
+
+    /// The 64 uniform bytes of the message
+    pub fn finalize(self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(&self.expand(UNIFORM_BYTES_LEN));
+        bytes
+    }
+
+    fn expand(self, len: usize) -> Vec<u8> {
+        match self.state {
+            State::Xmd(b0) => expand_xmd(b0, &self.dst_prime, len),
+            State::Xof(h) => {
+                assert!(len > 0 && len <= 0xffff, "invalid expand_message_xof length {}", len);
+
+                // H(msg || I2OSP(len, 2) || DST_prime, len)
+                let mut out = vec![0u8; len];
+                h.chain((len as u16).to_be_bytes())
+                    .chain(&self.dst_prime)
+                    .finalize_xof()
+                    .read(&mut out);
+                out
+            }
+        }
+    }
+}
+
+fn expand_xmd(b0: Sha512, dst_prime: &[u8], len: usize) -> Vec<u8> {
+    let ell = len.div_ceil(B_IN_BYTES);
+    assert!(len > 0 && ell <= 255, "invalid expand_message_xmd length {}", len);
+
+    // b₀ := H(Z_pad || msg || I2OSP(len, 2) || I2OSP(0, 1) || DST_prime)
+    let b0 = b0
+        .chain_update((len as u16).to_be_bytes())
+        .chain_update([0u8])
+        .chain_update(dst_prime)
+        .finalize();
+
+    // b₁ := H(b₀ || I2OSP(1, 1) || DST_prime), bᵢ := H((b₀ ⊕ bᵢ₋₁) || I2OSP(i, 1) || DST_prime)
+    let mut out = Vec::with_capacity(ell * B_IN_BYTES);
+    let mut bi = Sha512::new()
+        .chain_update(b0)
+        .chain_update([1u8])
+        .chain_update(dst_prime)
+        .finalize();
+    out.extend_from_slice(&bi);
+    for i in 2..=ell {
+        let mut xored = [0u8; B_IN_BYTES];
+        for (x, (a, b)) in xored.iter_mut().zip(b0.iter().zip(bi.iter())) {
+            *x = a ^ b;
+        }
+        bi = Sha512::new()
+            .chain_update(xored)
+            .chain_update([i as u8])
+            .chain_update(dst_prime)
+            .finalize();
+        out.extend_from_slice(&bi);
+    }
+    out.truncate(len);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The expand_message_xmd SHA-512 vectors of RFC 9380 appendix K.3, and the
+    // expand_message_xof SHAKE256 vectors of appendix K.6
+    const DST: &[u8] = b"QUUX-V01-CS02-with-expander-SHA512-256";
+    const XOF_DST: &[u8] = b"QUUX-V01-CS02-with-expander-SHAKE256";
+
+    fn msgs() -> Vec<Vec<u8>> {
+        vec![
+            b"".to_vec(),
+            b"abc".to_vec(),
+            b"abcdef0123456789".to_vec(),
+            [b"q128_".to_vec(), vec![b'q'; 128]].concat(),
+            [b"a512_".to_vec(), vec![b'a'; 512]].concat(),
+        ]
+    }
+
+    #[test]
+    fn test_expand_message_xmd_32() {
+        let expected = [
+            "6b9a7312411d92f921c6f68ca0b6380730a1a4d982c507211a90964c394179ba",
+            "0da749f12fbe5483eb066a5f595055679b976e93abe9be6f0f6318bce7aca8dc",
+            "087e45a86e2939ee8b91100af1583c4938e0f5fc6c9db4b107b83346bc967f58",
+            "7336234ee9983902440f6bc35b348352013becd88938d2afec44311caf8356b3",
+            "57b5f7e766d5be68a6bfe1768e3c2b7f1228b3e4b3134956dd73a59b954c66f4",
+        ];
+        for (msg, expected) in msgs().iter().zip(expected.iter()) {
+            assert_eq!(hex::encode(expand_message_xmd(msg, DST, 0x20)), *expected);
+        }
+    }
+
+    #[test]
+    fn test_expand_message_xmd_128() {
+        let expected = [
+            "41b037d1734a5f8df225dd8c7de38f851efdb45c372887be655212d07251b921b052b62eaed99b46f72f2ef4cc96bfaf254ebbbec091e1a3b9e4fb5e5b619d2e0c5414800a1d882b62bb5cd1778f098b8eb6cb399d5d9d18f5d5842cf5d13d7eb00a7cff859b605da678b318bd0e65ebff70bec88c753b159a805d2c89c55961",
+            "7f1dddd13c08b543f2e2037b14cefb255b44c83cc397c1786d975653e36a6b11bdd7732d8b38adb4a0edc26a0cef4bb45217135456e58fbca1703cd6032cb1347ee720b87972d63fbf232587043ed2901bce7f22610c0419751c065922b488431851041310ad659e4b23520e1772ab29dcdeb2002222a363f0c2b1c972b3efe1",
+            "3f721f208e6199fe903545abc26c837ce59ac6fa45733f1baaf0222f8b7acb0424814fcb5eecf6c1d38f06e9d0a6ccfbf85ae612ab8735dfdf9ce84c372a77c8f9e1c1e952c3a61b7567dd0693016af51d2745822663d0c2367e3f4f0bed827feecc2aaf98c949b5ed0d35c3f1023d64ad1407924288d366ea159f46287e61ac",
+            "b799b045a58c8d2b4334cf54b78260b45eec544f9f2fb5bd12fb603eaee70db7317bf807c406e26373922b7b8920fa29142703dd52bdf280084fb7ef69da78afdf80b3586395b433dc66cde048a258e476a561e9deba7060af40adf30c64249ca7ddea79806ee5beb9a1422949471d267b21bc88e688e4014087a0b592b695ed",
+            "05b0bfef265dcee87654372777b7c44177e2ae4c13a27f103340d9cd11c86cb2426ffcad5bd964080c2aee97f03be1ca18e30a1f14e27bc11ebbd650f305269cc9fb1db08bf90bfc79b42a952b46daf810359e7bc36452684784a64952c343c52e5124cd1f71d474d5197fefc571a92929c9084ffe1112cf5eea5192ebff330b",
+        ];
+        for (msg, expected) in msgs().iter().zip(expected.iter()) {
+            assert_eq!(hex::encode(expand_message_xmd(msg, DST, 0x80)), *expected);
+        }
+    }
+
+    #[test]
+    fn test_expand_message_xof_32() {
+        let expected = [
+            "2ffc05c48ed32b95d72e807f6eab9f7530dd1c2f013914c8fed38c5ccc15ad76",
+            "b39e493867e2767216792abce1f2676c197c0692aed061560ead251821808e07",
+            "245389cf44a13f0e70af8665fe5337ec2dcd138890bb7901c4ad9cfceb054b65",
+            "719b3911821e6428a5ed9b8e600f2866bcf23c8f0515e52d6c6c019a03f16f0e",
+            "9181ead5220b1963f1b5951f35547a5ea86a820562287d6ca4723633d17ccbbc",
+        ];
+        for (msg, expected) in msgs().iter().zip(expected.iter()) {
+            assert_eq!(hex::encode(expand_message_xof(msg, XOF_DST, 0x20)), *expected);
+        }
+    }
+
+    #[test]
+    fn test_expand_message_xof_128() {
+        let expected = [
+            "7a1361d2d7d82d79e035b8880c5a3c86c5afa719478c007d96e6c88737a3f631dd74a2c88df79a4cb5e5d9f7504957c70d669ec6bfedc31e01e2bacc4ff3fdf9b6a00b17cc18d9d72ace7d6b81c2e481b4f73f34f9a7505dccbe8f5485f3d20c5409b0310093d5d6492dea4e18aa6979c23c8ea5de01582e9689612afbb353df",
+            "a54303e6b172909783353ab05ef08dd435a558c3197db0c132134649708e0b9b4e34fb99b92a9e9e28fc1f1d8860d85897a8e021e6382f3eea10577f968ff6df6c45fe624ce65ca25932f679a42a404bc3681efe03fcd45ef73bb3a8f79ba784f80f55ea8a3c367408f30381299617f50c8cf8fbb21d0f1e1d70b0131a7b6fbe",
+            "e42e4d9538a189316e3154b821c1bafb390f78b2f010ea404e6ac063deb8c0852fcd412e098e231e43427bd2be1330bb47b4039ad57b30ae1fc94e34993b162ff4d695e42d59d9777ea18d3848d9d336c25d2acb93adcad009bcfb9cde12286df267ada283063de0bb1505565b2eb6c90e31c48798ecdc71a71756a9110ff373",
+            "4ac054dda0a38a65d0ecf7afd3c2812300027c8789655e47aecf1ecc1a2426b17444c7482c99e5907afd9c25b991990490bb9c686f43e79b4471a23a703d4b02f23c669737a886a7ec28bddb92c3a98de63ebf878aa363a501a60055c048bea11840c4717beae7eee28c3cfa42857b3d130188571943a7bd747de831bd6444e0",
+            "09afc76d51c2cccbc129c2315df66c2be7295a231203b8ab2dd7f95c2772c68e500bc72e20c602abc9964663b7a03a389be128c56971ce81001a0b875e7fd17822db9d69792ddf6a23a151bf470079c518279aef3e75611f8f828994a9988f4a8a256ddb8bae161e658d5a2a09bcfe839c6396dc06ee5c8ff3c22d3b1f9deb7e",
+        ];
+        for (msg, expected) in msgs().iter().zip(expected.iter()) {
+            assert_eq!(hex::encode(expand_message_xof(msg, XOF_DST, 0x80)), *expected);
+        }
+    }
+
+    // RFC 9380 has no ristretto255 vectors, so the whole suite, the expansion and the
+    // one-way map, is checked against the OPRF(ristretto255, SHA-512) vectors of RFC 9497
+    // appendix A.1.1. Its HashToGroup is hash_to_ristretto255 under the DST below, and
+    // each vector gives an input, the blind r and the blinded element rH(input)
+    #[test]
+    fn test_hash_to_ristretto255_rfc9497_vectors() {
+        use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
+        use std::convert::TryInto;
+
+        let dst = b"HashToGroup-OPRFV1-\x00-ristretto255-SHA512";
+        let blind = hex::decode("64d37aed22a27f5191de1c1d69fadb899d8862b58eb4220029e036ec4c1f6706");
+        let blind = Scalar::from_canonical_bytes(blind.unwrap().try_into().unwrap()).unwrap();
+        let vectors = [
+            (
+                "00",
+                "609a0ae68c15a3cf6903766461307e5c8bb2f95e7e6550e1ffa2dc99e412803c",
+            ),
+            (
+                "5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a",
+                "da27ef466870f5f15296299850aa088629945a17d1f5b7f5ff043f76b3c06418",
+            ),
+        ];
+        for (input, blinded) in vectors.iter() {
+            let bytes = hash_to_ristretto255(&hex::decode(input).unwrap(), dst);
+            let point = RistrettoPoint::from_uniform_bytes(&bytes);
+            assert_eq!(hex::encode((blind * point).compress().as_bytes()), *blinded);
+        }
+    }
+
+    #[test]
+    fn test_hasher_domain_separation() {
+        let mut h = Hasher::new(TRS_A0_DST);
+        h.update(b"ab");
+        h.update(b"c");
+        let bytes = h.finalize();
+        assert_eq!(bytes, hash_to_ristretto255(b"abc", TRS_A0_DST));
+        assert_eq!(bytes.to_vec(), expand_message_xmd(b"abc", TRS_A0_DST, 64));
+
+        let mut h = Hasher::with_expander(Expander::XofShake256, TRS_A0_XOF_DST);
+        h.update(b"ab");
+        h.update(b"c");
+        assert_eq!(h.finalize().to_vec(), expand_message_xof(b"abc", TRS_A0_XOF_DST, 64));
+
+        let dsts = [
+            TRS_TRACING_BASE_DST,
+            TRS_A0_DST,
+            TRS_TRACING_BASE_XOF_DST,
+            TRS_A0_XOF_DST,
+            CLSAG_KEY_IMAGE_BASE_DST,
+            CLSAG_VALUE_GENERATOR_DST,
+        ];
+        for (i, a) in dsts.iter().enumerate() {
+            for b in &dsts[i + 1..] {
+                assert_ne!(hash_to_ristretto255(b"abc", a), hash_to_ristretto255(b"abc", b));
+            }
+        }
+
+        // A DST over 255 bytes is hashed down rather than rejected
+        let long_dst = vec![b'x'; 300];
+        assert_eq!(expand_message_xmd(b"abc", &long_dst, 32).len(), 32);
+        assert_eq!(expand_message_xof(b"abc", &long_dst, 32).len(), 32);
+    }
+}